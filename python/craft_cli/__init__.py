@@ -30,7 +30,13 @@ except ImportError:  # pragma: no cover
 # names included here only to be exposed as external API; the particular order of imports
 # is to break cyclic dependencies
 from .messages import EmitterMode, emit  # isort:skip
-from .dispatcher import BaseCommand, CommandGroup, Dispatcher, GlobalArgument
+from .dispatcher import (
+    BaseCommand,
+    CommandGroup,
+    Dispatcher,
+    GlobalArgument,
+    get_requested_mode,
+)
 from .errors import ArgumentParsingError, CraftError, CraftCommandError, ProvideHelpException
 from .helptexts import HIDDEN  # noqa: F401
 
@@ -45,4 +51,5 @@ __all__ = [
     "GlobalArgument",
     "ProvideHelpException",
     "emit",
+    "get_requested_mode",
 ]
@@ -1,8 +1,9 @@
-from . import utils
+from . import testing, utils
 
 def sum_as_string(a: int, b: int) -> str: ...
 
 __all__ = [
     "sum_as_string",
+    "testing",
     "utils",
 ]
@@ -0,0 +1,27 @@
+"""Helpers for testing applications built on Craft CLI."""
+
+from craft_cli._rs.emitter import Emitter
+
+class Screen:
+    """A virtual terminal of a fixed size, for snapshot tests of what is drawn.
+
+    Pass it as an emitter's ``screen`` argument to draw on it instead of the terminal.
+    """
+
+    def __init__(self, rows: int, cols: int) -> None: ...
+    def contents(self) -> str:
+        """Get the text on the screen, one line per row with trailing blanks trimmed."""
+
+def record(emitter: Emitter) -> None:
+    """Keep every message the emitter emits from now on, hidden or not."""
+
+def recorded(emitter: Emitter) -> list[tuple[str, str]]:
+    """Get the type and text of every message recorded so far, in order."""
+
+def assert_messages(emitter: Emitter, expected: list[tuple[str, str]]) -> None:
+    """Check that messages matching ``expected`` were recorded, in that order.
+
+    Each expected message is a pair of a type, such as ``"progress"`` or ``"warning"``,
+    and a regular expression matched against the start of the text, as with
+    ``re.match``. Other messages may come before, after and in between them.
+    """
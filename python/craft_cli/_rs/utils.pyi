@@ -2,3 +2,27 @@
 
 def humanize_list(values: list[str], conjunction: str = "and") -> str:
     """Bleh."""
+
+def find_similar(word: str, candidates: list[str], cutoff: float = 0.6) -> list[str]:
+    """Find the candidates similar enough to a word to suggest in its place, best first.
+
+    Similarity runs from 0 for nothing in common to 1 for identical, and candidates
+    below ``cutoff`` are left out.
+    """
+
+def humanize_os_error(error: OSError) -> str:
+    """Explain an ``OSError`` in one line, with the path involved and what to do about it."""
+
+def render_markdown(text: str, styled: bool | None = None) -> str:
+    """Render a constrained subset of Markdown into styled terminal text.
+
+    Supports headings, bold spans, code spans and bullet lists. Styling is applied
+    if ``styled`` is set, and by default only if the terminal supports it.
+    """
+
+def hyperlink(url: str, text: str | None = None) -> str:
+    """Format a hyperlink for stderr.
+
+    If the terminal can't render OSC 8 hyperlinks, the plain text is returned instead.
+    The text defaults to the URL itself.
+    """
@@ -1,8 +1,10 @@
-"""Bash completion script generation module."""
+"""Shell completion script generation module."""
 
-from .completion import complete
+from .completion import Shell, complete, generate
 
 
 __all__ = [
+    "Shell",
     "complete",
+    "generate",
 ]
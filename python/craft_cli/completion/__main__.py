@@ -1,4 +1,4 @@
-"""Bash completion script generation script."""
+"""Shell completion script generation script."""
 
 from .completion import main
 
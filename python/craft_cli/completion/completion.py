@@ -32,11 +32,26 @@ from typing_extensions import Self
 
 import craft_cli
 
-TEMPLATE_PATH = "bash_completion.sh.j2"
-
 DispatcherAndConfig = tuple[craft_cli.Dispatcher, dict[str, Any] | None]
 
 
+class Shell(enum.Enum):
+    """A shell that completion scripts can be generated for.
+
+    The zsh script is the bash one loaded through zsh's ``bashcompinit`` layer, so it
+    completes the same words as in bash, without zsh's descriptions or grouping.
+    """
+
+    bash = "bash_completion.sh.j2"
+    zsh = "zsh_completion.zsh.j2"
+    fish = "fish_completion.fish.j2"
+
+    @property
+    def template(self) -> str:
+        """The name of the template used to render this shell's completion script."""
+        return self.value
+
+
 class Option(enum.Flag):
     """An option flag for compgen."""
 
@@ -129,6 +144,8 @@ class Arg(ABC):
 
     flags: list[str]
     completion_command: str | CompGen
+    help_msg: str = ""
+    takes_value: bool = False
 
     @classmethod
     def from_global_argument(cls, argument: craft_cli.GlobalArgument) -> Self:
@@ -140,7 +157,12 @@ class Arg(ABC):
 
         completion_command = CompGen(words=argument.choices) if argument.choices else CompGen()
 
-        return cls(flags=flags, completion_command=completion_command)
+        return cls(
+            flags=flags,
+            completion_command=completion_command,
+            help_msg=argument.help_message,
+            takes_value=argument.type == "option",
+        )
 
     @classmethod
     def from_action(cls, action: argparse.Action) -> Self:
@@ -148,7 +170,10 @@ class Arg(ABC):
         completion_command = CompGen(words=list(action.choices)) if action.choices else CompGen()
 
         return cls(
-            flags=cast("list[str]", action.option_strings), completion_command=completion_command
+            flags=cast("list[str]", action.option_strings),
+            completion_command=completion_command,
+            help_msg=action.help or "",
+            takes_value=action.const is None and action.nargs != 0,
         )
 
     @property
@@ -185,6 +210,7 @@ class CommandMapping:
     options: list[OptionArgument]
     args: list[Argument]
     params: str | CompGen
+    help_msg: str = ""
 
     @property
     def all_args(self) -> str:
@@ -192,15 +218,37 @@ class CommandMapping:
         return " ".join([a.flag_list for a in self.args])
 
 
-def complete(shell_cmd: str, get_app_info: Callable[[], DispatcherAndConfig]) -> str:
-    """Generate a bash completion script based on a craft-cli dispatcher.
+def complete(
+    shell_cmd: str,
+    get_app_info: Callable[[], DispatcherAndConfig],
+    shell: Shell = Shell.bash,
+) -> str:
+    """Generate a completion script based on a craft-cli dispatcher.
 
     :param shell_cmd: The name of the command being completed for
     :param get_app_info: A function that returns a populated craft-cli dispatcher and the config
     needed to create its commands
-    :return: A bash completion script for ``shell_cmd``
+    :param shell: The shell to generate the completion script for
+    :return: A completion script for ``shell_cmd``
     """
     dispatcher, app_config = get_app_info()
+    return generate(shell_cmd, dispatcher, app_config, shell)
+
+
+def generate(
+    shell_cmd: str,
+    dispatcher: craft_cli.Dispatcher,
+    app_config: dict[str, Any] | None,
+    shell: Shell = Shell.bash,
+) -> str:
+    """Generate a completion script from an already-built dispatcher.
+
+    :param shell_cmd: The name of the command being completed for
+    :param dispatcher: A populated craft-cli dispatcher
+    :param app_config: The config needed to create the dispatcher's commands
+    :param shell: The shell to generate the completion script for
+    :return: A completion script for ``shell_cmd``
+    """
     env = jinja2.Environment(
         trim_blocks=True,
         lstrip_blocks=True,
@@ -210,7 +258,8 @@ def complete(shell_cmd: str, get_app_info: Callable[[], DispatcherAndConfig]) ->
         loader=jinja2.FileSystemLoader(Path(__file__).parent),
         autoescape=jinja2.select_autoescape(),
     )
-    template = env.get_template(TEMPLATE_PATH)
+    env.filters["quote"] = shlex.quote
+    template = env.get_template(shell.template)
 
     command_map: dict[str, CommandMapping] = {}
     for name, cmd_cls in dispatcher.commands.items():
@@ -235,7 +284,9 @@ def complete(shell_cmd: str, get_app_info: Callable[[], DispatcherAndConfig]) ->
             param_actions |= Action.file
 
         parameters = CompGen(actions=param_actions, options=Option.bashdefault)
-        command_map[name] = CommandMapping(list(options), list(args), parameters)
+        command_map[name] = CommandMapping(
+            list(options), list(args), parameters, help_msg=cmd_cls.help_msg
+        )
 
     global_opts = [
         OptionArgument.from_global_argument(arg)
@@ -248,6 +299,9 @@ def complete(shell_cmd: str, get_app_info: Callable[[], DispatcherAndConfig]) ->
         commands=command_map,
         global_args=dispatcher.global_arguments,
         global_opts=global_opts,
+        all_global_opts=[
+            OptionArgument.from_global_argument(arg) for arg in dispatcher.global_arguments
+        ],
     )
 
 
@@ -268,10 +322,17 @@ def _validate_app_info(raw_ref: str) -> Callable[[], DispatcherAndConfig]:
 
 
 def main() -> None:
-    """Entry point for bash completion script generation."""
+    """Entry point for completion script generation."""
     parser = argparse.ArgumentParser(
         prog="craft_cli.completion",
-        description="Generate bash completion scripts from your craft-cli dispatcher. Only bash and other Bourne-like shells are supported currently.",
+        description="Generate shell completion scripts from your craft-cli dispatcher.",
+    )
+    parser.add_argument(
+        "--shell",
+        type=lambda name: Shell[name],
+        default=Shell.bash,
+        metavar="SHELL",
+        help=f"The shell to generate completions for, one of: {', '.join(s.name for s in Shell)}. Defaults to bash.",
     )
     parser.add_argument(
         "shell_cmd",
@@ -293,6 +354,6 @@ def main() -> None:
         craft_cli.EmitterMode.QUIET, "craft-cli completion", "Generating completion scripts..."
     )
 
-    print(complete(args.shell_cmd, args.app_info))
+    print(complete(args.shell_cmd, args.app_info, args.shell))
 
     craft_cli.emit.ended_ok()
@@ -20,6 +20,8 @@ from __future__ import annotations
 import argparse
 import dataclasses
 import difflib
+import functools
+import sys
 from collections.abc import Callable, Sequence
 from typing import Any, Literal, NamedTuple, NoReturn, Optional
 
@@ -29,6 +31,36 @@ from craft_cli.errors import ArgumentParsingError, ProvideHelpException
 from craft_cli.helptexts import HelpBuilder, OutputFormat
 
 
+COMPLETION_COMMAND = "completion"
+"""The name of the hidden command that produces shell completion scripts."""
+
+
+class _CompletionCommand(BaseCommand):
+    """The hidden command writing a shell completion script to stdout.
+
+    The script is written as it is, in every mode, so that it can be redirected to a
+    file or sourced straight away.
+    """
+
+    name = COMPLETION_COMMAND
+    help_msg = "Write a shell completion script"
+    overview = "Write the completion script for a shell, bash by default, to stdout."
+    hidden = True
+
+    def __init__(self, config: dict[str, Any] | None, *, script: str) -> None:
+        super().__init__(config)
+        self._script = script
+
+    def fill_parser(self, parser: _CustomArgumentParser) -> None:
+        """Accept the shell, already validated when pre-parsing."""
+        parser.add_argument("shell", nargs="?")
+
+    def run(self, parsed_args: argparse.Namespace) -> None:  # noqa: ARG002 (unused argument)
+        """Write the script."""
+        sys.stdout.write(self._script)
+        sys.stdout.flush()
+
+
 class CommandGroup(NamedTuple):
     """Definition of a command group.
 
@@ -226,6 +258,35 @@ def _get_commands_info(commands_groups: list[CommandGroup]) -> dict[str, type[Ba
     return commands
 
 
+def get_requested_mode(
+    sysargs: Sequence[str], default: EmitterMode = EmitterMode.BRIEF
+) -> EmitterMode:
+    """Get the verbosity requested in the command line, before the Dispatcher parses it.
+
+    This lets the emitter be initialized in the requested mode, so even the first
+    messages honor it. Problems like an unknown level or mutually exclusive options are
+    left for ``Dispatcher.pre_parse_args`` to report, giving ``default`` meanwhile.
+    """
+    requested = []
+    sysargs_it = iter(sysargs)
+    for sysarg in sysargs_it:
+        if sysarg in ("-q", "--quiet"):
+            requested.append("quiet")
+        elif sysarg in ("-v", "--verbose"):
+            requested.append("verbose")
+        elif sysarg == "--verbosity":
+            requested.append(next(sysargs_it, ""))
+        elif sysarg.startswith("--verbosity="):
+            requested.append(sysarg.split("=", 1)[1])
+
+    if len(requested) != 1:
+        return default
+    try:
+        return EmitterMode[requested[0].upper()]
+    except KeyError:
+        return default
+
+
 class Dispatcher:
     """Set up infrastructure and let the needed command run.
 
@@ -250,6 +311,7 @@ class Dispatcher:
         default_command: type[BaseCommand] | None = None,
         docs_base_url: str | None = None,
     ) -> None:
+        self._appname = appname
         self._default_command = default_command
         self._docs_base_url = docs_base_url
         self._help_builder = HelpBuilder(appname, summary, commands_groups, docs_base_url)
@@ -259,7 +321,7 @@ class Dispatcher:
             self.global_arguments.extend(extra_global_args)
 
         self.commands = _get_commands_info(commands_groups)
-        self._command_class: type[BaseCommand] | None = None
+        self._command_class: Callable[[Any], BaseCommand] | None = None
         self._command_args: list[str] | None = None
         self._loaded_command: BaseCommand | None = None
         self._parsed_command_args: argparse.Namespace | None = None
@@ -385,6 +447,25 @@ class Dispatcher:
 
         return self._help_builder.get_command_help(command, command_options, output_format)
 
+    def _get_completion_script(self, parameters: list[str], app_config: Any) -> str:
+        """Produce the shell completion script requested through the hidden command."""
+        # imported here as the completion module needs the whole package loaded
+        from craft_cli.completion import Shell, generate  # noqa: PLC0415
+
+        if len(parameters) > 1:
+            raise self._build_usage_exc("Too many parameters when requesting completion")
+
+        shell_name = parameters[0] if parameters else Shell.bash.name
+        try:
+            shell = Shell[shell_name]
+        except KeyError:
+            choices = humanize_list([f"'{shell.name}'" for shell in Shell])
+            raise self._build_usage_exc(
+                f"Bad shell {shell_name!r}; valid values are {choices}."
+            ) from None
+
+        return generate(self._appname, self, app_config, shell)
+
     def _build_no_command_error(self, missing_command: str) -> str:
         """Build the error help text for missing command, providing options."""
         all_alternatives = [
@@ -510,6 +591,16 @@ class Dispatcher:
             help_text = self._get_requested_help(cmd_args, app_config)
             raise ProvideHelpException(help_text)
 
+        # handle requested completion scripts through the implicit, hidden "completion" command,
+        # unless the application provides its own command with that name; it's run like any
+        # other command, so that the script goes to stdout
+        if command == COMPLETION_COMMAND and command not in self.commands:
+            script = self._get_completion_script(cmd_args, app_config)
+            self._command_args = cmd_args
+            self._command_class = functools.partial(_CompletionCommand, script=script)
+            emit.trace(f"General parsed sysargs: command={command!r} args={cmd_args}")
+            return global_args
+
         self._command_args = cmd_args
         try:
             self._command_class = self.commands[command]
@@ -24,6 +24,8 @@ import textwrap
 from operator import attrgetter
 from typing import TYPE_CHECKING
 
+from craft_cli._rs.utils import render_markdown
+
 if TYPE_CHECKING:
     from craft_cli.dispatcher import BaseCommand, CommandGroup
 
@@ -179,7 +181,8 @@ class HelpBuilder:
         textblocks.append(HEADER.format(appname=self.appname))
 
         # summary
-        textblocks.append("Summary:" + textwrap.indent(self.general_summary, "    "))
+        summary = render_markdown(self.general_summary)
+        textblocks.append("Summary:" + textwrap.indent(summary, "    "))
 
         # column alignment is dictated by longest common commands names and groups names
         max_title_len = 0
@@ -253,7 +256,8 @@ class HelpBuilder:
         textblocks.append(HEADER.format(appname=self.appname))
 
         # summary
-        textblocks.append("Summary:" + textwrap.indent(self.general_summary, "    "))
+        summary = render_markdown(self.general_summary)
+        textblocks.append("Summary:" + textwrap.indent(summary, "    "))
 
         # column alignment is dictated by longest common commands names and groups names
         max_title_len = 0
@@ -325,7 +329,7 @@ class HelpBuilder:
         # Remove reST-style double backticks
         # Match _only_ double backticks, never triples
         overview = re.sub(r"(?<!`)``(?!`)", "", overview)
-        textblocks.append(f"Summary:{overview}")
+        textblocks.append(f"Summary:{render_markdown(overview)}")
 
         # column alignment is dictated by longest options title
         max_title_len = max(len(title) for title, text in options)
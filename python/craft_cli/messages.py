@@ -29,6 +29,7 @@ import getpass
 import logging
 import os
 import pathlib
+import re
 import select
 import sys
 import threading
@@ -49,6 +50,7 @@ except ImportError:
 
 from craft_cli import errors
 from craft_cli._rs.printer import Printer
+from craft_cli._rs.utils import humanize_os_error, hyperlink, render_markdown
 
 if TYPE_CHECKING:
     from types import TracebackType
@@ -65,6 +67,10 @@ _MAX_LOG_FILES = 5
 # the size of bytes chunk that the pipe reader will read at once
 _PIPE_READER_CHUNK_SIZE = 4096
 
+# what ends a line read by the pipe reader; a lone carriage return is how tools redraw
+# their current line
+_PIPE_READER_LINE_END = re.compile(rb"\r\n|\r|\n")
+
 # set to true when running *application* tests so some behaviours change (see
 # craft_cli/pytest_plugin.py )
 TESTMODE = False
@@ -218,6 +224,10 @@ class _PipeReaderThread(threading.Thread):
         # a newline)
         self.remaining_content = b""
 
+        # the last line redrawn with a carriage return, which stays on screen if the
+        # tool then ends the line
+        self.redrawn_line: bytes | None = None
+
         # printer and stream to write the assembled lines
         self.printer = printer
         self.stream = stream
@@ -227,29 +237,46 @@ class _PipeReaderThread(threading.Thread):
         pointer = 0
         data = self.remaining_content + data
         while True:
-            # get the position of next newline (find starts in pointer position)
-            newline_position = data.find(b"\n", pointer)
+            # get the position of next line end (search starts in pointer position)
+            line_end = _PIPE_READER_LINE_END.search(data, pointer)
 
-            # no more newlines, store the rest of data for the next time and break
-            if newline_position == -1:
+            # no more line ends, store the rest of data for the next time and break; a
+            # carriage return at the very end may be the start of a "\r\n", so wait
+            # for what follows it
+            if line_end is None or (line_end.group() == b"\r" and line_end.end() == len(data)):
                 self.remaining_content = data[pointer:]
                 break
 
-            # get the useful line and update pointer for next cycle (plus one, to
-            # skip the new line itself)
-            useful_line = data[pointer:newline_position]
-            pointer = newline_position + 1
-
-            # write the useful line to intended outputs. Decode with errors="replace"
-            # here because we don't know where this line is coming from.
-            unicode_line = useful_line.decode("utf8", errors="replace")
-            # replace tabs with a set number of spaces so that the printer
-            # can correctly count the characters.
-            unicode_line = unicode_line.replace("\t", "  ")
-            text = f":: {unicode_line}"
-            self.printer.show(
-                self.stream.fileno() if self.stream else None, text, **self.printer_flags
-            )
+            # get the useful line and update pointer for next cycle (skipping the
+            # line end itself)
+            useful_line = data[pointer : line_end.start()]
+            pointer = line_end.end()
+
+            # a line ended by a lone carriage return is redrawn by the next one, so it's
+            # shown as ephemeral; empty ones are just the tool clearing its line
+            if line_end.group() == b"\r":
+                if useful_line:
+                    self.redrawn_line = useful_line
+                    self._show(useful_line, ephemeral=True)
+            else:
+                if not useful_line and self.redrawn_line is not None:
+                    useful_line = self.redrawn_line
+                self.redrawn_line = None
+                self._show(useful_line)
+
+    def _show(self, useful_line: bytes, *, ephemeral: bool = False) -> None:
+        """Send a single line to the printer."""
+        # write the useful line to intended outputs. Decode with errors="replace"
+        # here because we don't know where this line is coming from.
+        unicode_line = useful_line.decode("utf8", errors="replace")
+        # replace tabs with a set number of spaces so that the printer
+        # can correctly count the characters.
+        unicode_line = unicode_line.replace("\t", "  ")
+        text = f":: {unicode_line}"
+        flags = self.printer_flags
+        if ephemeral:
+            flags = {**flags, "ephemeral": True, "end_line": False}
+        self.printer.show(self.stream.fileno() if self.stream else None, text, **flags)
 
     def _run_posix(self) -> None:
         """Run the thread, handling pipes in the POSIX way."""
@@ -467,6 +494,8 @@ class Emitter:
         self._log_handler: _Handler = None  # type: ignore[assignment]
         self._streaming_brief = False
         self._docs_base_url: str | None = None
+        self._assume_yes = False
+        self._exit_code = 0
 
     def init(  # noqa: PLR0913 (too many arguments)
         self,
@@ -732,8 +761,16 @@ class Emitter:
         """Finish the messaging system gracefully."""
         self._stop()
 
-    def _report_error(self, error: errors.CraftError) -> None:
-        """Report the different message lines from a CraftError."""
+    @property
+    def suggested_exit_code(self) -> int:
+        """The status for the application to exit with.
+
+        It's the ``retcode`` of the error reported with ``error``, if any, and 0 otherwise.
+        """
+        return self._exit_code
+
+    def _report_error(self, error: errors.CraftError, message: str) -> None:
+        """Report the different message lines from a CraftError, led by ``message``."""
         use_timestamp = True
         exception_stream = sys.stderr.fileno()
         if self._mode in (EmitterMode.QUIET, EmitterMode.BRIEF, EmitterMode.VERBOSE):
@@ -742,7 +779,7 @@ class Emitter:
 
         # The initial message. Print every line individually to correctly clear
         # previous lines, if necessary.
-        for line in str(error).splitlines():
+        for line in message.splitlines():
             self._printer.show(
                 sys.stderr.fileno(), line, use_timestamp=use_timestamp, end_line=True
             )
@@ -768,9 +805,16 @@ class Emitter:
 
         # hints for the user to know more
         if error.resolution:
-            text = f"Recommended resolution: {error.resolution}"
+            # the log gets the plain text, without the terminal styling
+            text = f"Recommended resolution: {render_markdown(error.resolution, styled=False)}"
+            self._printer.show(None, text, use_timestamp=use_timestamp, end_line=True)
+            text = f"Recommended resolution: {render_markdown(error.resolution)}"
             self._printer.show(
-                sys.stderr.fileno(), text, use_timestamp=use_timestamp, end_line=True
+                sys.stderr.fileno(),
+                text,
+                use_timestamp=use_timestamp,
+                end_line=True,
+                avoid_logging=True,
             )
 
         doc_url = None
@@ -780,25 +824,41 @@ class Emitter:
             doc_url = error.docs_url
 
         if doc_url:
-            text = f"For more information, check out: {doc_url}"
+            text = f"For more information, check out: {hyperlink(doc_url)}"
             self._printer.show(
                 sys.stderr.fileno(), text, use_timestamp=use_timestamp, end_line=True
             )
 
         # expose the logfile path only if indicated
         if error.logpath_report:
-            text = f"Full execution log: {str(self._log_filepath)!r}"
+            log_link = hyperlink(self._log_filepath.absolute().as_uri(), str(self._log_filepath))
+            text = f"Full execution log: '{log_link}'"
             self._printer.show(
                 sys.stderr.fileno(), text, use_timestamp=use_timestamp, end_line=True
             )
 
     @_active_guard(ignore_when_stopped=True)
-    def error(self, error: errors.CraftError) -> None:
-        """Handle the system's indicated error and stop machinery."""
+    def error(self, error: errors.CraftError | OSError) -> None:
+        """Handle the system's indicated error and stop machinery.
+
+        An ``OSError``, given directly or as the cause of an error without a message, is
+        explained from its errno and path.
+
+        The error's ``retcode`` is kept as the ``suggested_exit_code``.
+        """
+        if isinstance(error, OSError):
+            cause = error
+            error = errors.CraftError(humanize_os_error(cause))
+            error.__cause__ = cause
+        message = str(error)
+        if not message and isinstance(error.__cause__, OSError):
+            # Explained here only, leaving the application's error as it was
+            message = humanize_os_error(error.__cause__)
+        self._exit_code = error.retcode
         if self._streaming_brief:
             # Clear the message prefix, as this error stands alone
             self._printer.set_terminal_prefix("")
-        self._report_error(error)
+        self._report_error(error, message)
         self._stop()
 
     @_active_guard()
@@ -819,15 +879,41 @@ class Emitter:
         self._printer.set_secrets(secrets)
 
     @_active_guard()
-    def confirm(self, prompt: str, *, default: bool = False) -> bool:
+    def set_assume_yes(self, assume_yes: bool) -> None:  # noqa: FBT001 (boolean positional arg)
+        """Answer every confirmation positively without asking the user.
+
+        This is the hook for an application's ``--yes`` option.
+        """
+        self._assume_yes = assume_yes
+
+    @_active_guard()
+    def confirm(
+        self,
+        prompt: str,
+        *,
+        default: bool = False,
+        non_interactive: Literal["default", "fail"] = "default",
+    ) -> bool:
         """Query user for yes/no answer.
 
-        If stdin is not a tty, the default value is returned.
+        If the emitter was told to assume yes (see ``set_assume_yes``), True is returned
+        without asking.
+        If stdin is not a tty, ``non_interactive`` decides what happens: with "default"
+        the default value is returned, with "fail" a CraftError is raised.
         If user returns an empty answer, the default value is returned.
         :returns: True if answer starts with [yY], False if answer starts with [nN],
             otherwise the default.
         """
+        if self._assume_yes:
+            self.debug(f"Assuming yes to confirmation: {prompt!r}")
+            return True
+
         if not sys.stdin.isatty():
+            if non_interactive == "fail":
+                raise errors.CraftError(
+                    f"Cannot confirm {prompt!r} without a terminal.",
+                    resolution="Run interactively, or pass the option to assume yes.",
+                )
             return default
 
         choices = " [Y/n]: " if default else " [y/N]: "
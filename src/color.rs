@@ -0,0 +1,133 @@
+//! Deciding whether output is styled with color.
+//!
+//! The decision is made once, when an emitter is created, and handed to `console` so
+//! that everything styled through it follows the same answer, including styled
+//! strings built by the application.
+
+use std::ffi::OsString;
+
+use crate::printer::Target;
+
+/// Whether messages on a stream should be styled, following the usual conventions.
+///
+/// In order of precedence:
+///
+/// - `NO_COLOR` set to anything but an empty string turns color off.
+/// - `FORCE_COLOR` or `CLICOLOR_FORCE` turn color on even when the stream isn't a
+///   terminal, for CI systems that render ANSI in their logs. `FORCE_COLOR` set to
+///   `0` or `false` turns color off instead.
+/// - What the application `requested`, if anything.
+/// - `CLICOLOR` set to `0` turns color off.
+/// - On Unix, `TERM` set to `dumb` or not set at all turns color off.
+/// - Otherwise, color is only used on interactive terminals.
+#[must_use]
+pub fn enabled(target: Target, requested: Option<bool>) -> bool {
+    let is_term = match target {
+        Target::Stdout => console::Term::stdout().is_term(),
+        Target::Stderr => console::Term::stderr().is_term(),
+        Target::Null => return false,
+    };
+    decide(|name| std::env::var_os(name), requested, is_term)
+}
+
+/// Make everything styled through `console` follow the decision for its stream.
+pub fn apply(requested: Option<bool>) {
+    console::set_colors_enabled(enabled(Target::Stdout, requested));
+    console::set_colors_enabled_stderr(enabled(Target::Stderr, requested));
+}
+
+/// Decide whether to use color on a stream, given the environment.
+fn decide(var: impl Fn(&str) -> Option<OsString>, requested: Option<bool>, is_term: bool) -> bool {
+    // Empty values count as unset, as with most conventions on environment variables
+    let var = |name| var(name).filter(|value| !value.is_empty());
+    if var("NO_COLOR").is_some() {
+        return false;
+    }
+    if let Some(force) = var("FORCE_COLOR") {
+        return force != "0" && force != "false";
+    }
+    if var("CLICOLOR_FORCE").is_some_and(|value| value != "0") {
+        return true;
+    }
+    if let Some(requested) = requested {
+        return requested;
+    }
+    if var("CLICOLOR").is_some_and(|value| value == "0") {
+        return false;
+    }
+    if cfg!(unix) && var("TERM").is_none_or(|term| term == "dumb") {
+        return false;
+    }
+    is_term
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::env;
+
+    mod decide {
+        use super::*;
+
+        #[test]
+        fn terminal_only() {
+            assert!(decide(env(&[("TERM", "xterm")]), None, true));
+            assert!(!decide(env(&[]), None, false));
+        }
+
+        #[test]
+        fn no_color() {
+            assert!(!decide(env(&[("NO_COLOR", "1")]), None, true));
+            assert!(decide(
+                env(&[("NO_COLOR", ""), ("TERM", "xterm")]),
+                None,
+                true
+            ));
+        }
+
+        #[test]
+        fn forced() {
+            assert!(decide(env(&[("FORCE_COLOR", "1")]), None, false));
+            assert!(decide(env(&[("CLICOLOR_FORCE", "1")]), None, false));
+            assert!(!decide(env(&[("CLICOLOR_FORCE", "0")]), None, false));
+            assert!(!decide(env(&[("FORCE_COLOR", "0")]), None, true));
+            assert!(!decide(env(&[("FORCE_COLOR", "false")]), None, true));
+        }
+
+        #[test]
+        fn no_color_beats_forced() {
+            assert!(!decide(
+                env(&[("NO_COLOR", "1"), ("FORCE_COLOR", "1")]),
+                None,
+                true
+            ));
+        }
+
+        #[test]
+        fn requested() {
+            assert!(decide(env(&[]), Some(true), false));
+            assert!(!decide(env(&[("CLICOLOR", "1")]), Some(false), true));
+            assert!(!decide(env(&[("NO_COLOR", "1")]), Some(true), true));
+        }
+
+        #[test]
+        fn clicolor_off() {
+            assert!(!decide(env(&[("CLICOLOR", "0")]), None, true));
+            assert!(decide(
+                env(&[("CLICOLOR", "1"), ("TERM", "xterm")]),
+                None,
+                true
+            ));
+        }
+
+        #[test]
+        fn dumb_terminal() {
+            assert!(!decide(env(&[("TERM", "dumb")]), None, true));
+            assert!(decide(
+                env(&[("TERM", "dumb"), ("FORCE_COLOR", "1")]),
+                None,
+                true
+            ));
+        }
+    }
+}
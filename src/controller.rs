@@ -0,0 +1,112 @@
+//! A ready-made emitter for applications to share, like the pure-Python craft-cli's
+//! module-level `emit`.
+//!
+//! The controller exists from the start, but the emitter behind it is only created
+//! once the application calls `init`, usually after parsing its command line. Until
+//! then, using it fails with an error saying so, rather than emitting nowhere.
+
+use pyo3::{
+    Bound, Py, PyAny, PyResult, Python, pyclass, pymethods,
+    types::{PyAnyMethods, PyDict},
+};
+
+use crate::emitter::Emitter;
+use crate::error::Error;
+use crate::printer::Verbosity;
+
+/// A stand-in for an emitter created later, passing everything on to it once it is.
+#[pyclass(module = "craft_cli._rs.emitter")]
+#[derive(Default)]
+pub struct EmitterController {
+    /// The emitter, once initialized.
+    emitter: Option<Py<Emitter>>,
+}
+
+#[pymethods]
+impl EmitterController {
+    /// Make a controller with no emitter yet.
+    #[new]
+    fn py_new() -> Self {
+        Self::default()
+    }
+
+    /// Create the emitter, with the arguments of `Emitter.init`.
+    #[pyo3(signature = (mode, appname, greeting, log_filepath = None, docs_base_url = None, **kwargs))]
+    // Matching the signature of the pure-Python craft-cli's `init`
+    #[expect(clippy::too_many_arguments)]
+    fn init(
+        &mut self,
+        py: Python<'_>,
+        mode: Verbosity,
+        appname: &str,
+        greeting: String,
+        log_filepath: Option<String>,
+        docs_base_url: Option<String>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<()> {
+        let emitter = py.get_type::<Emitter>().call_method(
+            "init",
+            (mode, appname, greeting, log_filepath, docs_base_url),
+            kwargs,
+        )?;
+        self.emitter = Some(emitter.downcast_into::<Emitter>()?.unbind());
+        Ok(())
+    }
+
+    /// Whether `init` was called.
+    #[getter]
+    fn initialized(&self) -> bool {
+        self.emitter.is_some()
+    }
+
+    /// Pass everything else on to the emitter.
+    fn __getattr__<'py>(&self, py: Python<'py>, name: &str) -> PyResult<Bound<'py, PyAny>> {
+        match &self.emitter {
+            Some(emitter) => emitter.bind(py).getattr(name),
+            None => Err(Error::Uninitialized.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::{exceptions::PyRuntimeError, types::PyDictMethods};
+
+    use super::*;
+    use crate::printer::OutputFormat;
+    use crate::test_utils::{assert_error_contents, assert_error_type};
+
+    mod controller {
+        use super::*;
+
+        #[test]
+        fn proxies_once_initialized() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("app.log");
+
+            Python::with_gil(|py| {
+                let emit = Py::new(py, EmitterController::default()).unwrap();
+                let emit = emit.bind(py);
+                let err = emit.call_method1("message", ("too early",)).unwrap_err();
+                assert_error_type::<PyRuntimeError>(&err);
+                assert_error_contents(&err, r"init\(\)");
+
+                let kwargs = PyDict::new(py);
+                kwargs
+                    .set_item("log_filepath", path.to_str().unwrap())
+                    .unwrap();
+                kwargs
+                    .set_item("output_format", OutputFormat::Json)
+                    .unwrap();
+                emit.call_method("init", (Verbosity::Quiet, "app", "Hello"), Some(&kwargs))
+                    .unwrap();
+                assert!(emit.borrow().initialized());
+                emit.call_method1("message", ("in time",)).unwrap();
+                emit.call_method0("ended_ok").unwrap();
+            });
+
+            let log = std::fs::read_to_string(path).unwrap();
+            assert!(log.contains("] in time\n"), "{log}");
+        }
+    }
+}
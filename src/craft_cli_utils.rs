@@ -6,7 +6,7 @@ use pyo3::pymodule;
 #[pymodule(submodule)]
 #[pyo3(module = "craft_cli._rs.utils")]
 pub mod utils {
-    use pyo3::{pyfunction, types::PyModule, Bound, PyResult};
+    use pyo3::{pyfunction, types::{PyAnyMethods, PyModule}, Bound, PyAny, PyResult};
 
     use crate::utils::fix_imports;
 
@@ -29,6 +29,73 @@ pub mod utils {
         )
     }
 
+    /// Find the candidates similar enough to a word to suggest in its place, best
+    /// first.
+    ///
+    /// Useful for "did you mean" suggestions on mistyped names. Similarity runs from 0
+    /// for nothing in common to 1 for identical, and candidates below `cutoff` are left
+    /// out.
+    #[pyfunction]
+    #[pyo3(signature = (word, candidates, cutoff = 0.6))]
+    // PyO3 can only extract a list into an owned vector
+    #[expect(clippy::needless_pass_by_value)]
+    fn find_similar(word: &str, candidates: Vec<String>, cutoff: f64) -> Vec<String> {
+        crate::utils::find_similar(word, candidates.iter().map(String::as_str), cutoff)
+            .into_iter()
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Explain an `OSError` in one line, with the path involved and what the user can
+    /// do about it.
+    #[pyfunction]
+    fn humanize_os_error(error: &Bound<'_, PyAny>) -> PyResult<String> {
+        let errno: Option<i32> = error.getattr("errno")?.extract()?;
+        let strerror: Option<String> = error.getattr("strerror")?.extract()?;
+        let filename = error.getattr("filename")?;
+        let path = if filename.is_none() {
+            None
+        } else {
+            Some(filename.str()?.to_string())
+        };
+
+        let kind = errno.map_or(std::io::ErrorKind::Other, |errno| {
+            std::io::Error::from_raw_os_error(errno).kind()
+        });
+        let detail = match strerror {
+            Some(strerror) => strerror,
+            None => error.str()?.to_string(),
+        };
+        Ok(crate::utils::humanize_io_error(
+            kind,
+            &detail,
+            path.as_deref(),
+        ))
+    }
+
+    /// Render a constrained subset of Markdown into styled terminal text.
+    ///
+    /// Supports headings, bold spans, code spans and bullet lists. Styling is
+    /// applied if `styled` is set, and by default only if the terminal supports it.
+    #[pyfunction]
+    #[pyo3(signature = (text, styled = None))]
+    fn render_markdown(text: &str, styled: Option<bool>) -> String {
+        match styled {
+            Some(styled) => crate::markdown::render_with(text, styled),
+            None => crate::markdown::render(text),
+        }
+    }
+
+    /// Format a hyperlink for stderr.
+    ///
+    /// If the terminal can't render OSC 8 hyperlinks, the plain text is returned
+    /// instead. The text defaults to the URL itself.
+    #[pyfunction]
+    #[pyo3(signature = (url, text = None))]
+    fn hyperlink(url: &str, text: Option<&str>) -> String {
+        crate::printer::hyperlink(url, text.unwrap_or(url), crate::printer::Target::Stderr)
+    }
+
     /// Fix syspath for easier importing in Python.
     #[pymodule_init]
     fn init(m: &Bound<'_, PyModule>) -> PyResult<()> {
@@ -0,0 +1,110 @@
+//! Diagnostics for debugging the printer itself.
+//!
+//! The printer can't report on itself through the terminal it's drawing on, nor
+//! through the emitter that feeds it. Instead, when `CRAFT_CLI_DIAGNOSTICS` names a
+//! file, its most recent notes are kept in memory and dumped to that file if the
+//! printer fails or panics. Otherwise, notes cost nothing.
+
+use std::{
+    collections::VecDeque,
+    fs,
+    io::{self, Write as _},
+    path::PathBuf,
+    sync::{LazyLock, Mutex, PoisonError},
+};
+
+/// How many of the most recent notes are kept.
+const CAPACITY: usize = 256;
+
+/// The diagnostics kept for this process, if enabled.
+static DIAGNOSTICS: LazyLock<Option<Diagnostics>> = LazyLock::new(|| {
+    std::env::var_os("CRAFT_CLI_DIAGNOSTICS")
+        .filter(|path| !path.is_empty())
+        .map(|path| Diagnostics::new(path.into()))
+});
+
+/// A ring buffer of notes, and the file to dump them to.
+struct Diagnostics {
+    /// The file the notes are dumped to.
+    path: PathBuf,
+
+    /// The most recent notes, oldest first.
+    notes: Mutex<VecDeque<String>>,
+}
+
+impl Diagnostics {
+    /// Keep notes to dump to `path`.
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            notes: Mutex::new(VecDeque::with_capacity(CAPACITY)),
+        }
+    }
+
+    /// Keep a note, forgetting the oldest if full.
+    fn note(&self, text: &str) {
+        // A panic while noting leaves nothing worse than a missing note behind
+        let mut notes = self.notes.lock().unwrap_or_else(PoisonError::into_inner);
+        if notes.len() == CAPACITY {
+            notes.pop_front();
+        }
+        notes.push_back(format!("{} {text}", jiff::Timestamp::now()));
+    }
+
+    /// Append the notes to the file, followed by why they were dumped.
+    fn dump(&self, reason: &str) -> io::Result<()> {
+        let notes = self.notes.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        for note in notes.iter() {
+            writeln!(file, "{note}")?;
+        }
+        writeln!(file, "{} Printer failed: {reason}", jiff::Timestamp::now())
+    }
+}
+
+/// Keep a note on what the printer is doing, if diagnostics are enabled.
+///
+/// The text is only built when it's kept.
+pub fn note(text: impl FnOnce() -> String) {
+    if let Some(diagnostics) = &*DIAGNOSTICS {
+        diagnostics.note(&text());
+    }
+}
+
+/// Dump the notes kept so far, as the printer failed for `reason`.
+pub fn dump(reason: &str) {
+    if let Some(diagnostics) = &*DIAGNOSTICS {
+        // The printer's failure is reported anyway, which matters more
+        _ = diagnostics.dump(reason);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod diagnostics {
+        use super::*;
+
+        #[test]
+        fn ring_buffer_dumped() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("diagnostics.log");
+            let diagnostics = Diagnostics::new(path.clone());
+
+            for i in 0..=CAPACITY {
+                diagnostics.note(&format!("note {i}"));
+            }
+            diagnostics.dump("terminal went away").unwrap();
+
+            let dumped = fs::read_to_string(&path).unwrap();
+            let lines: Vec<&str> = dumped.lines().collect();
+            assert_eq!(lines.len(), CAPACITY + 1);
+            assert!(lines[0].ends_with(" note 1"));
+            assert!(lines[CAPACITY].ends_with(" Printer failed: terminal went away"));
+        }
+    }
+}
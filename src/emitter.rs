@@ -3,26 +3,211 @@
 use std::{
     borrow::Cow,
     fs::{self, File},
-    io::Write as _,
+    io::{self, BufRead, BufWriter, Write as _},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, MutexGuard, PoisonError, RwLock,
+        atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant, SystemTime},
 };
 
-use pyo3::{Bound, PyResult, Python, pyclass, pymethods, pymodule, types::PyType};
+#[cfg(feature = "python")]
+use pyo3::{
+    Bound, FromPyObject, Py, PyAny, PyClassInitializer, PyResult, Python, pyclass, pymethods,
+    pymodule,
+    types::{PyAnyMethods, PyBytes, PyBytesMethods, PyDict, PyDictMethods, PyType},
+};
+
+use crate::error::{Error, Result};
+#[cfg(unix)]
+use crate::journal::Journal;
+use crate::log_bridge::Sink;
+#[cfg(feature = "python")]
+use crate::observer::Observers;
+use crate::printer::{
+    BarUpdate, Fields, Message, MessageType, OutputFormat, Printer, SpinnerHint, Stats, Target,
+    Verbosity, hyperlink,
+};
+#[cfg(all(unix, feature = "python"))]
+use crate::remote::Listener;
+#[cfg(feature = "python")]
+use crate::screen::Screen;
+#[cfg(unix)]
+use crate::sd_notify::Notifier;
+use crate::secrets::Secrets;
+use crate::settings::{Attention, Ci, ELAPSED_PLACEHOLDER, Exclusive, Overflow, Settings};
+#[cfg(feature = "python")]
+use crate::settings::{DEFAULT_FINISH_MESSAGE, DEFAULT_MAX_QUEUED_BYTES};
+use crate::stream::{Reader, Stream};
+#[cfg(feature = "python")]
+use crate::theme::Theme;
+#[cfg(feature = "python")]
+use crate::timestamp::Clock;
+use crate::timestamp::Timestamps;
+
+/// Whether an emitter controls the terminal, so that others can be kept from fighting
+/// over it.
+static TERMINAL_CLAIMED: AtomicBool = AtomicBool::new(false);
+
+/// The emitter in control of the terminal, if it was created from Python to be
+/// given back to callers creating another.
+#[cfg(feature = "python")]
+static PY_ACTIVE: Mutex<Option<Py<Emitter>>> = Mutex::new(None);
+
+/// An emitter's control of the terminal, given up when dropped.
+struct TerminalClaim;
+
+impl TerminalClaim {
+    /// Take control of the terminal for an emitter with these settings, if it draws
+    /// on it at all.
+    ///
+    /// # Errors
+    ///
+    /// Fails if another emitter is in control and the settings make that an error.
+    fn take(settings: &Settings) -> Result<Option<Self>> {
+        if !matches!(settings.format(), OutputFormat::Human) || settings.screen().is_some() {
+            return Ok(None);
+        }
+        if TERMINAL_CLAIMED.swap(true, Ordering::AcqRel) {
+            return match settings.exclusive() {
+                Exclusive::Off => Ok(None),
+                Exclusive::Error | Exclusive::Reuse => Err(Error::EmitterActive),
+            };
+        }
+        Ok(Some(Self))
+    }
+}
+
+impl Drop for TerminalClaim {
+    fn drop(&mut self) {
+        #[cfg(feature = "python")]
+        drop(
+            PY_ACTIVE
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .take(),
+        );
+        TERMINAL_CLAIMED.store(false, Ordering::Release);
+    }
+}
+
+/// A section begun on an emitter and not yet ended.
+struct OpenSection {
+    /// The title of the section.
+    title: String,
+
+    /// When the section was begun.
+    begun: Instant,
+
+    /// Whether the section's details are hidden in brief mode.
+    collapsed: bool,
+
+    /// The name the section was marked with for the continuous integration system, if
+    /// it was, to mark its end with too.
+    marker: Option<String>,
+}
+
+/// Name a section for GitLab, which only allows some characters in the names of
+/// sections, and needs them unique within a job.
+fn gitlab_section_name(title: &str, index: usize) -> String {
+    let name: String = title
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-') {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{name}_{index}")
+}
+
+/// What the messages emitted are adjusted with, set for a block of code.
+#[derive(Clone, Default)]
+struct Context {
+    /// A stream every message is forced to, regardless of its type and the verbosity.
+    stream: Option<Target>,
+
+    /// The prefixes applied to every message, rendered as `[outer] [inner] `.
+    prefix: String,
+
+    /// The structured fields attached to every message.
+    fields: Fields,
+}
 
-use crate::printer::{Message, MessageType, Printer, Target, Verbosity};
+/// The context of messages emitted outside any prefix, forced stream or fields.
+const ROOT: Context = Context {
+    stream: None,
+    prefix: String::new(),
+    fields: Fields::new(),
+};
+
+impl Context {
+    /// This context, with messages forced to `stream` if given.
+    fn with_stream(&self, stream: Option<Target>) -> Self {
+        Self {
+            stream: stream.or(self.stream),
+            ..self.clone()
+        }
+    }
+
+    /// This context, with `[prefix]` nested inside the current prefixes.
+    fn with_prefix(&self, prefix: &str) -> Self {
+        Self {
+            prefix: format!("{}[{prefix}] ", self.prefix),
+            ..self.clone()
+        }
+    }
+
+    /// This context, with `fields` replacing the current ones of the same key.
+    fn with_fields(&self, fields: Fields) -> Self {
+        let mut context = self.clone();
+        context.fields.extend(fields);
+        context
+    }
+
+    /// The context of a Python method, with the stream and fields given to it.
+    #[cfg(feature = "python")]
+    fn py(stream: Option<Target>, fields: Option<PyFields>) -> Self {
+        ROOT.with_stream(stream)
+            .with_fields(fields.unwrap_or_default().0)
+    }
+
+    /// Prefix text with the prefixes applied, if any.
+    fn prefixed<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.prefix.is_empty() {
+            Cow::Borrowed(text)
+        } else {
+            Cow::Owned(format!("{}{text}", self.prefix))
+        }
+    }
+}
 
 /// Emitter
-#[pyclass]
-struct Emitter {
+///
+/// The emitter can be used from many threads at once through a shared reference.
+/// Each message is logged and queued whole, and the messages of any one thread are
+/// logged and shown in the order it emitted them. Changes such as to the verbosity,
+/// sections and scopes apply to every thread from then on, and messages emitted once
+/// the emitter has finished are left out.
+#[cfg_attr(feature = "python", pyclass(frozen))]
+pub struct Emitter {
     /// Internal printer instance for sending messages.
     ///
     /// Executes I/O operations in a separate thread to make all logging non-blocking.
     printer: Printer,
 
     /// A handle to the desired log file.
-    log_handle: File,
+    ///
+    /// Lines are buffered, and flushed on problems and when the emitter finishes.
+    ///
+    /// It's shared so that it can be written to when a signal arrives.
+    log_handle: Arc<Mutex<BufWriter<File>>>,
 
-    /// The original filepath of the log file.
-    log_filepath: String,
+    /// The filepath of the log file, moved by `relocate_log`.
+    log_filepath: RwLock<String>,
 
     // Used by `report_error` on the Python side, which was left in Python due to
     // the retrieved errors all still being in Python.
@@ -31,50 +216,187 @@ struct Emitter {
     docs_base_url: String,
 
     /// The verbosity mode.
-    verbosity: Verbosity,
+    verbosity: RwLock<Verbosity>,
 
     /// The greeting the emitter was started with.
     greeting: String,
+
+    /// The name of the running application, as invoked.
+    appname: String,
+
+    /// When the emitter was created.
+    started: Instant,
+
+    /// Python callbacks observing every message emitted.
+    #[cfg(feature = "python")]
+    observers: Observers,
+
+    /// Values to mask in all terminal and log output.
+    secrets: RwLock<Secrets>,
+
+    /// The progress scopes currently open, innermost last, with when they were opened.
+    scopes: Mutex<Vec<(String, Instant)>>,
+
+    /// The sections currently open, innermost last.
+    sections: Mutex<Vec<OpenSection>>,
+
+    /// How many sections were begun so far, to name them uniquely.
+    sections_begun: AtomicUsize,
+
+    /// The settings the emitter was created with, after applying the environment.
+    settings: Settings,
+
+    /// How timestamps are read and formatted.
+    timestamps: Timestamps,
+
+    /// The system journal log lines are mirrored to, if enabled.
+    #[cfg(unix)]
+    journal: Option<Journal>,
+
+    /// The service manager progress is reported to, if running as a service.
+    #[cfg(unix)]
+    notifier: Option<Notifier>,
+
+    /// The threads reading the streams opened, joined when finishing.
+    readers: Mutex<Vec<Reader>>,
+
+    /// Whether senders to the printer are still handed out, to streams and the log
+    /// bridge, until finishing begins.
+    ///
+    /// It's locked while handing one out, so that none is left holding the printer
+    /// open when it's stopped.
+    open: Mutex<bool>,
+
+    /// Every message emitted since recording started, if it did.
+    recorded: Mutex<Option<Vec<Message>>>,
+
+    /// The warnings and errors emitted, if they're summed up when finishing.
+    problems: Mutex<Vec<(MessageType, String)>>,
+
+    /// The emitter's control of the terminal, if it took it, until finishing.
+    claim: Mutex<Option<TerminalClaim>>,
+
+    /// The status the application should exit with, set when reporting an error.
+    exit_code: AtomicI32,
 }
 
-#[pymethods]
 impl Emitter {
-    /// Construct a new `Emitter` from Python.
-    #[new]
-    fn new(
-        py: Python<'_>,
+    /// Construct a new `Emitter`, taking control of the terminal.
+    ///
+    /// The `settings` are resolved against the environment, which takes precedence
+    /// over them.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the environment or the settings hold invalid values, or if the log file
+    /// can't be opened. The log file's directory is created if missing. Also fails if
+    /// another emitter is in control of the terminal and the settings make it
+    /// exclusive.
+    pub fn new(
         log_filepath: String,
         verbosity: Verbosity,
         docs_base_url: &str,
         greeting: String,
-    ) -> PyResult<Self> {
-        let mut printer = Printer::new();
-
-        // Spawn the printer thread without using the GIL at all
-        // This is necessary to avoid deadlocks when using OnceCell, see the link below
-        // for more information.
-        // https://pyo3.rs/v0.25.1/faq.html#im-experiencing-deadlocks-using-pyo3-with-stdsynconcelock-stdsynclazylock-lazy_static-and-once_cell
-        py.allow_threads(|| printer.start(verbosity));
+        settings: Settings,
+    ) -> Result<Self> {
+        let started = Instant::now();
+        let settings = settings.resolve()?;
+        // Taken before starting the printer, which would hide the cursor right away
+        let claim = TerminalClaim::take(&settings)?;
+        let mut timestamps = Timestamps::default();
+        timestamps.set_clock(settings.clock());
+        if let Some(format) = settings.timestamp_format() {
+            timestamps.set_format(format.to_string())?;
+        }
+        if let Some(timestamp) = settings.deterministic() {
+            timestamps.set_fixed(timestamp.to_string());
+        }
 
+        if let Some(dir) = Path::new(&log_filepath).parent()
+            && !dir.as_os_str().is_empty()
+        {
+            fs::create_dir_all(dir)?;
+        }
         let log_handle = fs::OpenOptions::new()
             .write(true)
             .truncate(true)
             .create(true)
-            .open(&log_filepath)?;
+            .open(&log_filepath)
+            .map(|file| Arc::new(Mutex::new(BufWriter::new(file))))?;
+
+        crate::color::apply(settings.colors());
+        let mut printer = Printer::new();
+        let (heartbeat_log, heartbeat_timestamps) = (Arc::clone(&log_handle), timestamps.clone());
+        printer.set_heartbeat(move |text| {
+            let mut log = lock_log(&heartbeat_log);
+            // Flushed straight away, as the application may be killed while waiting
+            _ = writeln!(log, "{} [HEARTBEAT] {text}", heartbeat_timestamps.now())
+                .and_then(|()| log.flush());
+        });
+        printer.start(verbosity, settings.clone());
+
+        let appname = settings.appname().map_or_else(
+            || Self::appname_from_argv0(&std::env::args().next().unwrap_or_default()),
+            ToString::to_string,
+        );
+        #[cfg(unix)]
+        let journal = settings
+            .journal()
+            .then(|| Journal::connect(appname.clone()));
 
-        Ok(Self {
+        #[cfg_attr(not(unix), expect(unused_mut))]
+        let mut emitter = Self {
             printer,
             log_handle,
-            log_filepath,
+            log_filepath: RwLock::new(log_filepath),
             docs_base_url: docs_base_url.trim_end_matches('/').to_string(),
-            verbosity,
+            verbosity: RwLock::new(verbosity),
             greeting,
-        })
+            appname,
+            started,
+            #[cfg(feature = "python")]
+            observers: Observers::default(),
+            secrets: RwLock::default(),
+            scopes: Mutex::default(),
+            sections: Mutex::default(),
+            sections_begun: AtomicUsize::new(0),
+            settings,
+            timestamps,
+            #[cfg(unix)]
+            journal: None,
+            #[cfg(unix)]
+            notifier: Notifier::from_env(),
+            readers: Mutex::default(),
+            open: Mutex::new(true),
+            recorded: Mutex::new(None),
+            problems: Mutex::new(Vec::new()),
+            claim: Mutex::new(claim),
+            exit_code: AtomicI32::new(0),
+        };
+        emitter.write_header()?;
+        #[cfg(unix)]
+        match journal {
+            Some(Ok(journal)) => emitter.journal = Some(journal),
+            Some(Err(e)) => {
+                emitter.write_log("LOG", &format!("not mirrored to the journal: {e}"))?;
+            }
+            None => {}
+        }
+        #[cfg(unix)]
+        if let Some(notifier) = &emitter.notifier {
+            notifier.ready();
+        }
+        emitter.attach_log_bridge();
+        Ok(emitter)
     }
 
     /// Create a log filepath from the app name as an easy default.
-    #[classmethod]
-    fn log_filepath_from_name(_cls: &Bound<'_, PyType>, app_name: String) -> String {
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no data directory and the current directory doesn't exist.
+    #[must_use]
+    pub fn log_filepath_from_name(app_name: &str) -> String {
         let dirs = xdg::BaseDirectories::with_prefix(app_name);
         let mut p = dirs
             .get_data_home()
@@ -87,24 +409,98 @@ impl Emitter {
     }
 
     /// Get the current verbosity mode of the emitter.
-    fn get_verbosity(&self) -> Verbosity {
-        self.verbosity
+    #[must_use]
+    pub fn verbosity(&self) -> Verbosity {
+        *self
+            .verbosity
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Keep every message emitted from now on, hidden or not, for tests to check with
+    /// `recorded`.
+    pub fn record(&self) {
+        self.lock_recorded().get_or_insert_with(Vec::new);
+    }
+
+    /// The messages emitted since `record` was called, as sent to the printer.
+    #[must_use]
+    pub fn recorded(&self) -> Vec<Message> {
+        self.lock_recorded().clone().unwrap_or_default()
+    }
+
+    /// The path of the log file being written to.
+    #[must_use]
+    pub fn log_filepath(&self) -> PathBuf {
+        PathBuf::from(&*self.lock_log_filepath())
+    }
+
+    /// Move the log file to `new_path`, and keep appending to it there.
+    ///
+    /// This is for when where the log belongs is only known partway through a run,
+    /// such as an artifact directory. The file is renamed in a single step when both
+    /// paths are on the same filesystem, and copied over otherwise. The new path's
+    /// directory is created if missing.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the log file can't be moved, in which case it's left where it was.
+    pub fn relocate_log(&self, new_path: &Path) -> Result<()> {
+        if let Some(dir) = new_path.parent()
+            && !dir.as_os_str().is_empty()
+        {
+            fs::create_dir_all(dir)?;
+        }
+
+        // Hold the log and its path for the whole move, so no line lands in between
+        let mut log_filepath = self
+            .log_filepath
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut log = lock_log(&self.log_handle);
+        log.flush()?;
+        match fs::rename(&*log_filepath, new_path) {
+            // The open file moved along with its name
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+                fs::copy(&*log_filepath, new_path)?;
+                let file = fs::OpenOptions::new().append(true).open(new_path)?;
+                *log = BufWriter::new(file);
+                // The copy is complete, so a leftover original only wastes space
+                _ = fs::remove_file(&*log_filepath);
+            }
+            Err(e) => return Err(e.into()),
+        }
+        drop(log);
+
+        let old_path =
+            std::mem::replace(&mut *log_filepath, new_path.to_string_lossy().into_owned());
+        drop(log_filepath);
+        self.write_log("LOG", &format!("moved here from {old_path:?}"))
     }
 
     /// Set the verbosity of the emitter.
-    fn set_verbosity(&mut self, new: Verbosity) {
-        self.verbosity = new;
+    pub fn set_verbosity(&self, new: Verbosity) {
+        *self
+            .verbosity
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = new;
+        self.printer.set_mode(new);
+        self.attach_log_bridge();
 
         if let Verbosity::Verbose | Verbosity::Debug | Verbosity::Trace = new {
             let messages = [
                 self.greeting.clone(),
-                format!("Logging execution to {}", self.log_filepath),
+                format!("Logging execution to {}", self.lock_log_filepath()),
             ];
             for message in messages {
-                self.printer.send(Message {
-                    text: message,
+                self.send(Message {
+                    text: message.into(),
                     model: MessageType::Info(),
                     target: Target::Stderr,
+                    spinner: SpinnerHint::Auto,
+                    fields: Fields::new(),
+                    urgent: false,
                 });
             }
         }
@@ -114,24 +510,35 @@ impl Emitter {
     ///
     /// Useful for providing more information to the user that isn't particularly
     /// helpful for "regular use"
-    fn verbose(&mut self, text: &str) -> PyResult<()> {
-        let timestamped = Self::apply_timestamp(text);
-        self.log(&timestamped)?;
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn verbose(&self, text: &str) -> Result<()> {
+        self.verbose_in(&ROOT, text)
+    }
 
-        let (maybe_timestamped, target) = match self.verbosity {
+    /// Verbose information, adjusted by `context`.
+    fn verbose_in(&self, context: &Context, text: &str) -> Result<()> {
+        let prefixed = context.prefixed(text);
+        let text = prefixed.as_ref();
+        let timestamped = self.apply_timestamp(text);
+        let (maybe_timestamped, target) = match self.verbosity() {
             Verbosity::Brief | Verbosity::Quiet => (text, Target::Null),
-            Verbosity::Verbose => (text, Target::Stderr),
-            _ => (timestamped.as_ref(), Target::Stderr),
+            _ if self.timestamped() => (timestamped.as_ref(), Target::Stderr),
+            _ => (text, Target::Stderr),
         };
 
         let message = Message {
-            text: maybe_timestamped.to_string(),
+            text: maybe_timestamped.into(),
             target,
             model: MessageType::Debug(),
+            spinner: SpinnerHint::Auto,
+            fields: Fields::new(),
+            urgent: false,
         };
 
-        self.printer.send(message);
-        Ok(())
+        self.emit(context, text, message)
     }
 
     /// Debug information.
@@ -139,23 +546,34 @@ impl Emitter {
     /// Use to record anything that the user may not want to normally see, but
     /// would be useful for the app developers to understand why things may be
     /// failing.
-    fn debug(&mut self, text: &str) -> PyResult<()> {
-        let timestamped = Self::apply_timestamp(text);
-        self.log(&timestamped)?;
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn debug(&self, text: &str) -> Result<()> {
+        self.debug_in(&ROOT, text)
+    }
 
-        let target = match self.verbosity {
+    /// Debug information, adjusted by `context`.
+    fn debug_in(&self, context: &Context, text: &str) -> Result<()> {
+        let prefixed = context.prefixed(text);
+        let text = prefixed.as_ref();
+        let timestamped = self.apply_timestamp(text);
+        let target = match self.verbosity() {
             Verbosity::Brief | Verbosity::Quiet | Verbosity::Verbose => Target::Null,
             _ => Target::Stderr,
         };
 
         let message = Message {
-            text: timestamped.to_string(),
+            text: timestamped.as_ref().into(),
             target,
             model: MessageType::Debug(),
+            spinner: SpinnerHint::Auto,
+            fields: Fields::new(),
+            urgent: false,
         };
 
-        self.printer.send(message);
-        Ok(())
+        self.emit(context, text, message)
     }
 
     /// Trace information.
@@ -163,156 +581,3607 @@ impl Emitter {
     /// Use to expose system-generated information which in general would be
     /// overwhelming for debugging purposes but sometimes needed for more
     /// in-depth analysis.
-    fn trace(&mut self, text: &str) -> PyResult<()> {
-        let timestamped = Self::apply_timestamp(text);
-        self.log(&timestamped)?;
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn trace(&self, text: &str) -> Result<()> {
+        self.trace_in(&ROOT, text)
+    }
 
-        let target = match self.verbosity {
+    /// Trace information, adjusted by `context`.
+    fn trace_in(&self, context: &Context, text: &str) -> Result<()> {
+        let prefixed = context.prefixed(text);
+        let text = prefixed.as_ref();
+        let timestamped = self.apply_timestamp(text);
+        let target = match self.verbosity() {
             Verbosity::Trace => Target::Stderr,
             _ => Target::Null,
         };
 
         let message = Message {
-            text: timestamped.to_string(),
+            text: timestamped.as_ref().into(),
             target,
             model: MessageType::Trace(),
+            spinner: SpinnerHint::Auto,
+            fields: Fields::new(),
+            urgent: false,
         };
 
-        self.printer.send(message);
-        Ok(())
+        self.emit(context, text, message)
     }
 
     /// Progress information.
     ///
     /// This is normally used to present several related messages relaying how
     /// a task is going. If a progress message is important enough that it
-    /// shouldn't be overwritten by the next ones, use "permanent=True".
+    /// shouldn't be overwritten by the next ones, use `permanent`.
     ///
     /// These messages will be truncated to the terminal's width and overwritten
     /// by the next line (unless in verbose or trace mode, or set to permanent).
-    fn progress(&mut self, text: &str, mut permanent: Option<bool>) -> PyResult<()> {
-        let timestamped = Self::apply_timestamp(text);
-        self.log(&timestamped)?;
+    ///
+    /// The `spinner` hint overrides when a spinner is shown while waiting for the
+    /// next message.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn progress(
+        &self,
+        text: &str,
+        permanent: Option<bool>,
+        spinner: SpinnerHint,
+    ) -> Result<()> {
+        self.progress_in(&ROOT, text, permanent, spinner)
+    }
+
+    /// Progress information, adjusted by `context`.
+    fn progress_in(
+        &self,
+        context: &Context,
+        text: &str,
+        permanent: Option<bool>,
+        spinner: SpinnerHint,
+    ) -> Result<()> {
+        let (text, message) = self.progress_message(context, text, permanent, spinner);
+        self.emit(context, &text, message)
+    }
+
+    /// Change the text of the progress message a spinner is shown for, in place,
+    /// as for a long step moving on to another phase.
+    ///
+    /// Without a spinner, this is the same as `progress`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn progress_update(&self, text: &str) -> Result<()> {
+        self.progress_update_in(&ROOT, text)
+    }
+
+    /// An update of the progress message spun on, adjusted by `context`.
+    fn progress_update_in(&self, context: &Context, text: &str) -> Result<()> {
+        let (text, message) = self.progress_message(context, text, None, SpinnerHint::Auto);
+        let message = self.logged(context, &text, message)?;
+        self.printer
+            .update_progress(self.prepare(context.stream, message));
+        Ok(())
+    }
 
-        let (maybe_timestamped, target) = match self.verbosity {
+    /// Build a progress message, adjusted by `context`, along with its text for the
+    /// log.
+    fn progress_message(
+        &self,
+        context: &Context,
+        text: &str,
+        mut permanent: Option<bool>,
+        spinner: SpinnerHint,
+    ) -> (String, Message) {
+        let indented = self.indent(&context.prefixed(text));
+        let text = indented.as_str();
+        let timestamped = self.apply_timestamp(text);
+        let (maybe_timestamped, target) = match self.verbosity() {
             Verbosity::Quiet => {
                 permanent = Some(false);
                 (text, Target::Null)
             }
+            Verbosity::Brief if self.collapsed() => (text, Target::Null),
+            Verbosity::Brief if self.timestamped() => (timestamped.as_ref(), Target::Stderr),
             Verbosity::Brief => (text, Target::Stderr),
-            Verbosity::Verbose => {
-                permanent = Some(true);
-                (text, Target::Stderr)
-            }
             _ => {
                 permanent = Some(true);
-                (timestamped.as_ref(), Target::Stderr)
+                if self.timestamped() {
+                    (timestamped.as_ref(), Target::Stderr)
+                } else {
+                    (text, Target::Stderr)
+                }
             }
         };
 
         let msg_obj = Message {
-            text: maybe_timestamped.to_string(),
+            text: maybe_timestamped.into(),
             model: if permanent.unwrap_or(false) {
                 MessageType::ProgPersistent(target)
             } else {
                 MessageType::ProgEphemeral(target)
             },
             target,
+            spinner,
+            fields: Fields::new(),
+            urgent: false,
         };
 
-        self.printer.send(msg_obj);
-        Ok(())
+        (indented, msg_obj)
     }
 
-    /// Show a simple message to the user.
+    /// Show a progress bar for a task of a known size.
     ///
-    /// Ideally used as the final message in a sequence to show a result, as it
-    /// goes to stdout unlike other message types.
-    fn message(&mut self, text: String) -> PyResult<()> {
-        let timestamped = Self::apply_timestamp(&text);
-        self.log(&timestamped)?;
-
-        let target = match self.verbosity {
+    /// Move the bar along with `advance_bar` or `set_bar`, and remove it with
+    /// `finish_bar`, which leaves its message behind.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn progress_bar(&self, text: &str, total: u64) -> Result<()> {
+        let indented = self.indent(&ROOT.prefixed(text));
+        let text = indented.as_str();
+        let target = match self.verbosity() {
             Verbosity::Quiet => Target::Null,
-            _ => Target::Stdout,
+            Verbosity::Brief if self.collapsed() => Target::Null,
+            _ => Target::Stderr,
         };
 
         let message = Message {
-            text,
-            model: MessageType::Info(),
+            text: text.into(),
+            model: MessageType::ProgBar(target, total),
             target,
+            spinner: SpinnerHint::Auto,
+            fields: Fields::new(),
+            urgent: false,
         };
 
-        self.printer.send(message);
+        self.emit(&ROOT, text, message)
+    }
+
+    /// Show a progress bar laid out with an indicatif template, for layouts the
+    /// default bar doesn't cover.
+    ///
+    /// Besides indicatif's own keys, `{eta}` is the smoothed estimate of the time left.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the template is invalid, or the message can't be written to the log
+    /// file.
+    pub fn progress_bar_with_template(&self, text: &str, total: u64, template: &str) -> Result<()> {
+        let style = indicatif::ProgressStyle::with_template(template).map_err(|e| {
+            Error::InvalidArgument(format!("Invalid progress bar template {template:?}: {e}"))
+        })?;
+        self.progress_bar(text, total)?;
+        self.printer.bar_style(style);
         Ok(())
     }
 
-    #[expect(unused)]
-    /// Render an incremental progress bar.
-    fn progress_bar(&mut self, text: &str, total: u64) -> PyResult<()> {
-        unimplemented!()
+    /// Move the current progress bar forward, for producers that report increments.
+    pub fn advance_bar(&self, amount: u64) {
+        self.printer.bar(BarUpdate::Advance(amount));
     }
 
-    /// Stop gracefully.
-    fn ended_ok(&mut self) -> PyResult<()> {
-        self.finish()
+    /// Move the current progress bar to a position, for producers that report
+    /// cumulative progress.
+    pub fn set_bar(&self, position: u64) {
+        self.printer.bar(BarUpdate::Set(position));
     }
-}
 
-impl Emitter {
-    /// Apply the timestamp to a message if necessary.
-    fn apply_timestamp(text: &str) -> Cow<'_, str> {
-        format!(
-            "{} {}",
-            jiff::Timestamp::now().strftime("%Y-%m-%D %H:%M:%s%.3f"),
-            text
+    /// Remove the current progress bar, leaving its message behind.
+    pub fn finish_bar(&self) {
+        self.printer.bar(BarUpdate::Finish);
+    }
+
+    /// Run `f` with every message it emits forced to `stream`, if given.
+    ///
+    /// This is for the rare messages that must go to a specific stream no matter
+    /// the verbosity, like a final result meant for other programs on stdout.
+    pub fn with_stream<T>(&self, stream: Option<Target>, f: impl FnOnce(&Scoped<'_>) -> T) -> T {
+        f(&self.adjusted(ROOT.with_stream(stream)))
+    }
+
+    /// Run `f` with every message it emits prefixed with `[prefix]`, on the terminal
+    /// and in the log.
+    ///
+    /// This attributes messages to a part of the application, such as a plugin,
+    /// without it having to add the prefix itself. Prefixes nest.
+    pub fn with_prefix<T>(&self, prefix: &str, f: impl FnOnce(&Scoped<'_>) -> T) -> T {
+        f(&self.adjusted(ROOT.with_prefix(prefix)))
+    }
+
+    /// Run `f` with structured `fields` attached to every message it emits.
+    ///
+    /// Fields are shown as ` key=value` suffixes from verbose mode up, written to the
+    /// log, and included as an object in the JSON output format. Fields nest, with
+    /// inner values replacing outer ones of the same key.
+    pub fn with_fields<T>(&self, fields: Fields, f: impl FnOnce(&Scoped<'_>) -> T) -> T {
+        f(&self.adjusted(ROOT.with_fields(fields)))
+    }
+
+    /// A view of the emitter adjusting every message emitted through it by `context`.
+    const fn adjusted(&self, context: Context) -> Scoped<'_> {
+        Scoped {
+            emitter: self,
+            context,
+        }
+    }
+
+    /// Open a progress scope for a subtask.
+    ///
+    /// Progress messages are indented under the scope's message until the scope is
+    /// closed with `close_scope`. Scopes can be nested.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn open_scope(&self, text: &str) -> Result<()> {
+        self.progress(text, Some(true), SpinnerHint::Auto)?;
+        lock(&self.scopes).push((text.to_string(), Instant::now()));
+        Ok(())
+    }
+
+    /// Close the innermost progress scope, showing how long it took overall.
+    ///
+    /// Does nothing if no scope is open.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn close_scope(&self) -> Result<()> {
+        let Some((text, opened)) = lock(&self.scopes).pop() else {
+            return Ok(());
+        };
+        let duration = match self.settings.deterministic() {
+            Some(_) => ELAPSED_PLACEHOLDER.to_string(),
+            None => format!("{:#}", indicatif::HumanDuration(opened.elapsed())),
+        };
+        self.progress(
+            &format!("{text} (took {duration})"),
+            Some(true),
+            SpinnerHint::Never,
         )
-        .into()
     }
 
-    /// Print a string to the log.
-    fn log(&mut self, text: &str) -> PyResult<()> {
-        self.log_handle.write_all(text.as_ref())?;
+    /// Begin a section of the run, such as a build step.
+    ///
+    /// The section's title is shown as a permanent header, with progress messages
+    /// indented under it until the section is ended with `end_section`. Sections can
+    /// be nested, and are marked in the log so it can be split up by section.
+    ///
+    /// In brief mode, a `collapsed` section only shows its header and summary, with
+    /// the progress in between going to the log alone.
+    ///
+    /// With continuous integration markers, sections are also collapsible parts of
+    /// the job's log: timed sections on GitLab, and groups on GitHub Actions, where
+    /// only the outermost sections are as groups can't be nested.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the header can't be written to the log file.
+    pub fn begin_section(&self, title: &str, collapsed: bool) -> Result<()> {
+        self.write_log("SECTION", &format!("begin {title:?}"))?;
+        let begun = self.sections_begun.fetch_add(1, Ordering::Relaxed) + 1;
+        let outermost = lock(&self.sections).is_empty();
+        let marker = match self.settings.ci_markers() {
+            _ if matches!(self.verbosity(), Verbosity::Quiet) => None,
+            Some(Ci::GitHubActions) if outermost => {
+                self.printer.marker(format!("::group::{title}"));
+                Some(title.to_string())
+            }
+            Some(Ci::GitLab) => {
+                let name = gitlab_section_name(title, begun);
+                let options = if collapsed { "[collapsed=true]" } else { "" };
+                self.printer.marker(format!(
+                    "section_start:{}:{name}{options}\r\x1b[0K{title}",
+                    self.unix_time()
+                ));
+                Some(name)
+            }
+            _ => None,
+        };
+        self.progress(title, Some(true), SpinnerHint::Auto)?;
+        lock(&self.sections).push(OpenSection {
+            title: title.to_string(),
+            begun: Instant::now(),
+            collapsed,
+            marker,
+        });
         Ok(())
     }
 
-    /// Stop the printing infrastructure and print a final message to see the logs.
-    fn finish(&mut self) -> PyResult<()> {
+    /// End the innermost section, showing how long it took.
+    ///
+    /// Does nothing if no section is open.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the summary can't be written to the log file.
+    pub fn end_section(&self) -> Result<()> {
+        let Some(OpenSection {
+            title,
+            begun,
+            marker,
+            ..
+        }) = lock(&self.sections).pop()
+        else {
+            return Ok(());
+        };
+        let elapsed = begun.elapsed().as_secs_f64();
+        let (shown, logged) = match self.settings.deterministic() {
+            Some(_) => (
+                ELAPSED_PLACEHOLDER.to_string(),
+                ELAPSED_PLACEHOLDER.to_string(),
+            ),
+            None => (format!("{elapsed:.1}s"), format!("{elapsed:.3}s")),
+        };
+        self.progress(
+            &format!("{title} completed in {shown}"),
+            Some(true),
+            SpinnerHint::Never,
+        )?;
+        match (marker, self.settings.ci_markers()) {
+            (Some(_), Some(Ci::GitHubActions)) => self.printer.marker("::endgroup::".to_string()),
+            (Some(name), Some(Ci::GitLab)) => self
+                .printer
+                .marker(format!("section_end:{}:{name}\r\x1b[0K", self.unix_time())),
+            _ => {}
+        }
+        self.write_log("SECTION", &format!("end {title:?} after {logged}"))
+    }
+
+    /// Show a simple message to the user.
+    ///
+    /// Ideally used as the final message in a sequence to show a result, as it
+    /// goes to stdout unlike other message types.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn message(&self, text: &str) -> Result<()> {
+        self.message_in(&ROOT, text)
+    }
+
+    /// A simple message to the user, adjusted by `context`.
+    fn message_in(&self, context: &Context, text: &str) -> Result<()> {
+        let prefixed = context.prefixed(text);
+        let text = prefixed.as_ref();
+        let target = match self.verbosity() {
+            Verbosity::Quiet => Target::Null,
+            _ => Target::Stdout,
+        };
+
         let message = Message {
-            text: format!("Full execution log at '{}'", self.log_filepath),
+            text: text.into(),
             model: MessageType::Info(),
-            target: Target::Stderr,
+            target,
+            spinner: SpinnerHint::Auto,
+            fields: Fields::new(),
+            urgent: false,
         };
-        self.printer.send(message);
-        self.printer.stop()?;
-        Ok(())
+
+        self.emit(context, text, message)
     }
-}
 
-impl Drop for Emitter {
-    fn drop(&mut self) {
-        self.printer.stop().expect(
-            "An unknown error has occurred! The Emitter was not stopped correctly,\
-            so context about the error has been lost. Please report this error.",
-        );
+    /// Tell the user that something was done successfully.
+    ///
+    /// Like `message`, this goes to stdout in every mode but quiet, but stands out from
+    /// plain messages as a blessed way to end a run.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn success(&self, text: &str) -> Result<()> {
+        self.success_in(&ROOT, text)
     }
-}
 
-#[pymodule(submodule)]
-#[pyo3(module = "craft_cli._rs.emitter")]
-pub mod emitter {
-    use crate::utils::fix_imports;
-    use pyo3::{Bound, PyResult, types::PyModule};
+    /// A success message, adjusted by `context`.
+    fn success_in(&self, context: &Context, text: &str) -> Result<()> {
+        let prefixed = context.prefixed(text);
+        let text = prefixed.as_ref();
+        let target = match self.verbosity() {
+            Verbosity::Quiet => Target::Null,
+            _ => Target::Stdout,
+        };
 
-    #[pymodule_export]
-    use crate::emitter::Emitter;
+        let message = Message {
+            text: text.into(),
+            model: MessageType::Success(),
+            target,
+            spinner: SpinnerHint::Auto,
+            fields: Fields::new(),
+            urgent: false,
+        };
 
-    #[pymodule_export]
-    use crate::printer::Verbosity;
+        self.emit(context, text, message)
+    }
 
-    /// Fix syspath for easier importing in Python.
-    #[pymodule_init]
-    fn init(m: &Bound<'_, PyModule>) -> PyResult<()> {
-        fix_imports(m, "craft_cli._rs.emitter")
+    /// Warn the user about something.
+    ///
+    /// Warnings go to stderr in every mode but quiet, and are always logged.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn warning(&self, text: &str) -> Result<()> {
+        self.warning_in(&ROOT, text)
+    }
+
+    /// A warning, adjusted by `context`.
+    fn warning_in(&self, context: &Context, text: &str) -> Result<()> {
+        let prefixed = context.prefixed(text);
+        let text = prefixed.as_ref();
+        let timestamped = self.apply_timestamp(text);
+        let (maybe_timestamped, target) = match self.verbosity() {
+            Verbosity::Quiet => (text, Target::Null),
+            _ if self.timestamped() => (timestamped.as_ref(), Target::Stderr),
+            _ => (text, Target::Stderr),
+        };
+
+        let message = Message {
+            text: maybe_timestamped.into(),
+            model: MessageType::Warning(),
+            target,
+            spinner: SpinnerHint::Auto,
+            fields: Fields::new(),
+            urgent: false,
+        };
+
+        self.emit(context, text, message)
+    }
+
+    /// Emit a message that was already built elsewhere, such as by another process.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn forward(&self, message: Message) -> Result<()> {
+        let text = Arc::clone(&message.text);
+        self.emit(&ROOT, &text, message)
+    }
+
+    /// Re-emit every message read from a stream of JSON lines, until the stream ends.
+    ///
+    /// This is meant for the output of a child process using the JSON output format,
+    /// so that nested invocations show as one. Lines that aren't messages are passed on
+    /// as debug messages, so nothing written to the stream is lost, and invalid UTF-8
+    /// is replaced rather than treated as an error.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the stream can't be read, or a message can't be logged.
+    pub fn passthrough(&self, reader: impl BufRead) -> Result<()> {
+        for line in crate::utils::lines_lossy(reader) {
+            let (line, _) = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<Message>(&line) {
+                Ok(msg) => self.forward(msg)?,
+                Err(_) => self.debug(&line)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Open a stream to show the output of a subprocess as it's written, after showing
+    /// `text` as progress if given.
+    ///
+    /// Give `Stream::writer` to the subprocess as its stdout or stderr. Every line it
+    /// writes is logged and shown as progress, ephemeral in brief mode and kept from
+    /// verbose mode up. Closing the stream waits for every line to be shown, and so
+    /// does finishing the emitter for any stream left open.
+    ///
+    /// ANSI sequences in the output, such as colors, are stripped unless `keep_ansi` is
+    /// set, in which case they're kept on the terminal from verbose mode up. They're
+    /// always stripped from the log.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the emitter has already finished, if the pipe or its reader thread
+    /// can't be created, or if `text` can't be logged.
+    pub fn open_stream(&self, text: Option<&str>, keep_ansi: bool) -> Result<Stream> {
+        self.open_stream_in(&ROOT, text, keep_ansi)
+    }
+
+    /// Open a stream, with its lines and `text` adjusted by `context`.
+    fn open_stream_in(
+        &self,
+        context: &Context,
+        text: Option<&str>,
+        keep_ansi: bool,
+    ) -> Result<Stream> {
+        let stream = {
+            let open = lock(&self.open);
+            let sink = self.sink().filter(|_| *open).ok_or(Error::Finished)?;
+            let (stream, reader) = Stream::open(sink, context.prefix.clone(), keep_ansi)?;
+            let mut readers = lock(&self.readers);
+            readers.retain(|reader| !reader.is_finished());
+            readers.push(reader);
+            stream
+        };
+        // Nothing is read before the stream is handed back, so this still comes first
+        if let Some(text) = text {
+            self.progress_in(context, text, None, SpinnerHint::Auto)?;
+        }
+        Ok(stream)
+    }
+
+    /// Write every message from now on to a Unix socket or FIFO instead of the
+    /// terminal, for another process to re-emit.
+    ///
+    /// # Errors
+    ///
+    /// Fails if nothing is listening on the socket, or the FIFO can't be opened.
+    #[cfg(unix)]
+    pub fn stream_to(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        log::debug!("Streaming messages to {}", path.display());
+        self.printer.redirect(crate::remote::connect(path)?);
+        Ok(())
+    }
+
+    /// Clean up when interrupted by SIGINT or SIGTERM, then call `then` with the
+    /// signal.
+    ///
+    /// Cleaning up writes out the pending messages, stops any spinner or progress bar,
+    /// shows the cursor again and notes the interruption in the log. Any handler set
+    /// for the signal before this is still run, but the default handlers are not, so
+    /// `then` should usually end the program.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the signal handlers can't be installed.
+    #[cfg(unix)]
+    pub fn handle_signals(&self, then: impl Fn(i32) + Send + 'static) -> Result<()> {
+        use signal_hook::consts::{SIGINT, SIGTERM};
+
+        let mut signals = signal_hook::iterator::Signals::new([SIGINT, SIGTERM])?;
+        let interrupt = self.printer.interrupter();
+        let log_handle = Arc::clone(&self.log_handle);
+        let timestamps = self.timestamps.clone();
+        std::thread::spawn(move || {
+            for signal in signals.forever() {
+                interrupt();
+                let mut log = lock_log(&log_handle);
+                // There's nowhere left to report a failure to write the log
+                _ = writeln!(log, "{} [SIGNAL] Interrupted", timestamps.now());
+                _ = log.flush();
+                drop(log);
+                then(signal);
+            }
+        });
+        Ok(())
+    }
+
+    /// Wait until every message emitted so far has been written to the terminal.
+    ///
+    /// Useful before handing the terminal to something else, like a subprocess.
+    pub fn flush(&self) {
+        self.printer.flush();
+    }
+
+    /// Stop drawing on the terminal once every message emitted so far is shown, so that
+    /// something else can use it, such as a prompt.
+    ///
+    /// Messages emitted while paused are logged straight away, but only shown once
+    /// resumed.
+    pub fn pause(&self) {
+        self.printer.pause();
+    }
+
+    /// Take the terminal back after pausing, showing the messages held back.
+    pub fn resume(&self) {
+        self.printer.resume();
+    }
+
+    /// Counts of what was printed so far, such as how many messages of each type were
+    /// emitted and how many lines were dropped.
+    #[must_use]
+    pub fn stats(&self) -> Stats {
+        self.printer.stats()
+    }
+
+    /// The settings the emitter was created with, after applying the environment.
+    #[must_use]
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Whether messages on a stream are styled with color.
+    ///
+    /// Applications styling their own text should follow this, to match the rest of
+    /// the output. Text styled with `console` already does.
+    #[must_use]
+    pub fn colors_enabled(&self, stream: Target) -> bool {
+        match stream {
+            Target::Stdout => console::colors_enabled(),
+            Target::Stderr => console::colors_enabled_stderr(),
+            Target::Null => false,
+        }
+    }
+
+    /// Whether the terminal's stdout or stderr was found closed, such as a pipe into a
+    /// program like `head` that has exited.
+    ///
+    /// Nothing more is shown on a closed stream, but messages are still logged. The
+    /// conventional status to exit with in this case is 141, as if killed by SIGPIPE.
+    #[must_use]
+    pub fn output_closed(&self) -> bool {
+        self.printer.output_closed()
+    }
+
+    /// The status for the application to exit with.
+    ///
+    /// It's the return code of the error reported with `error`, if any, and 0
+    /// otherwise.
+    #[must_use]
+    pub fn suggested_exit_code(&self) -> i32 {
+        self.exit_code.load(Ordering::Relaxed)
+    }
+
+    /// Register a secret value, such as a token or password.
+    ///
+    /// Every occurrence of the value is masked in all messages and log lines
+    /// written from then on.
+    pub fn add_secret(&self, value: String) {
+        self.secrets
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .add(value);
+        self.attach_log_bridge();
+    }
+
+    /// Stop gracefully.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the printer stopped on an error.
+    pub fn ended_ok(&self) -> Result<()> {
+        self.finish(true, "finished")
+    }
+
+    /// Report an error that ended the run, and stop.
+    ///
+    /// Errors go to stderr in every mode. Unless `logpath_report` is unset, as for
+    /// expected errors like a missing file that the message alone explains, they're
+    /// followed by where to find the full execution log. The suggested exit code
+    /// becomes 1.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the error can't be written to the log file, or if the printer stopped
+    /// on an error.
+    pub fn error(&self, text: &str, logpath_report: bool) -> Result<()> {
+        self.error_with_retcode(text, logpath_report, 1)
+    }
+
+    /// Report an error that ended the run, and stop, as with `error`, suggesting to
+    /// exit with `retcode`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the error can't be written to the log file, or if the printer stopped
+    /// on an error.
+    pub fn error_with_retcode(&self, text: &str, logpath_report: bool, retcode: i32) -> Result<()> {
+        self.error_in(&ROOT, text, logpath_report, retcode)
+    }
+
+    /// Report an error that ended the run, adjusted by `context`, and stop.
+    fn error_in(
+        &self,
+        context: &Context,
+        text: &str,
+        logpath_report: bool,
+        retcode: i32,
+    ) -> Result<()> {
+        self.exit_code.store(retcode, Ordering::Relaxed);
+        let prefixed = context.prefixed(text);
+        let text = prefixed.as_ref();
+        let timestamped = self.apply_timestamp(text);
+        let maybe_timestamped = if self.timestamped() {
+            timestamped.as_ref()
+        } else {
+            text
+        };
+        let message = Message {
+            text: maybe_timestamped.into(),
+            model: MessageType::Error(),
+            target: Target::Stderr,
+            spinner: SpinnerHint::Auto,
+            fields: Fields::new(),
+            urgent: false,
+        };
+        self.emit(context, text, message)?;
+        self.finish(logpath_report, "failed")
+    }
+
+    /// Draw the user's attention to the run having `ended`, if it took long enough and
+    /// that was asked for.
+    ///
+    /// This is written straight to the terminal once the printer has stopped, as it
+    /// isn't a message, or sent with the platform's notification tool.
+    fn draw_attention(&self, ended: &str) {
+        let Some(attention) = self.settings.attention() else {
+            return;
+        };
+        if self.started.elapsed() < self.settings.attention_after() {
+            return;
+        }
+        let sequence = match attention {
+            Attention::Bell => "\x07".to_string(),
+            Attention::Notification => format!("\x1b]9;{} {ended}\x07", self.appname),
+            Attention::Desktop => {
+                if crate::notify::wanted() {
+                    crate::notify::send(&self.appname, &format!("build {ended}"));
+                }
+                return;
+            }
+        };
+        if let Some(screen) = self.settings.screen() {
+            screen.write_str(&sequence);
+        } else if console::user_attended_stderr() {
+            // Drawing attention is best-effort, like notifications
+            _ = console::Term::stderr().write_str(&sequence);
+        }
+    }
+
+    /// Get the application name from the path it was invoked with.
+    fn appname_from_argv0(argv0: &str) -> String {
+        match Path::new(argv0).file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None if argv0.is_empty() => String::from("craft"),
+            None => argv0.to_string(),
+        }
+    }
+
+    /// Forward records from the `log` crate to this emitter, as it is now.
+    fn attach_log_bridge(&self) {
+        let open = lock(&self.open);
+        if *open && let Some(sink) = self.sink() {
+            crate::log_bridge::attach(sink);
+        }
+    }
+
+    /// The parts of this emitter, as it is now, needed to emit from another thread.
+    ///
+    /// There's none once the emitter has finished.
+    fn sink(&self) -> Option<Sink> {
+        Some(Sink {
+            log_handle: Arc::clone(&self.log_handle),
+            printer: self.printer.sender()?,
+            budget: self.printer.budget(),
+            verbosity: self.verbosity(),
+            timestamps: self.timestamps.clone(),
+            secrets: self.secrets().clone(),
+            timestamped: self.settings.timestamped(),
+            #[cfg(feature = "tracing")]
+            deterministic: self.settings.deterministic().is_some(),
+            #[cfg(unix)]
+            journal: self.journal.clone(),
+        })
+    }
+
+    /// The seconds since the Unix epoch, as continuous integration systems time
+    /// sections with, or zero in deterministic mode.
+    fn unix_time(&self) -> u64 {
+        if self.settings.deterministic().is_some() {
+            return 0;
+        }
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs())
+    }
+
+    /// Whether any collapsed section is open.
+    fn collapsed(&self) -> bool {
+        lock(&self.sections).iter().any(|section| section.collapsed)
+    }
+
+    /// Indent text under the progress scopes currently open.
+    fn indent(&self, text: &str) -> String {
+        let depth = lock(&self.scopes).len() + lock(&self.sections).len();
+        format!("{}{text}", "  ".repeat(depth))
+    }
+
+    /// Whether messages on stderr are shown with timestamps, as they are from debug
+    /// verbosity up and always in continuous integration.
+    fn timestamped(&self) -> bool {
+        matches!(self.verbosity(), Verbosity::Debug | Verbosity::Trace)
+            || self.settings.timestamped()
+    }
+
+    /// Apply the timestamp to a message if necessary.
+    fn apply_timestamp<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        format!("{} {}", self.timestamps.now(), text).into()
+    }
+
+    /// Log a line and send a message for it to the printer.
+    ///
+    /// Every message goes through here, so that the log has a line for each one
+    /// regardless of the verbosity.
+    fn emit(&self, context: &Context, log_text: &str, message: Message) -> Result<()> {
+        let message = self.logged(context, log_text, message)?;
+        self.queue(context.stream, message)
+    }
+
+    /// Log a message, giving it back with the context's fields attached.
+    fn logged(&self, context: &Context, log_text: &str, mut message: Message) -> Result<Message> {
+        if self.settings.problem_summary()
+            && let MessageType::Warning() | MessageType::Error() = message.model
+        {
+            lock(&self.problems).push((message.model, log_text.to_string()));
+        }
+        if context.fields.is_empty() {
+            self.log(message.model, log_text)?;
+        } else {
+            let suffix: String = context
+                .fields
+                .iter()
+                .map(|(key, value)| {
+                    if value.is_empty() || value.contains(char::is_whitespace) {
+                        format!(" {key}={value:?}")
+                    } else {
+                        format!(" {key}={value}")
+                    }
+                })
+                .collect();
+            self.log(message.model, &format!("{log_text}{suffix}"))?;
+
+            // JSON output carries the fields on their own
+            let shown = matches!(
+                self.verbosity(),
+                Verbosity::Verbose | Verbosity::Debug | Verbosity::Trace
+            );
+            if shown && matches!(self.settings.format(), OutputFormat::Human) {
+                message.text = format!("{}{suffix}", message.text).into();
+            }
+            message.fields = context.fields.clone();
+        }
+        Ok(message)
+    }
+
+    /// Send a message to the printer and any observers, forced to `stream` if given,
+    /// handling a full queue as the settings say.
+    fn queue(&self, stream: Option<Target>, message: Message) -> Result<()> {
+        let message = self.prepare(stream, message);
+        match self.settings.overflow() {
+            Overflow::Block => self.printer.send(message),
+            Overflow::DropEphemeral => {
+                if let Err(message) = self.printer.try_send(message) {
+                    self.printer.shed_ephemeral();
+                    self.printer.send(message);
+                }
+            }
+            Overflow::Error => self
+                .printer
+                .try_send(message)
+                .map_err(|_| Error::QueueFull)?,
+        }
+        Ok(())
+    }
+
+    /// Send a message to the printer and any observers, waiting for room in the queue.
+    ///
+    /// The emitter's own messages always get through, whatever the settings.
+    fn send(&self, message: Message) {
+        let message = self.prepare(None, message);
+        self.printer.send(message);
+    }
+
+    /// Get a message ready to send, forced to `stream` if given, and show it to any
+    /// observers.
+    fn prepare(&self, stream: Option<Target>, mut message: Message) -> Message {
+        if let Some(stream) = stream {
+            message.retarget(stream);
+        }
+        if let Cow::Owned(masked) = self.secrets().mask(&message.text) {
+            message.text = masked.into();
+        }
+        #[cfg(feature = "python")]
+        self.observers.notify(&message);
+        if let Some(recorded) = &mut *self.lock_recorded() {
+            recorded.push(message.clone());
+        }
+        #[cfg(unix)]
+        if let Some(notifier) = &self.notifier
+            && let MessageType::ProgEphemeral(..)
+            | MessageType::ProgPersistent(..)
+            | MessageType::ProgBar(..) = message.model
+        {
+            notifier.status(&message.text);
+        }
+        message
+    }
+
+    /// Start the log with a header about the run, if the application named itself or
+    /// gave its version.
+    fn write_header(&self) -> Result<()> {
+        let (appname, version) = (self.settings.appname(), self.settings.version());
+        if appname.is_none() && version.is_none() {
+            return Ok(());
+        }
+        let argv: Vec<String> = std::env::args().collect();
+        let platform = format!("{} {}", std::env::consts::OS, std::env::consts::ARCH);
+        let header = [
+            ("app", appname.map(ToString::to_string)),
+            ("version", version.map(ToString::to_string)),
+            ("argv", Some(format!("{argv:?}"))),
+            ("platform", Some(platform)),
+            ("started", Some(self.timestamps.now())),
+        ];
+        for (key, value) in header {
+            if let Some(value) = value {
+                self.write_log("HEADER", &format!("{key}: {value}"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a line to the log, tagged with the time and the level of its message.
+    fn log(&self, model: MessageType, text: &str) -> Result<()> {
+        self.write_log(&model.name().to_uppercase(), text)?;
+
+        // Problems are written out straight away, so they're in the log even if
+        // the application then dies without finishing
+        if let MessageType::Warning() | MessageType::Error() = model {
+            lock_log(&self.log_handle).flush()?;
+        }
+        Ok(())
+    }
+
+    /// Write a line to the log, tagged with the time and the given level.
+    fn write_log(&self, level: &str, text: &str) -> Result<()> {
+        let secrets = self.secrets();
+        let text = secrets.mask(text);
+        writeln!(
+            lock_log(&self.log_handle),
+            "{} [{level}] {text}",
+            self.timestamps.now()
+        )?;
+        #[cfg(unix)]
+        if let Some(journal) = &self.journal {
+            journal.send(level, &text);
+        }
+        Ok(())
+    }
+
+    /// Lock the messages recorded, even if a thread panicked while recording one.
+    fn lock_recorded(&self) -> MutexGuard<'_, Option<Vec<Message>>> {
+        lock(&self.recorded)
+    }
+
+    /// The values masked in output.
+    fn secrets(&self) -> std::sync::RwLockReadGuard<'_, Secrets> {
+        self.secrets.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The filepath of the log file, held in place while reading it.
+    fn lock_log_filepath(&self) -> std::sync::RwLockReadGuard<'_, String> {
+        self.log_filepath
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The log filepath, as a clickable link if the terminal supports it.
+    fn log_filepath_link(&self) -> String {
+        let log_filepath = self.lock_log_filepath();
+        let path =
+            std::path::absolute(&*log_filepath).unwrap_or_else(|_| PathBuf::from(&*log_filepath));
+        hyperlink(
+            &crate::utils::file_url(&path),
+            &log_filepath,
+            Target::Stderr,
+        )
+    }
+
+    /// Stop the printing infrastructure, printing the finish message to see the logs if
+    /// `logpath_report` is set and there is one, and announce that the run `ended`.
+    fn finish(&self, logpath_report: bool, ended: &str) -> Result<()> {
+        *lock(&self.open) = false;
+        // Readers hold on to the printer, and may not have shown everything yet
+        let readers = std::mem::take(&mut *lock(&self.readers));
+        for reader in readers {
+            if !reader.join() {
+                self.warning("Some subprocess output may be missing: its reader failed")?;
+            }
+        }
+        let problems = std::mem::take(&mut *lock(&self.problems));
+        if let Verbosity::Brief | Verbosity::Verbose = self.verbosity()
+            && let Some(summary) = problem_summary(&problems)
+        {
+            let message = Message {
+                text: summary.into(),
+                model: MessageType::Warning(),
+                target: Target::Stderr,
+                spinner: SpinnerHint::Never,
+                fields: Fields::new(),
+                urgent: false,
+            };
+            self.send(message);
+        }
+        if self.settings.report_duration() {
+            let duration = match self.settings.deterministic() {
+                Some(_) => ELAPSED_PLACEHOLDER.to_string(),
+                None => compact_duration(self.started.elapsed()),
+            };
+            self.progress(
+                &format!("Completed in {duration}"),
+                Some(true),
+                SpinnerHint::Never,
+            )?;
+        }
+        // Nothing else is shed once everything queued was shown
+        self.flush();
+        let shed = self.printer.shed();
+        if shed > 0 {
+            self.write_log(
+                "LOG",
+                &format!("Dropped {shed} progress messages as the terminal fell behind"),
+            )?;
+        }
+        if logpath_report && let Some(template) = self.settings.finish_message() {
+            let message = Message {
+                text: template
+                    .replace("{logpath}", &self.log_filepath_link())
+                    .into(),
+                model: MessageType::Info(),
+                target: Target::Stderr,
+                spinner: SpinnerHint::Auto,
+                fields: Fields::new(),
+                urgent: false,
+            };
+            self.send(message);
+        }
+        #[cfg(unix)]
+        if let Some(notifier) = &self.notifier {
+            notifier.stopping();
+        }
+        crate::log_bridge::detach(&self.log_handle);
+        lock_log(&self.log_handle).flush()?;
+        self.printer.stop()?;
+        drop(lock(&self.claim).take());
+        #[cfg(feature = "python")]
+        self.observers.stop();
+
+        self.draw_attention(ended);
+        Ok(())
+    }
+}
+
+/// A view of an emitter adjusting every message emitted through it, from
+/// `Emitter::with_stream`, `Emitter::with_prefix` and `Emitter::with_fields`.
+///
+/// The adjustments only apply to messages emitted through the view, so other threads
+/// sharing the emitter aren't affected.
+pub struct Scoped<'a> {
+    /// The emitter messages are emitted on.
+    emitter: &'a Emitter,
+
+    /// The stream, prefixes and fields every message is adjusted with.
+    context: Context,
+}
+
+impl Scoped<'_> {
+    /// The emitter messages are emitted on, for what isn't adjusted by the view.
+    #[must_use]
+    pub const fn emitter(&self) -> &Emitter {
+        self.emitter
+    }
+
+    /// Verbose information, adjusted by the view.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn verbose(&self, text: &str) -> Result<()> {
+        self.emitter.verbose_in(&self.context, text)
+    }
+
+    /// Debug information, adjusted by the view.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn debug(&self, text: &str) -> Result<()> {
+        self.emitter.debug_in(&self.context, text)
+    }
+
+    /// Trace information, adjusted by the view.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn trace(&self, text: &str) -> Result<()> {
+        self.emitter.trace_in(&self.context, text)
+    }
+
+    /// Progress information, adjusted by the view.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn progress(
+        &self,
+        text: &str,
+        permanent: Option<bool>,
+        spinner: SpinnerHint,
+    ) -> Result<()> {
+        self.emitter
+            .progress_in(&self.context, text, permanent, spinner)
+    }
+
+    /// An update of the progress message spun on, adjusted by the view.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn progress_update(&self, text: &str) -> Result<()> {
+        self.emitter.progress_update_in(&self.context, text)
+    }
+
+    /// A simple message to the user, adjusted by the view.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn message(&self, text: &str) -> Result<()> {
+        self.emitter.message_in(&self.context, text)
+    }
+
+    /// A success message, adjusted by the view.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn success(&self, text: &str) -> Result<()> {
+        self.emitter.success_in(&self.context, text)
+    }
+
+    /// A warning, adjusted by the view.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message can't be written to the log file.
+    pub fn warning(&self, text: &str) -> Result<()> {
+        self.emitter.warning_in(&self.context, text)
+    }
+
+    /// Open a stream to show the output of a subprocess, with its lines and `text`
+    /// adjusted by the view, as with `Emitter::open_stream`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the emitter has already finished, if the pipe or its reader thread
+    /// can't be created, or if `text` can't be logged.
+    pub fn open_stream(&self, text: Option<&str>, keep_ansi: bool) -> Result<Stream> {
+        self.emitter.open_stream_in(&self.context, text, keep_ansi)
+    }
+
+    /// Report an error that ended the run, adjusted by the view, and stop, as with
+    /// `Emitter::error`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the error can't be written to the log file, or if the printer stopped
+    /// on an error.
+    pub fn error(&self, text: &str, logpath_report: bool) -> Result<()> {
+        self.error_with_retcode(text, logpath_report, 1)
+    }
+
+    /// Report an error that ended the run, adjusted by the view, and stop, suggesting
+    /// to exit with `retcode`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the error can't be written to the log file, or if the printer stopped
+    /// on an error.
+    pub fn error_with_retcode(&self, text: &str, logpath_report: bool, retcode: i32) -> Result<()> {
+        self.emitter
+            .error_in(&self.context, text, logpath_report, retcode)
+    }
+
+    /// Run `f` with every message it emits further forced to `stream`, if given.
+    pub fn with_stream<T>(&self, stream: Option<Target>, f: impl FnOnce(&Scoped<'_>) -> T) -> T {
+        f(&self.emitter.adjusted(self.context.with_stream(stream)))
+    }
+
+    /// Run `f` with every message it emits further prefixed with `[prefix]`, inside
+    /// the view's prefixes.
+    pub fn with_prefix<T>(&self, prefix: &str, f: impl FnOnce(&Scoped<'_>) -> T) -> T {
+        f(&self.emitter.adjusted(self.context.with_prefix(prefix)))
+    }
+
+    /// Run `f` with `fields` further attached to every message it emits, replacing the
+    /// view's fields of the same key.
+    pub fn with_fields<T>(&self, fields: Fields, f: impl FnOnce(&Scoped<'_>) -> T) -> T {
+        f(&self.emitter.adjusted(self.context.with_fields(fields)))
+    }
+}
+
+/// Sum up `problems` on a line per kind, like `2 warnings: first; second`, if there
+/// were any.
+fn problem_summary(problems: &[(MessageType, String)]) -> Option<String> {
+    let lines: Vec<String> = [
+        (MessageType::Warning(), "warning"),
+        (MessageType::Error(), "error"),
+    ]
+    .into_iter()
+    .filter_map(|(model, noun)| {
+        let texts: Vec<&str> = problems
+            .iter()
+            .filter(|(kind, _)| *kind == model)
+            .map(|(_, text)| text.as_str())
+            .collect();
+        match texts.len() {
+            0 => None,
+            1 => Some(format!("1 {noun}: {}", texts[0])),
+            count => Some(format!("{count} {noun}s: {}", texts.join("; "))),
+        }
+    })
+    .collect();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// Format `duration` down to the second, like `3m 12s`.
+fn compact_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes}m {seconds}s")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+/// Lock some of the emitter's state, even if a thread panicked while holding it, as
+/// none of it is left half-changed.
+fn lock<T>(state: &Mutex<T>) -> MutexGuard<'_, T> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Lock the log file for writing.
+fn lock_log(log_handle: &Mutex<BufWriter<File>>) -> MutexGuard<'_, BufWriter<File>> {
+    // A panic while writing a line leaves nothing worse than a partial line behind
+    log_handle
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// The Python bindings for the `Emitter`.
+///
+/// None of the emitting work needs Python, so the GIL is released around it to avoid
+/// serializing multi-threaded applications on the emitter.
+///
+/// The text of messages can be given as bytes too, as build tools' output often is,
+/// and is decoded as UTF-8 with invalid sequences replaced.
+#[cfg(feature = "python")]
+#[pymethods]
+impl Emitter {
+    /// Construct a new `Emitter` from Python.
+    ///
+    /// The messages are logged to `log_filepath`, shown at the given `verbosity` and
+    /// start with the `greeting`. `docs_base_url` is the base URL for error messages.
+    /// The keyword arguments are:
+    ///
+    /// - `theme`: how messages are styled, the default theme if not set.
+    /// - `output_format`: with `OutputFormat.Json`, every message is written to stdout
+    ///   as a line of JSON instead.
+    /// - `clock` and `timestamp_format`: where timestamps are read from, and the
+    ///   strftime format they're written in if set.
+    /// - `spinners` and `spinner_after`: whether spinners are shown, once an operation
+    ///   went that many seconds without a message.
+    /// - `colors`: always or never color output, rather than only on terminals.
+    /// - `managed`: run as if in a build environment owned by a host process.
+    /// - `max_redraw_rate`: how many times a second ephemeral progress is redrawn.
+    /// - `cursor_control`: whether the cursor is hidden while messages are shown.
+    /// - `eta_window`: the seconds of updates progress bars estimate the time left
+    ///   from.
+    /// - `max_debug_rate`: how many debug and trace lines a second are shown, if set.
+    /// - `journal`: mirror the log to systemd-journald or syslog.
+    /// - `screen`: draw on that virtual terminal instead of the real one.
+    /// - `deterministic`: make output the same on every run, with that timestamp.
+    /// - `ci` and `ci_markers`: whether to write for continuous integration, detected
+    ///   from the environment if not set, and whether its interface shows sections,
+    ///   warnings and errors.
+    /// - `title`: show the current progress in the terminal's title.
+    /// - `highlight`: highlight the URLs and file paths in messages.
+    /// - `report_duration`: say how long the whole run took when finishing.
+    /// - `problem_summary`: sum up the warnings and errors when finishing.
+    /// - `finish_message`: point to the log when finishing, where `{logpath}` stands
+    ///   for where it is, unless it's `None`.
+    /// - `attention` and `attention_after`: how to draw the user's attention when a
+    ///   run of at least that many seconds ends.
+    /// - `queue_capacity` and `overflow`: how many messages wait for the printer at
+    ///   most, if set, and what happens to more.
+    /// - `max_queued_bytes`: how much text waits for the printer at most, unless it's
+    ///   `None`.
+    /// - `exclusive`: fail when another emitter controls the terminal, or give back
+    ///   the other one with `Exclusive.Reuse`.
+    /// - `appname` and `version`: if either is set, the log starts with a header about
+    ///   the run.
+    ///
+    /// These make up the emitter's settings, which the environment takes precedence
+    /// over. `Emitter.settings` shows what was resolved.
+    #[new]
+    #[pyo3(signature = (
+        log_filepath,
+        verbosity,
+        docs_base_url,
+        greeting,
+        *,
+        theme = None,
+        output_format = OutputFormat::Human,
+        clock = Clock::Local,
+        timestamp_format = None,
+        spinners = true,
+        spinner_after = 3.0,
+        colors = None,
+        managed = false,
+        max_redraw_rate = 20,
+        cursor_control = true,
+        eta_window = 5.0,
+        max_debug_rate = None,
+        journal = false,
+        screen = None,
+        deterministic = None,
+        ci = None,
+        ci_markers = false,
+        title = false,
+        highlight = false,
+        report_duration = false,
+        problem_summary = false,
+        finish_message = Some(DEFAULT_FINISH_MESSAGE.to_string()),
+        attention = None,
+        attention_after = 30.0,
+        queue_capacity = None,
+        overflow = Overflow::Block,
+        max_queued_bytes = Some(DEFAULT_MAX_QUEUED_BYTES),
+        exclusive = Exclusive::Off,
+        appname = None,
+        version = None,
+    ))]
+    // Python callers pass these by keyword, so there's no need for a builder
+    #[expect(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
+    fn py_new(
+        py: Python<'_>,
+        log_filepath: String,
+        verbosity: Verbosity,
+        docs_base_url: &str,
+        greeting: String,
+        theme: Option<Theme>,
+        output_format: OutputFormat,
+        clock: Clock,
+        timestamp_format: Option<String>,
+        spinners: bool,
+        spinner_after: f64,
+        colors: Option<bool>,
+        managed: bool,
+        max_redraw_rate: u32,
+        cursor_control: bool,
+        eta_window: f64,
+        max_debug_rate: Option<u32>,
+        journal: bool,
+        screen: Option<Screen>,
+        deterministic: Option<String>,
+        ci: Option<bool>,
+        ci_markers: bool,
+        title: bool,
+        highlight: bool,
+        report_duration: bool,
+        problem_summary: bool,
+        finish_message: Option<String>,
+        attention: Option<Attention>,
+        attention_after: f64,
+        queue_capacity: Option<usize>,
+        overflow: Overflow,
+        max_queued_bytes: Option<usize>,
+        exclusive: Exclusive,
+        appname: Option<String>,
+        version: Option<String>,
+    ) -> PyResult<PyClassInitializer<Self>> {
+        if let Exclusive::Reuse = exclusive
+            && let Some(active) = &*PY_ACTIVE
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+        {
+            return Ok(active.clone_ref(py).into());
+        }
+
+        let spinner_after = Duration::try_from_secs_f64(spinner_after)
+            .map_err(|e| Error::InvalidArgument(format!("Invalid spinner_after: {e}")))?;
+        let eta_window = Duration::try_from_secs_f64(eta_window)
+            .map_err(|e| Error::InvalidArgument(format!("Invalid eta_window: {e}")))?;
+        let attention_after = Duration::try_from_secs_f64(attention_after)
+            .map_err(|e| Error::InvalidArgument(format!("Invalid attention_after: {e}")))?;
+
+        let mut settings = Settings::default()
+            .with_format(output_format)
+            .with_theme(theme.unwrap_or_default())
+            .with_managed(managed)
+            .with_spinners(spinners)
+            .with_spinner_after(spinner_after)
+            .with_clock(clock)
+            .with_max_redraw_rate(max_redraw_rate)
+            .with_cursor_control(cursor_control)
+            .with_eta_window(eta_window)
+            .with_max_debug_rate(max_debug_rate)
+            .with_journal(journal)
+            .with_deterministic(deterministic)
+            .with_ci_markers(ci_markers)
+            .with_title(title)
+            .with_highlight(highlight)
+            .with_report_duration(report_duration)
+            .with_problem_summary(problem_summary)
+            .with_finish_message(finish_message)
+            .with_attention(attention, attention_after)
+            .with_queue_capacity(queue_capacity)
+            .with_overflow(overflow)
+            .with_max_queued_bytes(max_queued_bytes)
+            .with_exclusive(exclusive)
+            .with_appname(appname)
+            .with_version(version);
+        if let Some(colors) = colors {
+            settings = settings.with_colors(colors);
+        }
+        if let Some(format) = timestamp_format {
+            settings = settings.with_timestamp_format(format);
+        }
+        if let Some(screen) = screen {
+            settings = settings.with_screen(screen);
+        }
+        if let Some(ci) = ci {
+            settings = settings.with_ci(ci);
+        }
+
+        // Spawn the printer thread without using the GIL at all
+        // This is necessary to avoid deadlocks when using OnceCell, see the link below
+        // for more information.
+        // https://pyo3.rs/v0.25.1/faq.html#im-experiencing-deadlocks-using-pyo3-with-stdsynconcelock-stdsynclazylock-lazy_static-and-once_cell
+        let mut emitter = py.allow_threads(|| {
+            Self::new(log_filepath, verbosity, docs_base_url, greeting, settings)
+        })?;
+
+        // The Rust argv is the interpreter's, so use the script's instead, unless the
+        // application named itself. Embedded interpreters may have an empty argv.
+        if emitter.settings.appname().is_none()
+            && let Ok(argv0) = py
+                .import("sys")?
+                .getattr("argv")?
+                .get_item(0)
+                .and_then(|arg| arg.extract::<String>())
+        {
+            emitter.appname = Self::appname_from_argv0(&argv0);
+            #[cfg(unix)]
+            if let Some(journal) = &mut emitter.journal {
+                journal.identifier.clone_from(&emitter.appname);
+                emitter.attach_log_bridge();
+            }
+        }
+
+        if let Exclusive::Reuse = exclusive
+            && lock(&emitter.claim).is_some()
+        {
+            let emitter = Py::new(py, emitter)?;
+            *PY_ACTIVE
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(emitter.clone_ref(py));
+            return Ok(emitter.into());
+        }
+        Ok(emitter.into())
+    }
+
+    /// Create an emitter with the pure-Python craft-cli's `init` signature, logging to
+    /// `log_filepath` or to a new file named after `appname` if not given.
+    ///
+    /// Any other keyword arguments are passed on to the constructor. To create the
+    /// emitter idle and initialize it later, as craft-cli does, use an
+    /// `EmitterController`.
+    #[classmethod]
+    #[pyo3(signature = (mode, appname, greeting, log_filepath = None, docs_base_url = None, **kwargs))]
+    fn init<'py>(
+        cls: &Bound<'py, PyType>,
+        mode: Verbosity,
+        appname: &str,
+        greeting: String,
+        log_filepath: Option<String>,
+        docs_base_url: Option<String>,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let options = kwargs.map_or_else(|| Ok(PyDict::new(cls.py())), PyDictMethods::copy)?;
+        options.set_item(
+            "log_filepath",
+            log_filepath.unwrap_or_else(|| Self::log_filepath_from_name(appname)),
+        )?;
+        options.set_item("verbosity", mode)?;
+        options.set_item("docs_base_url", docs_base_url.unwrap_or_default())?;
+        options.set_item("greeting", greeting)?;
+        options.set_item("appname", appname)?;
+        cls.call((), Some(&options))
+    }
+
+    /// Create a log filepath from the app name as an easy default.
+    #[classmethod]
+    #[pyo3(name = "log_filepath_from_name")]
+    fn py_log_filepath_from_name(_cls: &Bound<'_, PyType>, app_name: &str) -> String {
+        Self::log_filepath_from_name(app_name)
+    }
+
+    /// The path of the log file being written to.
+    #[getter(log_filepath)]
+    fn py_log_filepath(&self) -> PathBuf {
+        self.log_filepath()
+    }
+
+    /// Get the path of the log file being written to.
+    #[pyo3(name = "get_log_path")]
+    fn py_get_log_path(&self) -> PathBuf {
+        self.log_filepath()
+    }
+
+    /// Move the log file to `new_path`, and keep appending to it there.
+    ///
+    /// The file is renamed in a single step when both paths are on the same
+    /// filesystem, and copied over otherwise.
+    #[pyo3(name = "relocate_log")]
+    // PyO3 can only extract a path into an owned buffer
+    #[expect(clippy::needless_pass_by_value)]
+    fn py_relocate_log(&self, py: Python<'_>, new_path: PathBuf) -> PyResult<()> {
+        Ok(py.allow_threads(|| self.relocate_log(&new_path))?)
+    }
+
+    /// Get the current verbosity mode of the emitter.
+    #[pyo3(name = "get_verbosity")]
+    fn py_get_verbosity(&self) -> Verbosity {
+        self.verbosity()
+    }
+
+    /// Set the verbosity of the emitter.
+    #[pyo3(name = "set_verbosity")]
+    fn py_set_verbosity(&self, py: Python<'_>, new: Verbosity) {
+        py.allow_threads(|| self.set_verbosity(new));
+    }
+
+    /// Verbose information.
+    ///
+    /// Useful for providing more information to the user that isn't particularly
+    /// helpful for "regular use"
+    #[pyo3(name = "verbose", signature = (text, *, stream = None, fields = None))]
+    fn py_verbose(
+        &self,
+        py: Python<'_>,
+        text: PyText,
+        stream: Option<Target>,
+        fields: Option<PyFields>,
+    ) -> PyResult<()> {
+        let context = Context::py(stream, fields);
+        Ok(py.allow_threads(move || self.verbose_in(&context, &text.0))?)
+    }
+
+    /// Debug information.
+    ///
+    /// Use to record anything that the user may not want to normally see, but
+    /// would be useful for the app developers to understand why things may be
+    /// failing.
+    #[pyo3(name = "debug", signature = (text, *, stream = None, fields = None))]
+    fn py_debug(
+        &self,
+        py: Python<'_>,
+        text: PyText,
+        stream: Option<Target>,
+        fields: Option<PyFields>,
+    ) -> PyResult<()> {
+        let context = Context::py(stream, fields);
+        Ok(py.allow_threads(move || self.debug_in(&context, &text.0))?)
+    }
+
+    /// Trace information.
+    ///
+    /// Use to expose system-generated information which in general would be
+    /// overwhelming for debugging purposes but sometimes needed for more
+    /// in-depth analysis.
+    #[pyo3(name = "trace", signature = (text, *, stream = None, fields = None))]
+    fn py_trace(
+        &self,
+        py: Python<'_>,
+        text: PyText,
+        stream: Option<Target>,
+        fields: Option<PyFields>,
+    ) -> PyResult<()> {
+        let context = Context::py(stream, fields);
+        Ok(py.allow_threads(move || self.trace_in(&context, &text.0))?)
+    }
+
+    /// Progress information.
+    ///
+    /// This is normally used to present several related messages relaying how
+    /// a task is going. If a progress message is important enough that it
+    /// shouldn't be overwritten by the next ones, use "permanent=True".
+    ///
+    /// These messages will be truncated to the terminal's width and overwritten
+    /// by the next line (unless in verbose or trace mode, or set to permanent).
+    ///
+    /// Use `spinner=SpinnerHint.Immediate` to show a spinner right away for a step
+    /// known to be slow, or `SpinnerHint.Never` where a spinner would be misleading.
+    #[pyo3(
+        name = "progress",
+        signature = (
+            text, permanent = None, *, spinner = SpinnerHint::Auto, stream = None, fields = None
+        )
+    )]
+    fn py_progress(
+        &self,
+        py: Python<'_>,
+        text: PyText,
+        permanent: Option<bool>,
+        spinner: SpinnerHint,
+        stream: Option<Target>,
+        fields: Option<PyFields>,
+    ) -> PyResult<()> {
+        let context = Context::py(stream, fields);
+        Ok(py.allow_threads(move || self.progress_in(&context, &text.0, permanent, spinner))?)
+    }
+
+    /// Change the text of the progress message a spinner is shown for, in place.
+    ///
+    /// Without a spinner, this is the same as `progress`.
+    #[pyo3(name = "progress_update")]
+    fn py_progress_update(&self, py: Python<'_>, text: PyText) -> PyResult<()> {
+        Ok(py.allow_threads(move || self.progress_update(&text.0))?)
+    }
+
+    /// Open a progress scope for a subtask, as a context manager.
+    ///
+    /// Progress messages within the `with` block are indented under the scope's
+    /// message, and how long the whole block took is shown when it ends.
+    fn progress_scope(slf: Py<Self>, text: String) -> ProgressScope {
+        ProgressScope { emitter: slf, text }
+    }
+
+    /// Begin a section of the run, such as a build step.
+    ///
+    /// Progress messages are indented under the section's title until it's ended with
+    /// `end_section`. In brief mode, a `collapsed` section only shows its header and
+    /// summary.
+    #[pyo3(name = "begin_section", signature = (title, *, collapsed = false))]
+    fn py_begin_section(&self, py: Python<'_>, title: &str, collapsed: bool) -> PyResult<()> {
+        Ok(py.allow_threads(|| self.begin_section(title, collapsed))?)
+    }
+
+    /// End the innermost section, showing how long it took.
+    #[pyo3(name = "end_section")]
+    fn py_end_section(&self, py: Python<'_>) -> PyResult<()> {
+        Ok(py.allow_threads(|| self.end_section())?)
+    }
+
+    /// Begin a section of the run, as a context manager that ends it.
+    #[pyo3(signature = (title, *, collapsed = false))]
+    fn section(slf: Py<Self>, title: String, collapsed: bool) -> Section {
+        Section {
+            emitter: slf,
+            title,
+            collapsed,
+        }
+    }
+
+    /// Get a child emitter whose messages are all prefixed with `[prefix]`, on the
+    /// terminal and in the log.
+    fn scoped(slf: Py<Self>, prefix: String) -> ScopedEmitter {
+        ScopedEmitter {
+            emitter: slf,
+            prefixes: vec![prefix],
+        }
+    }
+
+    /// Show a simple message to the user.
+    ///
+    /// Ideally used as the final message in a sequence to show a result, as it
+    /// goes to stdout unlike other message types.
+    #[pyo3(name = "message", signature = (text, *, stream = None, fields = None))]
+    fn py_message(
+        &self,
+        py: Python<'_>,
+        text: PyText,
+        stream: Option<Target>,
+        fields: Option<PyFields>,
+    ) -> PyResult<()> {
+        let context = Context::py(stream, fields);
+        Ok(py.allow_threads(move || self.message_in(&context, &text.0))?)
+    }
+
+    /// Tell the user that something was done successfully.
+    ///
+    /// Like `message`, this goes to stdout, but stands out from plain messages.
+    #[pyo3(name = "success", signature = (text, *, stream = None, fields = None))]
+    fn py_success(
+        &self,
+        py: Python<'_>,
+        text: PyText,
+        stream: Option<Target>,
+        fields: Option<PyFields>,
+    ) -> PyResult<()> {
+        let context = Context::py(stream, fields);
+        Ok(py.allow_threads(move || self.success_in(&context, &text.0))?)
+    }
+
+    /// Warn the user about something.
+    ///
+    /// Warnings go to stderr in every mode but quiet, and are always logged.
+    #[pyo3(name = "warning", signature = (text, *, stream = None, fields = None))]
+    fn py_warning(
+        &self,
+        py: Python<'_>,
+        text: PyText,
+        stream: Option<Target>,
+        fields: Option<PyFields>,
+    ) -> PyResult<()> {
+        let context = Context::py(stream, fields);
+        Ok(py.allow_threads(move || self.warning_in(&context, &text.0))?)
+    }
+
+    /// Show a progress bar for a task of a known size, as a context manager.
+    ///
+    /// The context manager's `advance` moves the bar forward by an amount, and `set`
+    /// moves it to a position. The bar is removed when the block ends, leaving its
+    /// message behind.
+    ///
+    /// An indicatif `template` can be given for layouts the default bar doesn't cover.
+    #[pyo3(name = "progress_bar", signature = (text, total, *, template = None))]
+    fn py_progress_bar(
+        slf: Py<Self>,
+        text: String,
+        total: u64,
+        template: Option<String>,
+    ) -> ProgressBar {
+        ProgressBar {
+            emitter: slf,
+            text,
+            total,
+            template,
+        }
+    }
+
+    /// Show a progress bar counting the `total` bytes of a transfer, as a context
+    /// manager.
+    ///
+    /// If `fileobj` is given, the context manager wraps it: reading from it or writing
+    /// to it through the context manager moves the bar along, so it can be handed to
+    /// code uploading or downloading. Otherwise, the context manager is a callback
+    /// taking how many more bytes were transferred.
+    #[pyo3(signature = (text, total, fileobj = None))]
+    fn transfer(slf: Py<Self>, text: String, total: u64, fileobj: Option<Py<PyAny>>) -> Transfer {
+        Transfer {
+            emitter: slf,
+            text,
+            total,
+            fileobj,
+        }
+    }
+
+    /// Open a stream to show the output of a subprocess as it's written, after showing
+    /// `text` as progress if given.
+    ///
+    /// As a context manager, the stream gives the file descriptor of a pipe to pass to
+    /// `subprocess` as `stdout` or `stderr`. Leaving it waits for every line written
+    /// to be shown.
+    ///
+    /// The subprocess's colors are kept on the terminal from verbose mode up if `ansi`
+    /// is set, and stripped otherwise. They're always stripped from the log.
+    #[cfg(unix)]
+    #[pyo3(name = "open_stream", signature = (text = None, *, ansi = false))]
+    fn py_open_stream(&self, py: Python<'_>, text: Option<&str>, ansi: bool) -> PyResult<Stream> {
+        Ok(py.allow_threads(|| self.open_stream(text, ansi))?)
+    }
+
+    /// A context manager routing whatever is written to `sys.stdout` and `sys.stderr`
+    /// through the emitter, as trace messages.
+    ///
+    /// Stray `print()` calls from the application or its plugins then land in the log
+    /// instead of garbling the progress line.
+    fn capture_output(slf: Py<Self>) -> CapturedOutput {
+        CapturedOutput {
+            emitter: slf,
+            saved: None,
+        }
+    }
+
+    /// Clean up when interrupted by SIGINT or SIGTERM, leaving the terminal usable and
+    /// noting the interruption in the log.
+    ///
+    /// `KeyboardInterrupt` is still raised as usual on SIGINT, and SIGTERM raises it
+    /// too, so that the application can stop the same way for both.
+    #[cfg(unix)]
+    #[pyo3(name = "handle_signals")]
+    fn py_handle_signals(&self) -> PyResult<()> {
+        Ok(self.handle_signals(|signal| {
+            // Python's own handler already raises it for SIGINT
+            if signal == signal_hook::consts::SIGTERM {
+                // SAFETY: this can be called from any thread, with or without the GIL
+                unsafe { pyo3::ffi::PyErr_SetInterrupt() };
+            }
+        })?)
+    }
+
+    /// The settings the emitter was created with, after applying the environment.
+    #[getter(settings)]
+    fn py_settings(&self) -> Settings {
+        self.settings.clone()
+    }
+
+    /// Whether messages on a stream are styled with color.
+    ///
+    /// Applications styling their own text should follow this, to match the rest of
+    /// the output.
+    #[pyo3(name = "colors_enabled", signature = (stream = Target::Stdout))]
+    fn py_colors_enabled(&self, stream: Target) -> bool {
+        self.colors_enabled(stream)
+    }
+
+    /// Whether the terminal's stdout or stderr was found closed, such as a pipe into a
+    /// program like `head` that has exited.
+    ///
+    /// The conventional status to exit with in this case is 141.
+    #[getter(output_closed)]
+    fn py_output_closed(&self) -> bool {
+        self.output_closed()
+    }
+
+    /// Wait until every message emitted so far has been written to the terminal.
+    ///
+    /// Useful before handing the terminal to something else, like a subprocess.
+    #[pyo3(name = "flush")]
+    fn py_flush(&self, py: Python<'_>) {
+        py.allow_threads(|| self.flush());
+    }
+
+    /// The emitter's verbosity, log file and whether it's still running, for debugging.
+    fn __repr__(&self) -> String {
+        let state = if self.printer.sender().is_some() {
+            "running"
+        } else {
+            "stopped"
+        };
+        format!(
+            "<Emitter verbosity={} log_filepath={:?} {state}>",
+            self.verbosity().name(),
+            *self.lock_log_filepath()
+        )
+    }
+
+    /// Counts of what was printed so far, such as how many messages of each type were
+    /// emitted and how many lines were dropped.
+    #[pyo3(name = "stats")]
+    fn py_stats(&self) -> Stats {
+        self.stats()
+    }
+
+    /// Stop drawing on the terminal once every message emitted so far is shown, so that
+    /// something else can use it, such as a prompt.
+    #[pyo3(name = "pause")]
+    fn py_pause(&self, py: Python<'_>) {
+        py.allow_threads(|| self.pause());
+    }
+
+    /// Take the terminal back after pausing, showing the messages held back.
+    #[pyo3(name = "resume")]
+    fn py_resume(&self, py: Python<'_>) {
+        py.allow_threads(|| self.resume());
+    }
+
+    /// Write every message from now on to a Unix socket or FIFO instead of the
+    /// terminal, for another process to re-emit.
+    #[cfg(unix)]
+    #[pyo3(name = "stream_to")]
+    fn py_stream_to(&self, py: Python<'_>, path: std::path::PathBuf) -> PyResult<()> {
+        Ok(py.allow_threads(|| self.stream_to(path))?)
+    }
+
+    /// Re-emit every message a child process writes as JSON lines to a file, until it
+    /// closes it.
+    ///
+    /// The file can be anything with a file descriptor, such as the stdout pipe of a
+    /// `subprocess.Popen`, or the descriptor itself.
+    #[cfg(unix)]
+    #[pyo3(name = "passthrough")]
+    fn py_passthrough(&self, py: Python<'_>, stream: &Bound<'_, PyAny>) -> PyResult<()> {
+        use std::os::fd::{BorrowedFd, RawFd};
+
+        let fd: RawFd = match stream.extract() {
+            Ok(fd) => fd,
+            Err(_) => stream.call_method0("fileno")?.extract()?,
+        };
+        // SAFETY: the descriptor is only borrowed long enough to duplicate it, while
+        // the caller still holds the stream open
+        let owned = unsafe { BorrowedFd::borrow_raw(fd) }.try_clone_to_owned()?;
+        let file = File::from(owned);
+        Ok(py.allow_threads(|| self.passthrough(std::io::BufReader::new(file)))?)
+    }
+
+    /// Re-emit every message another process writes to a `RemoteListener`, until it's
+    /// done writing.
+    #[cfg(unix)]
+    fn relay_remote(&self, py: Python<'_>, listener: &Listener) -> PyResult<()> {
+        Ok(py.allow_threads(|| listener.relay(self))?)
+    }
+
+    /// Register a callback to be invoked for every message emitted.
+    ///
+    /// The callback receives the message type's name and the message text. It is
+    /// run from a dedicated thread, so it won't slow down the code emitting messages.
+    fn add_observer(&self, callback: Py<PyAny>) {
+        self.observers.register(callback);
+    }
+
+    /// Register a secret value, such as a token or password.
+    ///
+    /// Every occurrence of the value is masked in all messages and log lines
+    /// written from then on.
+    #[pyo3(name = "add_secret")]
+    fn py_add_secret(&self, value: String) {
+        self.add_secret(value);
+    }
+
+    /// Stop gracefully.
+    #[pyo3(name = "ended_ok")]
+    fn py_ended_ok(&self, py: Python<'_>) -> PyResult<()> {
+        // Observers need the GIL to finish up, so it must be released here
+        Ok(py.allow_threads(|| self.ended_ok())?)
+    }
+
+    /// Report an error that ended the run, and stop.
+    ///
+    /// The error is shown as by `str()`, followed by where to find the full execution
+    /// log unless its `logpath_report` attribute is false. Its `retcode` attribute, 1
+    /// if it has none, is kept as the `suggested_exit_code`.
+    #[pyo3(name = "error")]
+    fn py_error(&self, py: Python<'_>, error: &Bound<'_, PyAny>) -> PyResult<()> {
+        let (text, logpath_report, retcode) = py_error_details(error)?;
+        Ok(py.allow_threads(|| self.error_with_retcode(&text, logpath_report, retcode))?)
+    }
+
+    /// The status for the application to exit with.
+    #[getter(suggested_exit_code)]
+    fn py_suggested_exit_code(&self) -> i32 {
+        self.suggested_exit_code()
+    }
+
+    /// Stop gracefully, as an awaitable that doesn't block the running event loop.
+    ///
+    /// Joining the printer thread and draining open streams happen on the loop's
+    /// default executor.
+    fn finish_async<'py>(slf: &Bound<'py, Self>) -> PyResult<Bound<'py, PyAny>> {
+        crate::utils::run_in_executor(&slf.getattr("ended_ok")?)
+    }
+}
+
+/// The text of a Python error as by `str()`, along with its `logpath_report` and
+/// `retcode` attributes, true and 1 if it has none.
+#[cfg(feature = "python")]
+fn py_error_details(error: &Bound<'_, PyAny>) -> PyResult<(String, bool, i32)> {
+    let text = error.str()?.to_string();
+    let logpath_report = match error.getattr_opt("logpath_report")? {
+        Some(flag) => flag.is_truthy()?,
+        None => true,
+    };
+    let retcode = match error.getattr_opt("retcode")? {
+        Some(retcode) => retcode.extract()?,
+        None => 1,
+    };
+    Ok((text, logpath_report, retcode))
+}
+
+/// Structured fields passed from Python as a dict, with every value turned into a
+/// string as by `str()`.
+#[cfg(feature = "python")]
+#[derive(Default)]
+struct PyFields(Fields);
+
+#[cfg(feature = "python")]
+impl<'py> FromPyObject<'py> for PyFields {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        ob.downcast::<PyDict>()?
+            .iter()
+            .map(|(key, value)| Ok((key.str()?.to_string(), value.str()?.to_string())))
+            .collect::<PyResult<_>>()
+            .map(Self)
+    }
+}
+
+/// Message text passed from Python, either as a string or as bytes, such as the output
+/// of a build tool, with invalid UTF-8 replaced rather than treated as an error.
+#[cfg(feature = "python")]
+struct PyText(String);
+
+#[cfg(feature = "python")]
+impl<'py> FromPyObject<'py> for PyText {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if let Ok(bytes) = ob.downcast::<PyBytes>() {
+            return Ok(Self(String::from_utf8_lossy(bytes.as_bytes()).into_owned()));
+        }
+        ob.extract().map(Self)
+    }
+}
+
+/// A context manager for a progress scope, from `Emitter.progress_scope`.
+#[cfg(feature = "python")]
+#[pyclass(frozen)]
+pub struct ProgressScope {
+    /// The emitter the scope is opened on.
+    emitter: Py<Emitter>,
+
+    /// The message the scope is opened with.
+    text: String,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl ProgressScope {
+    /// Open the scope.
+    fn __enter__(&self, py: Python<'_>) -> PyResult<()> {
+        let emitter = self.emitter.get();
+        Ok(py.allow_threads(|| emitter.open_scope(&self.text))?)
+    }
+
+    /// Close the scope, even if the block raised.
+    fn __exit__(
+        &self,
+        py: Python<'_>,
+        _exc_type: &Bound<'_, PyAny>,
+        _exc_value: &Bound<'_, PyAny>,
+        _traceback: &Bound<'_, PyAny>,
+    ) -> PyResult<()> {
+        let emitter = self.emitter.get();
+        Ok(py.allow_threads(|| emitter.close_scope())?)
+    }
+}
+
+/// A context manager for a section, from `Emitter.section`.
+#[cfg(feature = "python")]
+#[pyclass(frozen)]
+pub struct Section {
+    /// The emitter the section is begun on.
+    emitter: Py<Emitter>,
+
+    /// The title of the section.
+    title: String,
+
+    /// Whether the section's details are hidden in brief mode.
+    collapsed: bool,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Section {
+    /// Begin the section.
+    fn __enter__(&self, py: Python<'_>) -> PyResult<()> {
+        let emitter = self.emitter.get();
+        Ok(py.allow_threads(|| emitter.begin_section(&self.title, self.collapsed))?)
+    }
+
+    /// End the section, even if the block raised.
+    fn __exit__(
+        &self,
+        py: Python<'_>,
+        _exc_type: &Bound<'_, PyAny>,
+        _exc_value: &Bound<'_, PyAny>,
+        _traceback: &Bound<'_, PyAny>,
+    ) -> PyResult<()> {
+        let emitter = self.emitter.get();
+        Ok(py.allow_threads(|| emitter.end_section())?)
+    }
+}
+
+/// A context manager swapping `sys.stdout` and `sys.stderr` for the emitter, from
+/// `Emitter.capture_output`.
+#[cfg(feature = "python")]
+#[pyclass]
+pub struct CapturedOutput {
+    /// The emitter written text is emitted on.
+    emitter: Py<Emitter>,
+
+    /// The streams that were replaced, to put back on exit.
+    saved: Option<(Py<PyAny>, Py<PyAny>)>,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl CapturedOutput {
+    /// Replace the streams.
+    fn __enter__(&mut self, py: Python<'_>) -> PyResult<()> {
+        let sys = py.import("sys")?;
+        let stdout = sys.getattr("stdout")?.unbind();
+        let stderr = sys.getattr("stderr")?.unbind();
+
+        for name in ["stdout", "stderr"] {
+            let stream = CapturedStream {
+                emitter: self.emitter.clone_ref(py),
+                pending: Mutex::new(String::new()),
+            };
+            sys.setattr(name, Py::new(py, stream)?)?;
+        }
+        self.saved = Some((stdout, stderr));
+        Ok(())
+    }
+
+    /// Emit any unfinished lines and put the streams back, even if the block raised.
+    fn __exit__(
+        &mut self,
+        py: Python<'_>,
+        _exc_type: &Bound<'_, PyAny>,
+        _exc_value: &Bound<'_, PyAny>,
+        _traceback: &Bound<'_, PyAny>,
+    ) -> PyResult<()> {
+        let sys = py.import("sys")?;
+        if let Some((stdout, stderr)) = self.saved.take() {
+            for (name, original) in [("stdout", stdout), ("stderr", stderr)] {
+                let current = sys.getattr(name)?;
+                if let Ok(captured) = current.downcast::<CapturedStream>() {
+                    captured.get().flush(py)?;
+                }
+                sys.setattr(name, original)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A file-like object emitting every line written to it as a trace message.
+#[cfg(feature = "python")]
+#[pyclass(frozen)]
+pub struct CapturedStream {
+    /// The emitter lines are emitted on.
+    emitter: Py<Emitter>,
+
+    /// Text written since the last complete line.
+    pending: Mutex<String>,
+}
+
+#[cfg(feature = "python")]
+impl CapturedStream {
+    /// Lock the text written since the last complete line.
+    fn pending(&self) -> MutexGuard<'_, String> {
+        // A panic while appending leaves nothing worse than a partial line behind
+        self.pending
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Emit a line, queued to the printer like any other message, even when printing
+    /// from inside an observer.
+    fn emit(&self, py: Python<'_>, line: &str) -> PyResult<()> {
+        let emitter = self.emitter.get();
+        Ok(py.allow_threads(|| emitter.trace(line))?)
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl CapturedStream {
+    /// Emit every complete line in `text`, holding back any unfinished one.
+    fn write(&self, py: Python<'_>, text: &str) -> PyResult<usize> {
+        let complete: String = {
+            let mut pending = self.pending();
+            pending.push_str(text);
+            match pending.rfind('\n') {
+                Some(end) => pending.drain(..=end).collect(),
+                None => String::new(),
+            }
+        };
+        for line in complete.lines() {
+            self.emit(py, line)?;
+        }
+        Ok(text.chars().count())
+    }
+
+    /// Emit any unfinished line.
+    fn flush(&self, py: Python<'_>) -> PyResult<()> {
+        let line = std::mem::take(&mut *self.pending());
+        if line.is_empty() {
+            return Ok(());
+        }
+        self.emit(py, &line)
+    }
+
+    /// The stream is never a terminal, as its text goes through the emitter.
+    #[staticmethod]
+    fn isatty() -> bool {
+        false
+    }
+
+    /// The stream can always be written to.
+    #[staticmethod]
+    fn writable() -> bool {
+        true
+    }
+
+    /// The encoding of the text written, for code that checks before writing.
+    #[classattr]
+    fn encoding() -> &'static str {
+        "utf-8"
+    }
+}
+
+/// A child emitter prefixing every message, from `Emitter.scoped`.
+#[cfg(feature = "python")]
+#[pyclass(frozen)]
+pub struct ScopedEmitter {
+    /// The emitter messages are emitted on.
+    emitter: Py<Emitter>,
+
+    /// The prefixes applied to every message, outermost first.
+    prefixes: Vec<String>,
+}
+
+#[cfg(feature = "python")]
+impl ScopedEmitter {
+    /// Run `f` on the emitter with the GIL released, and a context with every prefix
+    /// applied, outermost first.
+    fn run<T: Send>(&self, py: Python<'_>, f: impl FnOnce(&Emitter, &Context) -> T + Send) -> T {
+        let emitter = self.emitter.get();
+        let context = self
+            .prefixes
+            .iter()
+            .fold(ROOT, |context, prefix| context.with_prefix(prefix));
+        py.allow_threads(|| f(emitter, &context))
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl ScopedEmitter {
+    /// Get a child emitter with a further prefix, after this one's.
+    fn scoped(&self, py: Python<'_>, prefix: String) -> Self {
+        let mut prefixes = self.prefixes.clone();
+        prefixes.push(prefix);
+        Self {
+            emitter: self.emitter.clone_ref(py),
+            prefixes,
+        }
+    }
+
+    /// Verbose information, prefixed.
+    fn verbose(&self, py: Python<'_>, text: PyText) -> PyResult<()> {
+        Ok(self.run(py, move |e, c| e.verbose_in(c, &text.0))?)
+    }
+
+    /// Debug information, prefixed.
+    fn debug(&self, py: Python<'_>, text: PyText) -> PyResult<()> {
+        Ok(self.run(py, move |e, c| e.debug_in(c, &text.0))?)
+    }
+
+    /// Trace information, prefixed.
+    fn trace(&self, py: Python<'_>, text: PyText) -> PyResult<()> {
+        Ok(self.run(py, move |e, c| e.trace_in(c, &text.0))?)
+    }
+
+    /// Progress information, prefixed.
+    #[pyo3(signature = (text, permanent = None, *, spinner = SpinnerHint::Auto))]
+    fn progress(
+        &self,
+        py: Python<'_>,
+        text: PyText,
+        permanent: Option<bool>,
+        spinner: SpinnerHint,
+    ) -> PyResult<()> {
+        Ok(self.run(py, move |e, c| {
+            e.progress_in(c, &text.0, permanent, spinner)
+        })?)
+    }
+
+    /// An update of the progress message spun on, prefixed.
+    fn progress_update(&self, py: Python<'_>, text: PyText) -> PyResult<()> {
+        Ok(self.run(py, move |e, c| e.progress_update_in(c, &text.0))?)
+    }
+
+    /// A simple message to the user, prefixed.
+    fn message(&self, py: Python<'_>, text: PyText) -> PyResult<()> {
+        Ok(self.run(py, move |e, c| e.message_in(c, &text.0))?)
+    }
+
+    /// A warning, prefixed.
+    fn warning(&self, py: Python<'_>, text: PyText) -> PyResult<()> {
+        Ok(self.run(py, move |e, c| e.warning_in(c, &text.0))?)
+    }
+
+    /// Open a stream to show the output of a subprocess, with its lines and `text`
+    /// prefixed.
+    #[cfg(unix)]
+    #[pyo3(signature = (text = None, *, ansi = false))]
+    fn open_stream(&self, py: Python<'_>, text: Option<String>, ansi: bool) -> PyResult<Stream> {
+        Ok(self.run(py, move |e, c| e.open_stream_in(c, text.as_deref(), ansi))?)
+    }
+
+    /// Report an error that ended the run, prefixed, and stop.
+    fn error(&self, py: Python<'_>, error: &Bound<'_, PyAny>) -> PyResult<()> {
+        let (text, logpath_report, retcode) = py_error_details(error)?;
+        Ok(self.run(py, move |e, c| {
+            e.error_in(c, &text, logpath_report, retcode)
+        })?)
+    }
+}
+
+/// A context manager for a progress bar, from `Emitter.progress_bar`.
+#[cfg(feature = "python")]
+#[pyclass(frozen)]
+pub struct ProgressBar {
+    /// The emitter the bar is shown on.
+    emitter: Py<Emitter>,
+
+    /// The message shown with the bar.
+    text: String,
+
+    /// The size of the task.
+    total: u64,
+
+    /// The indicatif template the bar is laid out with, if not the default.
+    template: Option<String>,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl ProgressBar {
+    /// Show the bar.
+    fn __enter__(slf: Py<Self>, py: Python<'_>) -> PyResult<Py<Self>> {
+        {
+            let this = slf.get();
+            let emitter = this.emitter.get();
+            py.allow_threads(|| match &this.template {
+                Some(template) => {
+                    emitter.progress_bar_with_template(&this.text, this.total, template)
+                }
+                None => emitter.progress_bar(&this.text, this.total),
+            })?;
+        }
+        Ok(slf)
+    }
+
+    /// Move the bar forward by an amount.
+    fn advance(&self, amount: u64) {
+        self.emitter.get().advance_bar(amount);
+    }
+
+    /// Move the bar to a position.
+    fn set(&self, position: u64) {
+        self.emitter.get().set_bar(position);
+    }
+
+    /// Remove the bar, even if the block raised.
+    fn __exit__(
+        &self,
+        _exc_type: &Bound<'_, PyAny>,
+        _exc_value: &Bound<'_, PyAny>,
+        _traceback: &Bound<'_, PyAny>,
+    ) {
+        self.emitter.get().finish_bar();
+    }
+}
+
+/// The layout of the progress bars of transfers, counting bytes.
+#[cfg(feature = "python")]
+const TRANSFER_TEMPLATE: &str =
+    "{msg} [{bar:40}] {binary_bytes}/{binary_total_bytes} ({binary_bytes_per_sec}, {eta} left)";
+
+/// A context manager for the progress bar of a transfer, from `Emitter.transfer`.
+#[cfg(feature = "python")]
+#[pyclass(frozen)]
+pub struct Transfer {
+    /// The emitter the bar is shown on.
+    emitter: Py<Emitter>,
+
+    /// The message shown with the bar.
+    text: String,
+
+    /// How many bytes are transferred.
+    total: u64,
+
+    /// The file-like object the transfer goes through, if any.
+    fileobj: Option<Py<PyAny>>,
+}
+
+#[cfg(feature = "python")]
+impl Transfer {
+    /// The file-like object the transfer goes through.
+    fn file<'py>(&self, py: Python<'py>) -> PyResult<&Bound<'py, PyAny>> {
+        match &self.fileobj {
+            Some(file) => Ok(file.bind(py)),
+            None => Err(Error::InvalidArgument(
+                "The transfer wasn't given a file to go through".to_string(),
+            )
+            .into()),
+        }
+    }
+
+    /// Move the bar forward by `amount` bytes.
+    fn advance(&self, amount: usize) {
+        self.emitter.get().advance_bar(amount as u64);
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Transfer {
+    /// Show the bar.
+    fn __enter__(slf: Py<Self>, py: Python<'_>) -> PyResult<Py<Self>> {
+        {
+            let this = slf.get();
+            let emitter = this.emitter.get();
+            py.allow_threads(|| {
+                emitter.progress_bar_with_template(&this.text, this.total, TRANSFER_TEMPLATE)
+            })?;
+        }
+        Ok(slf)
+    }
+
+    /// Move the bar forward by how many more bytes were transferred.
+    fn __call__(&self, amount: usize) {
+        self.advance(amount);
+    }
+
+    /// Read from the file, moving the bar forward by what was read.
+    #[pyo3(signature = (size = -1))]
+    fn read<'py>(&self, py: Python<'py>, size: isize) -> PyResult<Bound<'py, PyAny>> {
+        let data = self.file(py)?.call_method1("read", (size,))?;
+        self.advance(data.len()?);
+        Ok(data)
+    }
+
+    /// Read from the file into a buffer, moving the bar forward by what was read.
+    fn readinto<'py>(
+        &self,
+        py: Python<'py>,
+        buffer: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let read = self.file(py)?.call_method1("readinto", (buffer,))?;
+        // Non-blocking files give None when nothing was ready to read
+        if !read.is_none() {
+            self.advance(read.extract()?);
+        }
+        Ok(read)
+    }
+
+    /// Write to the file, moving the bar forward by what was written.
+    fn write<'py>(&self, py: Python<'py>, data: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+        let written = self.file(py)?.call_method1("write", (data,))?;
+        if !written.is_none() {
+            self.advance(written.extract()?);
+        }
+        Ok(written)
+    }
+
+    /// Pass everything else on to the file, such as `seek` or `name`.
+    fn __getattr__<'py>(&self, py: Python<'py>, name: &str) -> PyResult<Bound<'py, PyAny>> {
+        self.file(py)?.getattr(name)
+    }
+
+    /// Remove the bar, even if the block raised.
+    fn __exit__(
+        &self,
+        _exc_type: &Bound<'_, PyAny>,
+        _exc_value: &Bound<'_, PyAny>,
+        _traceback: &Bound<'_, PyAny>,
+    ) {
+        self.emitter.get().finish_bar();
+    }
+}
+
+impl Drop for Emitter {
+    fn drop(&mut self) {
+        crate::log_bridge::detach(&self.log_handle);
+        for reader in std::mem::take(
+            self.readers
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner),
+        ) {
+            reader.join();
+        }
+        // Emitters that weren't finished are dropped as the application fails
+        let failed = self.printer.sender().is_some();
+        self.printer.stop().expect(
+            "An unknown error has occurred! The Emitter was not stopped correctly,\
+            so context about the error has been lost. Please report this error.",
+        );
+        if failed {
+            self.draw_attention("failed");
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymodule(submodule)]
+#[pyo3(module = "craft_cli._rs.emitter")]
+pub mod emitter {
+    use crate::utils::fix_imports;
+    use pyo3::{
+        Bound, PyResult,
+        types::{PyModule, PyModuleMethods},
+    };
+
+    #[pymodule_export]
+    use crate::emitter::{
+        CapturedOutput, CapturedStream, Emitter, ProgressBar, ProgressScope, ScopedEmitter,
+        Section, Transfer,
+    };
+
+    #[pymodule_export]
+    use crate::printer::{OutputFormat, SpinnerHint, Stats, Target, Verbosity};
+
+    #[pymodule_export]
+    use crate::controller::EmitterController;
+
+    #[pymodule_export]
+    use crate::error::PrinterError;
+
+    #[pymodule_export]
+    use crate::global::{
+        EmitterScope, py_get_emitter, py_reset_emitter, py_set_emitter, scoped_emitter,
+    };
+
+    #[pymodule_export]
+    use crate::settings::{Attention, Exclusive, Overflow, Settings};
+
+    #[pymodule_export]
+    use crate::theme::Theme;
+
+    #[pymodule_export]
+    use crate::timestamp::Clock;
+
+    #[cfg(unix)]
+    #[pymodule_export]
+    use crate::remote::Listener;
+
+    #[cfg(unix)]
+    #[pymodule_export]
+    use crate::stream::Stream;
+
+    /// Add the shared emitter controller, and fix syspath for easier importing in
+    /// Python.
+    #[pymodule_init]
+    fn init(m: &Bound<'_, PyModule>) -> PyResult<()> {
+        m.add("emit", EmitterController::default())?;
+        fix_imports(m, "craft_cli._rs.emitter")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{emitter, emitter_at, emitter_with, json, log_lines};
+    use crate::theme::Theme;
+
+    /// A writer collecting everything written to it, to check the printer's output.
+    #[derive(Clone, Default)]
+    struct Shared(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Shared {
+        /// The JSON messages written so far.
+        fn messages(&self) -> Vec<Message> {
+            let buf = self.0.lock().unwrap();
+            String::from_utf8_lossy(&buf)
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+
+        /// The texts of the JSON messages written so far.
+        fn texts(&self) -> Vec<String> {
+            self.messages()
+                .iter()
+                .map(|msg| msg.text().to_string())
+                .collect()
+        }
+    }
+
+    impl std::io::Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    mod section {
+        use super::*;
+
+        #[test]
+        fn collapsed_in_brief() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter_with(&dir, Verbosity::Brief, json());
+            let out = Shared::default();
+            emitter.printer.redirect(Box::new(out.clone()));
+
+            emitter.begin_section("Build", true).unwrap();
+            emitter
+                .progress("compiling", Some(true), SpinnerHint::Auto)
+                .unwrap();
+            emitter.warning("careful").unwrap();
+            emitter.end_section().unwrap();
+            emitter.flush();
+
+            let texts = out.texts();
+            assert_eq!(texts[..2], ["Build", "careful"]);
+            assert!(texts[2].starts_with("Build completed in "));
+            assert_eq!(texts.len(), 3);
+            emitter.ended_ok().unwrap();
+        }
+
+        #[test]
+        fn expanded_in_verbose() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter_with(&dir, Verbosity::Verbose, json());
+            let out = Shared::default();
+            emitter.printer.redirect(Box::new(out.clone()));
+
+            emitter.begin_section("Build", true).unwrap();
+            emitter
+                .progress("compiling", None, SpinnerHint::Auto)
+                .unwrap();
+            emitter.end_section().unwrap();
+            emitter.flush();
+
+            assert_eq!(out.texts()[..2], ["Build", "  compiling"]);
+            emitter.ended_ok().unwrap();
+        }
+
+        #[test]
+        fn gitlab_names() {
+            assert_eq!(
+                gitlab_section_name("Build snap: amd64", 3),
+                "build_snap__amd64_3"
+            );
+        }
+    }
+
+    mod scope {
+        use super::*;
+
+        #[test]
+        fn nested_indented() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter_with(
+                &dir,
+                Verbosity::Brief,
+                json().with_deterministic(Some("2024-01-01 00:00:00.000".to_string())),
+            );
+            let out = Shared::default();
+            emitter.printer.redirect(Box::new(out.clone()));
+
+            emitter.open_scope("Fetching").unwrap();
+            emitter
+                .progress("a", Some(true), SpinnerHint::Auto)
+                .unwrap();
+            emitter.open_scope("Unpacking").unwrap();
+            emitter
+                .progress("b", Some(true), SpinnerHint::Auto)
+                .unwrap();
+            emitter.close_scope().unwrap();
+            emitter.close_scope().unwrap();
+            emitter.close_scope().unwrap();
+            emitter
+                .progress("c", Some(true), SpinnerHint::Auto)
+                .unwrap();
+            emitter.flush();
+
+            assert_eq!(
+                out.texts(),
+                [
+                    "Fetching",
+                    "  a",
+                    "  Unpacking",
+                    "    b",
+                    "  Unpacking (took <elapsed>)",
+                    "Fetching (took <elapsed>)",
+                    "c",
+                ]
+            );
+            emitter.ended_ok().unwrap();
+        }
+
+        #[test]
+        fn duration_on_close() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter_with(&dir, Verbosity::Brief, json());
+            let out = Shared::default();
+            emitter.printer.redirect(Box::new(out.clone()));
+
+            emitter.open_scope("Fetching").unwrap();
+            emitter.close_scope().unwrap();
+            emitter.flush();
+
+            assert_eq!(out.texts(), ["Fetching", "Fetching (took 0s)"]);
+            emitter.ended_ok().unwrap();
+        }
+    }
+
+    mod passthrough {
+        use super::*;
+
+        #[test]
+        fn messages_forwarded() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter_with(&dir, Verbosity::Brief, json());
+            let out = Shared::default();
+            emitter.printer.redirect(Box::new(out.clone()));
+
+            let input = concat!(
+                r#"{"version":2,"type":"warning","text":"careful","target":"stderr"}"#,
+                "\n\n",
+                r#"{"version":2,"type":"progress","permanent":true,"stream":"stderr","#,
+                r#""text":"Building","target":"stderr"}"#,
+                "\nmake: not json\n",
+                r#"{"version":2,"type":"shout","text":"Hi","target":"stderr"}"#,
+                "\n",
+            );
+            emitter.passthrough(input.as_bytes()).unwrap();
+            emitter.flush();
+
+            let shown = out.messages();
+            assert_eq!(shown.len(), 2);
+            assert_eq!(shown[0].text(), "careful");
+            assert!(matches!(shown[0].model(), MessageType::Warning()));
+            assert_eq!(shown[1].text(), "Building");
+            assert!(matches!(
+                shown[1].model(),
+                MessageType::ProgPersistent(Target::Stderr)
+            ));
+            emitter.ended_ok().unwrap();
+
+            let log = fs::read_to_string(dir.path().join("test.log")).unwrap();
+            assert!(log.contains("[WARNING] careful\n"), "{log}");
+            assert!(log.contains("[DEBUG] make: not json\n"), "{log}");
+            assert!(
+                log.contains(r#"[DEBUG] {"version":2,"type":"shout""#),
+                "{log}"
+            );
+        }
+    }
+
+    mod overflow {
+        use super::*;
+
+        #[test]
+        fn fails_when_full() {
+            let dir = tempfile::tempdir().unwrap();
+            let screen = crate::screen::Screen::new(4, 20);
+            let emitter = emitter_with(
+                &dir,
+                Verbosity::Brief,
+                Settings::default()
+                    .with_screen(screen.clone())
+                    .with_queue_capacity(Some(1))
+                    .with_overflow(Overflow::Error),
+            );
+
+            emitter.pause();
+            emitter.message("Packed hello.snap").unwrap();
+            let err = emitter.message("Packed bye.snap").unwrap_err();
+            assert!(matches!(err, Error::QueueFull));
+            emitter.resume();
+            emitter.flush();
+
+            assert_eq!(screen.contents(), "Packed hello.snap");
+            emitter.ended_ok().unwrap();
+        }
+    }
+
+    mod exclusive {
+        use super::*;
+
+        #[test]
+        fn one_in_control() {
+            let dir = tempfile::tempdir().unwrap();
+            let start = |name: &str| {
+                Emitter::new(
+                    dir.path().join(name).to_string_lossy().into_owned(),
+                    Verbosity::Quiet,
+                    "",
+                    "Hello".to_string(),
+                    Settings::default()
+                        .with_theme(Theme::no_color())
+                        .with_cursor_control(false)
+                        .with_exclusive(Exclusive::Error),
+                )
+            };
+
+            let first = start("first.log").unwrap();
+            assert!(matches!(start("second.log"), Err(Error::EmitterActive)));
+            // A screen isn't the terminal, so it's never in the way
+            let screen = Settings::default()
+                .with_screen(crate::screen::Screen::new(4, 20))
+                .with_exclusive(Exclusive::Error);
+            let on_screen = emitter_at(&dir.path().join("screen.log"), Verbosity::Quiet, screen);
+            on_screen.ended_ok().unwrap();
+
+            first.ended_ok().unwrap();
+            start("third.log").unwrap().ended_ok().unwrap();
+        }
+    }
+
+    #[cfg(feature = "python")]
+    mod init {
+        use super::*;
+
+        #[test]
+        fn craft_cli_signature() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("app.log").to_string_lossy().into_owned();
+
+            Python::with_gil(|py| {
+                let kwargs = PyDict::new(py);
+                kwargs
+                    .set_item("output_format", OutputFormat::Json)
+                    .unwrap();
+                let emitter = py
+                    .get_type::<Emitter>()
+                    .call_method(
+                        "init",
+                        (Verbosity::Brief, "app", "Hello", &path, "https://docs/"),
+                        Some(&kwargs),
+                    )
+                    .unwrap();
+                let emitter = emitter.downcast::<Emitter>().unwrap().get();
+                assert!(matches!(emitter.verbosity(), Verbosity::Brief));
+                assert_eq!(emitter.log_filepath(), path);
+                emitter.ended_ok().unwrap();
+            });
+        }
+    }
+
+    #[cfg(feature = "python")]
+    mod text {
+        use pyo3::types::PyBytes;
+
+        use super::*;
+
+        #[test]
+        fn bytes_decoded() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter(&dir);
+
+            Python::with_gil(|py| {
+                let emitter = Py::new(py, emitter).unwrap();
+                let emitter = emitter.bind(py);
+                let output = PyBytes::new(py, b"caf\xc3\xa9 \xff done");
+                emitter.call_method1("trace", (output,)).unwrap();
+                let scoped = emitter.call_method1("scoped", ("part",)).unwrap();
+                scoped
+                    .call_method1("trace", (b"built".as_slice(),))
+                    .unwrap();
+                emitter.call_method0("ended_ok").unwrap();
+            });
+
+            let log = fs::read_to_string(dir.path().join("test.log")).unwrap();
+            assert!(log.contains("] caf\u{e9} \u{fffd} done\n"), "{log}");
+            assert!(log.contains("] [part] built\n"), "{log}");
+        }
+    }
+
+    #[cfg(feature = "python")]
+    mod transfer {
+        use super::*;
+
+        #[test]
+        fn passes_through() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter_with(&dir, Verbosity::Brief, json());
+
+            Python::with_gil(|py| {
+                let emitter = Py::new(py, emitter).unwrap();
+                let emitter = emitter.bind(py);
+                let io = py.import("io").unwrap();
+                let source = io
+                    .call_method1("BytesIO", (b"0123456789".as_slice(),))
+                    .unwrap();
+                let sink = io.call_method0("BytesIO").unwrap();
+
+                let reading = emitter
+                    .call_method1("transfer", ("Downloading", 10, source))
+                    .unwrap();
+                let reading = reading.call_method0("__enter__").unwrap();
+                let writing = emitter
+                    .call_method1("transfer", ("Uploading", 10, &sink))
+                    .unwrap();
+                let chunk = reading.call_method1("read", (4,)).unwrap();
+                writing.call_method1("write", (chunk,)).unwrap();
+                let rest = reading.call_method0("read").unwrap();
+                writing.call_method1("write", (rest,)).unwrap();
+                assert_eq!(
+                    reading
+                        .call_method0("tell")
+                        .unwrap()
+                        .extract::<u64>()
+                        .unwrap(),
+                    10
+                );
+                let none = py.None().into_bound(py);
+                reading
+                    .call_method1("__exit__", (&none, &none, &none))
+                    .unwrap();
+
+                let value = sink.call_method0("getvalue").unwrap();
+                assert_eq!(value.extract::<Vec<u8>>().unwrap(), b"0123456789");
+                let callback = emitter.call_method1("transfer", ("Syncing", 10)).unwrap();
+                callback.call1((10,)).unwrap();
+                assert!(callback.call_method1("read", (1,)).is_err());
+                emitter.call_method0("ended_ok").unwrap();
+            });
+        }
+    }
+
+    #[cfg(all(unix, feature = "python"))]
+    mod finish_async {
+        use super::*;
+        use pyo3::types::PyModule;
+
+        #[test]
+        fn drains_streams() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter(&dir);
+            let path = emitter.log_filepath();
+
+            Python::with_gil(|py| {
+                let emitter = Py::new(py, emitter).unwrap();
+                let module = PyModule::from_code(
+                    py,
+                    cr#"
+import os
+
+async def run(emitter):
+    async with emitter.open_stream("Building") as fd:
+        os.write(fd, b"compiling\n")
+    await emitter.finish_async()
+"#,
+                    c"finish_async.py",
+                    c"finish_async",
+                )
+                .unwrap();
+                let run = module.getattr("run").unwrap().call1((&emitter,)).unwrap();
+                py.import("asyncio")
+                    .unwrap()
+                    .call_method1("run", (run,))
+                    .unwrap();
+                assert!(emitter.borrow(py).printer.sender().is_none());
+            });
+
+            let log = fs::read_to_string(path).unwrap();
+            assert!(log.contains("] :: compiling\n"), "{log}");
+        }
+    }
+
+    mod finish {
+        use super::*;
+
+        #[test]
+        fn while_adding_secrets() {
+            for _ in 0..20 {
+                let dir = tempfile::tempdir().unwrap();
+                let emitter = Arc::new(emitter(&dir));
+                let stop = Arc::new(AtomicBool::new(false));
+                let adding = {
+                    let (emitter, stop) = (Arc::clone(&emitter), Arc::clone(&stop));
+                    std::thread::spawn(move || {
+                        while !stop.load(Ordering::Relaxed) {
+                            emitter.add_secret("hunter2".to_string());
+                        }
+                    })
+                };
+                let (done, finished) = crossbeam_channel::bounded(1);
+                let finishing = {
+                    let emitter = Arc::clone(&emitter);
+                    std::thread::spawn(move || {
+                        emitter.ended_ok().unwrap();
+                        done.send(()).unwrap();
+                    })
+                };
+
+                // Stopping the printer would hang if a sender was handed out meanwhile
+                let result = finished.recv_timeout(Duration::from_secs(10));
+                stop.store(true, Ordering::Relaxed);
+                result.unwrap();
+                finishing.join().unwrap();
+                adding.join().unwrap();
+            }
+        }
+    }
+
+    #[cfg(feature = "python")]
+    mod threads {
+        use super::*;
+        use pyo3::types::PyModule;
+
+        #[test]
+        fn shared_while_changed() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter_with(&dir, Verbosity::Brief, json());
+            let path = emitter.log_filepath();
+
+            Python::with_gil(|py| {
+                let emitter = Py::new(py, emitter).unwrap();
+                let module = PyModule::from_code(
+                    py,
+                    cr#"
+import threading
+
+def run(emitter, Verbosity):
+    def work(n):
+        for i in range(50):
+            emitter.progress(f"worker {n} step {i}")
+            with emitter.progress_scope(f"worker {n} scope {i}"):
+                emitter.debug(f"worker {n} detail {i}")
+
+    threads = [threading.Thread(target=work, args=(n,)) for n in range(4)]
+    for thread in threads:
+        thread.start()
+    for i in range(20):
+        with emitter.section(f"section {i}"):
+            emitter.set_verbosity(Verbosity.Verbose if i % 2 else Verbosity.Brief)
+    for thread in threads:
+        thread.join()
+    emitter.ended_ok()
+"#,
+                    c"threads.py",
+                    c"threads",
+                )
+                .unwrap();
+                let verbosity = py.get_type::<Verbosity>();
+                module
+                    .getattr("run")
+                    .unwrap()
+                    .call1((&emitter, verbosity))
+                    .unwrap();
+                assert!(emitter.borrow(py).printer.sender().is_none());
+            });
+
+            let log = fs::read_to_string(path).unwrap();
+            for n in 0..4 {
+                assert!(log.contains(&format!("worker {n} step 49\n")), "{log}");
+            }
+            assert!(log.contains("end \"section 19\""), "{log}");
+        }
+    }
+
+    #[cfg(feature = "python")]
+    mod capture_output {
+        use super::*;
+        use pyo3::types::PyModule;
+
+        #[test]
+        fn routed_and_restored() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter(&dir);
+
+            Python::with_gil(|py| {
+                let emitter = Py::new(py, emitter).unwrap();
+                let module = PyModule::from_code(
+                    py,
+                    cr#"
+import sys
+
+def run(emitter):
+    stdout, stderr = sys.stdout, sys.stderr
+    with emitter.capture_output():
+        print("printed", "twice\nover lines")
+        sys.stderr.write("unfinished")
+        assert not sys.stdout.isatty()
+    assert sys.stdout is stdout and sys.stderr is stderr
+
+    try:
+        with emitter.capture_output():
+            print("before raising")
+            raise ValueError
+    except ValueError:
+        pass
+    assert sys.stdout is stdout and sys.stderr is stderr
+    emitter.ended_ok()
+"#,
+                    c"capture.py",
+                    c"capture",
+                )
+                .unwrap();
+                module.getattr("run").unwrap().call1((&emitter,)).unwrap();
+            });
+
+            let log = fs::read_to_string(dir.path().join("test.log")).unwrap();
+            assert!(log.contains("] printed twice\n"), "{log}");
+            assert!(log.contains("] over lines\n"), "{log}");
+            assert!(log.contains("] unfinished\n"), "{log}");
+            assert!(log.contains("] before raising\n"), "{log}");
+        }
+    }
+
+    #[cfg(feature = "python")]
+    mod repr {
+        use super::*;
+
+        #[test]
+        fn shows_state() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter_with(&dir, Verbosity::Brief, json());
+            let path = emitter.log_filepath();
+            emitter.ended_ok().unwrap();
+
+            Python::with_gil(|py| {
+                let emitter = Py::new(py, emitter).unwrap();
+                let repr = emitter.bind(py).as_any().repr().unwrap().to_string();
+                assert_eq!(
+                    repr,
+                    format!("<Emitter verbosity=brief log_filepath={path:?} stopped>")
+                );
+                let verbosity = Py::new(py, Verbosity::Brief).unwrap();
+                let verbosity = verbosity.bind(py).as_any().str().unwrap();
+                assert_eq!(verbosity.to_string(), "brief");
+                let model = Py::new(py, MessageType::ProgEphemeral(Target::Stderr)).unwrap();
+                assert_eq!(
+                    model.bind(py).as_any().repr().unwrap().to_string(),
+                    "MessageType.ProgEphemeral(Target.Stderr)"
+                );
+            });
+        }
+    }
+
+    mod progress_bar {
+        use super::*;
+
+        #[test]
+        fn invalid_template() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter(&dir);
+
+            let err = emitter
+                .progress_bar_with_template("Downloading", 10, "{bar:40x}")
+                .unwrap_err();
+            assert!(matches!(err, Error::InvalidArgument(_)));
+            emitter.ended_ok().unwrap();
+        }
+    }
+
+    mod error {
+        use super::*;
+
+        #[test]
+        fn logpath_reported() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter(&dir);
+            let out = Shared::default();
+            emitter.printer.redirect(Box::new(out.clone()));
+
+            emitter.error("Something broke", true).unwrap();
+
+            let texts = out.texts();
+            assert_eq!(texts[0], "Something broke");
+            assert!(texts[1].starts_with("Full execution log at "), "{texts:?}");
+            let log = fs::read_to_string(emitter.log_filepath()).unwrap();
+            assert!(log.contains("[ERROR] Something broke\n"), "{log}");
+        }
+
+        #[test]
+        fn logpath_not_reported() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter(&dir);
+            let out = Shared::default();
+            emitter.printer.redirect(Box::new(out.clone()));
+
+            emitter.error("No such file: 'app.yaml'", false).unwrap();
+
+            assert_eq!(out.texts(), ["No such file: 'app.yaml'"]);
+            assert_eq!(emitter.suggested_exit_code(), 1);
+        }
+
+        #[cfg(feature = "python")]
+        #[test]
+        fn retcode_suggested() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter(&dir);
+            assert_eq!(emitter.suggested_exit_code(), 0);
+
+            Python::with_gil(|py| {
+                let emitter = Py::new(py, emitter).unwrap();
+                let error = py
+                    .eval(
+                        c"type('Failed', (Exception,), {'retcode': 3})('boom')",
+                        None,
+                        None,
+                    )
+                    .unwrap();
+                emitter.bind(py).call_method1("error", (error,)).unwrap();
+                let code = emitter.bind(py).getattr("suggested_exit_code").unwrap();
+                assert_eq!(code.extract::<i32>().unwrap(), 3);
+            });
+        }
+
+        #[test]
+        fn custom_finish_message() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter_with(
+                &dir,
+                Verbosity::Quiet,
+                json().with_finish_message(Some("Details in {logpath}".to_string())),
+            );
+            let out = Shared::default();
+            emitter.printer.redirect(Box::new(out.clone()));
+
+            emitter.error("Something broke", true).unwrap();
+
+            let texts = out.texts();
+            assert!(texts[1].starts_with("Details in /"), "{texts:?}");
+            assert!(texts[1].ends_with("test.log"), "{texts:?}");
+        }
+    }
+
+    mod problems {
+        use super::*;
+
+        #[test]
+        fn summed_up_when_finishing() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter_with(&dir, Verbosity::Brief, json().with_problem_summary(true));
+            let out = Shared::default();
+            emitter.printer.redirect(Box::new(out.clone()));
+
+            emitter.warning("Deprecated key 'base'").unwrap();
+            emitter
+                .progress("Building", None, SpinnerHint::Auto)
+                .unwrap();
+            emitter.warning("No license set").unwrap();
+            emitter.error("Build failed", false).unwrap();
+
+            let texts = out.texts();
+            assert_eq!(
+                texts.last().unwrap(),
+                "2 warnings: Deprecated key 'base'; No license set\n1 error: Build failed"
+            );
+        }
+    }
+
+    mod duration {
+        use super::*;
+
+        #[test]
+        fn reported_when_finishing() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter_with(&dir, Verbosity::Quiet, json().with_report_duration(true));
+            emitter.ended_ok().unwrap();
+
+            let log = fs::read_to_string(emitter.log_filepath()).unwrap();
+            assert!(log.contains("[PROGRESS] Completed in 0s\n"), "{log}");
+        }
+
+        #[test]
+        fn compact() {
+            assert_eq!(compact_duration(Duration::from_millis(4200)), "4s");
+            assert_eq!(compact_duration(Duration::from_secs(192)), "3m 12s");
+            assert_eq!(compact_duration(Duration::from_secs(3605)), "1h 0m 5s");
+        }
+    }
+
+    #[cfg(unix)]
+    mod signals {
+        use super::*;
+
+        #[test]
+        fn interruption_logged() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter(&dir);
+            let (send, received) = crossbeam_channel::bounded(1);
+
+            emitter
+                .handle_signals(move |signal| _ = send.send(signal))
+                .unwrap();
+            signal_hook::low_level::raise(signal_hook::consts::SIGTERM).unwrap();
+
+            let signal = received.recv_timeout(Duration::from_secs(10)).unwrap();
+            assert_eq!(signal, signal_hook::consts::SIGTERM);
+            let log = fs::read_to_string(emitter.log_filepath()).unwrap();
+            assert!(log.contains("[SIGNAL] Interrupted\n"), "{log}");
+            emitter.ended_ok().unwrap();
+        }
+    }
+
+    mod stream {
+        use super::*;
+
+        #[test]
+        fn joined_when_finishing() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter_with(&dir, Verbosity::Verbose, json());
+
+            // Left open until after finishing, as if forgotten by the application
+            let stream = emitter.open_stream(Some("Building"), false).unwrap();
+            let mut writer = stream.writer().unwrap();
+            writeln!(writer, "make: done").unwrap();
+            drop(writer);
+            emitter.ended_ok().unwrap();
+            drop(stream);
+
+            let log = fs::read_to_string(emitter.log_filepath()).unwrap();
+            assert!(log.contains("[PROGRESS] Building"));
+            assert!(log.contains("[PROGRESS] :: make: done"));
+        }
+
+        #[test]
+        fn fails_when_finished() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter(&dir);
+            emitter.ended_ok().unwrap();
+
+            let result = emitter.open_stream(Some("Building"), false);
+            assert!(matches!(result, Err(Error::Finished)));
+        }
+
+        #[test]
+        fn prefixed_when_scoped() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter_with(&dir, Verbosity::Verbose, json());
+
+            emitter.with_prefix("part:foo", |e| {
+                let mut stream = e.open_stream(Some("Building"), false).unwrap();
+                let mut writer = stream.writer().unwrap();
+                writeln!(writer, "make: failed").unwrap();
+                drop(writer);
+                stream.close();
+                e.error("Build failed", false).unwrap();
+            });
+
+            let log = fs::read_to_string(emitter.log_filepath()).unwrap();
+            assert!(log.contains("[PROGRESS] [part:foo] Building\n"), "{log}");
+            assert!(
+                log.contains("[PROGRESS] [part:foo] :: make: failed\n"),
+                "{log}"
+            );
+            assert!(log.contains("[ERROR] [part:foo] Build failed\n"), "{log}");
+        }
+    }
+
+    mod log {
+        use super::*;
+
+        #[test]
+        fn directory_created() {
+            let dir = tempfile::tempdir().unwrap();
+            let log_filepath = dir.path().join("log").join("test.log");
+            let emitter = emitter_at(&log_filepath, Verbosity::Quiet, json());
+            emitter.ended_ok().unwrap();
+
+            assert_eq!(emitter.log_filepath(), log_filepath);
+            assert!(log_filepath.is_file());
+        }
+
+        #[test]
+        fn relocated() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter(&dir);
+            let new_path = dir.path().join("artifacts").join("build.log");
+
+            emitter.message("before").unwrap();
+            emitter.relocate_log(&new_path).unwrap();
+            emitter.message("after").unwrap();
+            emitter.ended_ok().unwrap();
+
+            assert!(!dir.path().join("test.log").exists());
+            let log = fs::read_to_string(&new_path).unwrap();
+            assert!(log.contains("[INFO] before"));
+            assert!(log.contains("[LOG] moved here from"));
+            assert!(log.contains("[INFO] after"));
+        }
+
+        #[test]
+        fn every_message_tagged() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter(&dir);
+
+            emitter.message("a message").unwrap();
+            emitter
+                .progress("some progress", None, SpinnerHint::Auto)
+                .unwrap();
+            emitter.verbose("verbose info").unwrap();
+            emitter.debug("debug info").unwrap();
+            emitter.trace("trace info").unwrap();
+            emitter.warning("a warning").unwrap();
+            emitter.ended_ok().unwrap();
+
+            let tags = log_lines(&emitter);
+            assert_eq!(
+                tags,
+                [
+                    "INFO] a message",
+                    "PROGRESS] some progress",
+                    "DEBUG] verbose info",
+                    "DEBUG] debug info",
+                    "TRACE] trace info",
+                    "WARNING] a warning",
+                ]
+            );
+        }
+
+        #[test]
+        fn warnings_flushed() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter(&dir);
+
+            emitter.debug("buffered").unwrap();
+            emitter.warning("careful").unwrap();
+
+            let log = fs::read_to_string(emitter.log_filepath()).unwrap();
+            assert!(log.ends_with("[WARNING] careful\n"));
+            emitter.ended_ok().unwrap();
+        }
+
+        #[test]
+        fn header_first() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter_with(
+                &dir,
+                Verbosity::Quiet,
+                json()
+                    .with_appname(Some("snapcraft".to_string()))
+                    .with_version(Some("8.0.1".to_string())),
+            );
+            emitter.message("packing").unwrap();
+            emitter.ended_ok().unwrap();
+
+            let lines = log_lines(&emitter);
+            assert_eq!(
+                lines[..2],
+                ["HEADER] app: snapcraft", "HEADER] version: 8.0.1"]
+            );
+            assert!(lines[2].starts_with("HEADER] argv: ["));
+            assert!(lines[3].starts_with("HEADER] platform: "));
+            assert!(lines[4].starts_with("HEADER] started: "));
+            assert_eq!(lines[5], "INFO] packing");
+        }
+
+        #[test]
+        fn ordered_per_thread() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter(&dir);
+
+            std::thread::scope(|scope| {
+                for thread in 0..4 {
+                    let emitter = &emitter;
+                    scope.spawn(move || {
+                        for n in 0..50 {
+                            emitter.message(&format!("{thread} {n}")).unwrap();
+                        }
+                    });
+                }
+            });
+            emitter.ended_ok().unwrap();
+
+            let log = fs::read_to_string(emitter.log_filepath()).unwrap();
+            let lines: Vec<_> = log
+                .lines()
+                .map(|line| line.split_once("] ").unwrap().1)
+                .collect();
+            for thread in 0..4 {
+                let numbers: Vec<_> = lines
+                    .iter()
+                    .filter_map(|line| line.strip_prefix(&format!("{thread} ")))
+                    .map(|n| n.parse::<usize>().unwrap())
+                    .collect();
+                assert_eq!(numbers, (0..50).collect::<Vec<_>>());
+            }
+        }
+
+        #[test]
+        fn prefixes_nest() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter(&dir);
+
+            emitter.with_prefix("part:foo", |e| {
+                e.message("building").unwrap();
+                e.with_prefix("step", |e| e.warning("careful").unwrap());
+            });
+            emitter.message("done").unwrap();
+            emitter.ended_ok().unwrap();
+
+            let log = fs::read_to_string(emitter.log_filepath()).unwrap();
+            let lines: Vec<_> = log
+                .lines()
+                .map(|line| line.split_once("] ").unwrap().1)
+                .collect();
+            assert_eq!(
+                lines,
+                ["[part:foo] building", "[part:foo] [step] careful", "done"]
+            );
+        }
+
+        #[test]
+        fn prefixes_per_thread() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = Arc::new(emitter(&dir));
+
+            let threads: Vec<_> = (0..4)
+                .map(|thread| {
+                    let emitter = Arc::clone(&emitter);
+                    std::thread::spawn(move || {
+                        emitter.with_prefix(&thread.to_string(), |e| {
+                            for n in 0..50 {
+                                e.message(&format!("{thread} {n}")).unwrap();
+                            }
+                        });
+                    })
+                })
+                .collect();
+            for thread in threads {
+                thread.join().unwrap();
+            }
+            emitter.ended_ok().unwrap();
+
+            let log = fs::read_to_string(emitter.log_filepath()).unwrap();
+            for line in log.lines() {
+                let text = line.split_once("] ").unwrap().1;
+                let (prefix, rest) = text.split_once("] ").unwrap();
+                assert_eq!(prefix[1..], rest[..prefix.len() - 1]);
+            }
+        }
+
+        #[test]
+        fn fields_suffixed() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter_with(&dir, Verbosity::Verbose, json());
+            let out = Shared::default();
+            emitter.printer.redirect(Box::new(out.clone()));
+
+            let fields = Fields::from([
+                ("part".to_string(), "foo".to_string()),
+                ("step".to_string(), "pull sources".to_string()),
+            ]);
+            emitter.with_fields(fields, |e| e.message("building").unwrap());
+            emitter.ended_ok().unwrap();
+
+            let log = fs::read_to_string(emitter.log_filepath()).unwrap();
+            assert!(log.contains(r#"[INFO] building part=foo step="pull sources""#));
+            // JSON output carries the fields apart from the text
+            let buf = out.0.lock().unwrap();
+            let first: Message =
+                serde_json::from_slice(buf.split(|b| *b == b'\n').next().unwrap()).unwrap();
+            assert_eq!(first.text(), "building");
+            assert_eq!(first.fields()["part"], "foo");
+        }
+
+        #[test]
+        fn section_markers() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter(&dir);
+
+            emitter.begin_section("Build", false).unwrap();
+            emitter
+                .progress("compiling", None, SpinnerHint::Auto)
+                .unwrap();
+            emitter.end_section().unwrap();
+            emitter.ended_ok().unwrap();
+
+            let lines = log_lines(&emitter);
+            assert_eq!(
+                lines[..3],
+                [
+                    "SECTION] begin \"Build\"",
+                    "PROGRESS] Build",
+                    "PROGRESS]   compiling",
+                ]
+            );
+            assert!(lines[3].starts_with("PROGRESS] Build completed in "));
+            assert!(lines[4].starts_with("SECTION] end \"Build\" after "));
+        }
+
+        #[test]
+        fn secrets_masked() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter(&dir);
+
+            emitter.add_secret("hunter2".to_string());
+            emitter.message("password is hunter2").unwrap();
+            emitter.ended_ok().unwrap();
+
+            let log = fs::read_to_string(emitter.log_filepath()).unwrap();
+            assert!(!log.contains("hunter2"));
+        }
     }
 }
@@ -0,0 +1,100 @@
+//! The error type for Craft CLI.
+
+use std::fmt;
+
+/// An error raised by Craft CLI.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading or writing a file or stream failed.
+    Io(std::io::Error),
+
+    /// An argument was given an unusable value.
+    InvalidArgument(String),
+
+    /// The printer thread panicked, with the given message.
+    PrinterPanicked(String),
+
+    /// The printer's queue was full, and messages were set to fail rather than wait.
+    QueueFull,
+
+    /// Another emitter controls the terminal, and emitters were set to be exclusive.
+    EmitterActive,
+
+    /// The emitter in use was asked for, but none was set.
+    NoEmitter,
+
+    /// An emitter controller was used before its emitter was initialized.
+    Uninitialized,
+
+    /// The emitter was used for something needing it running after it finished.
+    Finished,
+}
+
+/// A result with Craft CLI's error type.
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => e.fmt(f),
+            Error::InvalidArgument(msg) => f.write_str(msg),
+            Error::PrinterPanicked(msg) => write!(f, "The printer thread panicked: {msg}"),
+            Error::QueueFull => f.write_str("The printer's queue is full"),
+            Error::EmitterActive => {
+                f.write_str("Another emitter is already running, and in control of the terminal")
+            }
+            Error::NoEmitter => f.write_str("No emitter was set, with set_emitter"),
+            Error::Uninitialized => {
+                f.write_str("The emitter must be initialized with init() before it's used")
+            }
+            Error::Finished => f.write_str("The emitter has already finished"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::InvalidArgument(_)
+            | Error::PrinterPanicked(_)
+            | Error::QueueFull
+            | Error::EmitterActive
+            | Error::NoEmitter
+            | Error::Uninitialized
+            | Error::Finished => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+#[cfg(feature = "python")]
+pyo3::create_exception!(
+    craft_cli._rs.emitter,
+    PrinterError,
+    pyo3::exceptions::PyRuntimeError,
+    "The printer thread failed, so messages may not have been shown."
+);
+
+#[cfg(feature = "python")]
+impl From<Error> for pyo3::PyErr {
+    fn from(e: Error) -> Self {
+        match e {
+            // PyO3 maps these onto the matching `OSError` subclasses
+            Error::Io(e) => e.into(),
+            Error::InvalidArgument(msg) => pyo3::exceptions::PyValueError::new_err(msg),
+            e @ Error::PrinterPanicked(_) => PrinterError::new_err(e.to_string()),
+            // As from a non-blocking file that has no room to write to
+            e @ Error::QueueFull => pyo3::exceptions::PyBlockingIOError::new_err(e.to_string()),
+            e @ (Error::EmitterActive
+            | Error::NoEmitter
+            | Error::Uninitialized
+            | Error::Finished) => pyo3::exceptions::PyRuntimeError::new_err(e.to_string()),
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! The emitter in use, for code deep inside an application to emit on without being
+//! handed it.
+//!
+//! From Rust, it's a static shared by the whole process. From Python, it's a context
+//! variable, so that threads and asyncio tasks can each override it for the code they
+//! run, such as with a scoped emitter prefixing everything a plugin emits.
+
+use std::sync::{Arc, PoisonError, RwLock};
+
+#[cfg(feature = "python")]
+use pyo3::{
+    Bound, Py, PyAny, PyResult, Python, pyclass, pyfunction, pymethods,
+    sync::GILOnceCell,
+    types::{PyAnyMethods, PyTypeMethods},
+};
+
+use crate::emitter::Emitter;
+#[cfg(feature = "python")]
+use crate::emitter::ScopedEmitter;
+#[cfg(feature = "python")]
+use crate::error::Error;
+
+/// The emitter set for the whole process, if any.
+static GLOBAL: RwLock<Option<Arc<Emitter>>> = RwLock::new(None);
+
+/// The context variable holding the emitter in use on the Python side.
+#[cfg(feature = "python")]
+static CONTEXT_VAR: GILOnceCell<Py<PyAny>> = GILOnceCell::new();
+
+/// The emitter set with `set_emitter`, if any.
+#[must_use]
+pub fn get_emitter() -> Option<Arc<Emitter>> {
+    GLOBAL
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Set the emitter for code anywhere in the process to emit on, giving back the one
+/// set before, if any.
+///
+/// Emitting only needs a shared reference, but finishing doesn't: take the emitter
+/// back with `set_emitter(None)` to finish it.
+pub fn set_emitter(emitter: Option<Arc<Emitter>>) -> Option<Arc<Emitter>> {
+    std::mem::replace(
+        &mut *GLOBAL.write().unwrap_or_else(PoisonError::into_inner),
+        emitter,
+    )
+}
+
+/// The context variable holding the emitter in use, created on first use.
+#[cfg(feature = "python")]
+fn context_var(py: Python<'_>) -> PyResult<&Bound<'_, PyAny>> {
+    let var = CONTEXT_VAR.get_or_try_init(py, || {
+        let var = py
+            .import("contextvars")?
+            .getattr("ContextVar")?
+            .call1(("craft_cli_emitter",))?;
+        PyResult::Ok(var.unbind())
+    })?;
+    Ok(var.bind(py))
+}
+
+/// Get the emitter in use in the current context, either an `Emitter` or a
+/// `ScopedEmitter`.
+#[cfg(feature = "python")]
+#[pyfunction(name = "get_emitter")]
+pub fn py_get_emitter(py: Python<'_>) -> PyResult<Bound<'_, PyAny>> {
+    let emitter = context_var(py)?.call_method1("get", (py.None(),))?;
+    if emitter.is_none() {
+        return Err(Error::NoEmitter.into());
+    }
+    Ok(emitter)
+}
+
+/// Set the emitter in use in the current context and the ones it creates from now on.
+///
+/// Gives a token to reset the context to the emitter it had with `reset_emitter`.
+#[cfg(feature = "python")]
+#[pyfunction(name = "set_emitter")]
+pub fn py_set_emitter<'py>(emitter: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+    if !emitter.is_instance_of::<Emitter>() && !emitter.is_instance_of::<ScopedEmitter>() {
+        return Err(Error::InvalidArgument(format!(
+            "Expected an Emitter or a ScopedEmitter, got {}",
+            emitter.get_type().name()?
+        ))
+        .into());
+    }
+    context_var(emitter.py())?.call_method1("set", (emitter,))
+}
+
+/// Put back the emitter the context had before `set_emitter` gave `token`.
+#[cfg(feature = "python")]
+#[pyfunction(name = "reset_emitter")]
+pub fn py_reset_emitter(token: &Bound<'_, PyAny>) -> PyResult<()> {
+    context_var(token.py())?.call_method1("reset", (token,))?;
+    Ok(())
+}
+
+/// Use a child of the current emitter, prefixing messages with `[prefix]`, for the
+/// code run in a `with` block.
+#[cfg(feature = "python")]
+#[pyfunction]
+pub fn scoped_emitter(prefix: String) -> EmitterScope {
+    EmitterScope {
+        prefix,
+        token: None,
+    }
+}
+
+/// A context manager overriding the emitter in use with a scoped one, from
+/// `scoped_emitter`.
+#[cfg(feature = "python")]
+#[pyclass]
+pub struct EmitterScope {
+    /// The prefix of the scoped emitter.
+    prefix: String,
+
+    /// The token to reset the context with, once entered.
+    token: Option<Py<PyAny>>,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl EmitterScope {
+    /// Override the emitter in use, giving the scoped one.
+    fn __enter__<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let scoped = py_get_emitter(py)?.call_method1("scoped", (&self.prefix,))?;
+        self.token = Some(py_set_emitter(&scoped)?.unbind());
+        Ok(scoped)
+    }
+
+    /// Put back the emitter in use before, even if the block raised.
+    fn __exit__(
+        &mut self,
+        py: Python<'_>,
+        _exc_type: &Bound<'_, PyAny>,
+        _exc_value: &Bound<'_, PyAny>,
+        _traceback: &Bound<'_, PyAny>,
+    ) -> PyResult<()> {
+        match self.token.take() {
+            Some(token) => py_reset_emitter(token.bind(py)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::emitter;
+
+    mod global {
+        use super::*;
+
+        #[test]
+        fn set_and_taken_back() {
+            let dir = tempfile::tempdir().unwrap();
+            assert!(set_emitter(Some(Arc::new(emitter(&dir)))).is_none());
+
+            get_emitter().unwrap().message("deep inside").unwrap();
+
+            let emitter = Arc::into_inner(set_emitter(None).unwrap()).unwrap();
+            emitter.ended_ok().unwrap();
+            assert!(get_emitter().is_none());
+            let log = std::fs::read_to_string(emitter.log_filepath()).unwrap();
+            assert!(log.contains("] deep inside\n"), "{log}");
+        }
+    }
+
+    #[cfg(feature = "python")]
+    mod context {
+        use super::*;
+        use crate::test_utils::assert_error_type;
+
+        #[test]
+        fn scoped_override() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter(&dir);
+
+            Python::with_gil(|py| {
+                // Each test thread starts with a fresh context
+                let err = py_get_emitter(py).unwrap_err();
+                assert_error_type::<pyo3::exceptions::PyRuntimeError>(&err);
+
+                let emitter = Py::new(py, emitter).unwrap().into_bound(py).into_any();
+                let token = py_set_emitter(&emitter).unwrap();
+                let mut scope = scoped_emitter("plugin".to_string());
+                let scoped = scope.__enter__(py).unwrap();
+                assert!(scoped.is(&py_get_emitter(py).unwrap()));
+                assert!(scoped.is_instance_of::<ScopedEmitter>());
+                scoped.call_method1("message", ("hello",)).unwrap();
+                let none = py.None().into_bound(py);
+                scope.__exit__(py, &none, &none, &none).unwrap();
+                assert!(emitter.is(&py_get_emitter(py).unwrap()));
+
+                py_reset_emitter(&token).unwrap();
+                emitter.call_method0("ended_ok").unwrap();
+            });
+
+            let log = std::fs::read_to_string(dir.path().join("test.log")).unwrap();
+            assert!(log.contains("] [plugin] hello\n"), "{log}");
+        }
+    }
+}
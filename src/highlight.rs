@@ -0,0 +1,155 @@
+//! Highlighting of the URLs and file paths in message text, so that they stand out of
+//! long error output.
+//!
+//! URLs are recognized by their scheme, and paths only if they exist, as plenty of
+//! words with slashes in them are neither. Both are underlined, and made into
+//! hyperlinks where the terminal supports them.
+
+use std::borrow::Cow;
+use std::path::Path;
+
+use crate::printer::{Target, hyperlink};
+use crate::utils::file_url;
+
+/// Start underlining, leaving the rest of the style as it is.
+const UNDERLINE: &str = "\x1b[4m";
+
+/// Stop underlining, leaving the rest of the style as it is.
+const NO_UNDERLINE: &str = "\x1b[24m";
+
+/// The schemes of the URLs highlighted.
+const SCHEMES: [&str; 3] = ["https://", "http://", "file://"];
+
+/// Characters quoting or enclosing a link, rather than part of it.
+const OPENERS: [char; 4] = ['\'', '"', '(', '<'];
+
+/// Characters enclosing or ending the sentence after a link, rather than part of it.
+const CLOSERS: [char; 8] = ['\'', '"', ')', '>', ',', '.', ':', ';'];
+
+/// Highlight the URLs and existing paths in text for a stream, underlining them only if
+/// it supports colors.
+pub fn highlight(text: &str, target: Target) -> Cow<'_, str> {
+    let styled = match target {
+        Target::Stdout => console::colors_enabled(),
+        Target::Stderr => console::colors_enabled_stderr(),
+        Target::Null => false,
+    };
+    highlight_with(text, target, styled)
+}
+
+/// Highlight the URLs and existing paths in text for a stream, underlining them only if
+/// `styled` is true.
+pub fn highlight_with(text: &str, target: Target, styled: bool) -> Cow<'_, str> {
+    let mut highlighted = String::new();
+    let mut copied = 0;
+    for (start, word) in words(text) {
+        let Some((offset, link, url)) = linkable(word) else {
+            continue;
+        };
+        let start = start + offset;
+        highlighted.push_str(&text[copied..start]);
+        let linked = hyperlink(&url, link, target);
+        if styled {
+            highlighted.push_str(UNDERLINE);
+            highlighted.push_str(&linked);
+            highlighted.push_str(NO_UNDERLINE);
+        } else {
+            highlighted.push_str(&linked);
+        }
+        copied = start + link.len();
+    }
+    if copied == 0 {
+        return Cow::Borrowed(text);
+    }
+    highlighted.push_str(&text[copied..]);
+    Cow::Owned(highlighted)
+}
+
+/// The words of text, separated by whitespace, with where each starts.
+fn words(text: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(s)) => {
+                words.push((s, &text[s..i]));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        words.push((s, &text[s..]));
+    }
+    words
+}
+
+/// The link in a word, if it has one: where it starts in the word, its text, and the
+/// URL it points to.
+fn linkable(word: &str) -> Option<(usize, &str, String)> {
+    // Words already styled or linked are left as they are
+    if word.contains('\x1b') {
+        return None;
+    }
+    let link = word.trim_start_matches(OPENERS);
+    let offset = word.len() - link.len();
+    let link = link.trim_end_matches(CLOSERS);
+    if SCHEMES
+        .iter()
+        .any(|scheme| link.len() > scheme.len() && link.starts_with(scheme))
+    {
+        return Some((offset, link, link.to_string()));
+    }
+    if link.contains('/') && Path::new(link).exists() {
+        let path = std::path::absolute(link).ok()?;
+        return Some((offset, link, file_url(&path)));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod highlight_with {
+        use super::*;
+
+        #[test]
+        fn urls() {
+            let text = "See https://example.com/docs. Or (http://example.com)";
+
+            assert_eq!(
+                highlight_with(text, Target::Null, true),
+                "See \x1b[4mhttps://example.com/docs\x1b[24m. \
+                 Or (\x1b[4mhttp://example.com\x1b[24m)"
+            );
+            assert_eq!(
+                highlight_with("https:// and/or", Target::Null, true),
+                "https:// and/or"
+            );
+        }
+
+        #[test]
+        fn existing_paths() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("snapcraft.yaml");
+            std::fs::write(&path, "").unwrap();
+            let missing = dir.path().join("missing.yaml");
+            let text = format!("Invalid '{}', not {}", path.display(), missing.display());
+
+            assert_eq!(
+                highlight_with(&text, Target::Null, true),
+                format!(
+                    "Invalid '\x1b[4m{}\x1b[24m', not {}",
+                    path.display(),
+                    missing.display()
+                )
+            );
+            assert!(matches!(
+                highlight_with(&text, Target::Null, false),
+                Cow::Owned(plain) if plain == text
+            ));
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Mirroring of log lines to the system journal.
+//!
+//! Craft tools running as services or under orchestration have their logs collected
+//! by the system rather than read from a file. When enabled, every line written to the
+//! log file is also sent to systemd-journald, or to syslog where there's no journald,
+//! with a priority matching its level.
+#![cfg(unix)]
+
+use std::{io, os::unix::net::UnixDatagram, path::Path, sync::Arc};
+
+/// Where journald listens for entries in its native protocol.
+const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Where syslog listens for messages.
+const SYSLOG_SOCKET: &str = "/dev/log";
+
+/// The syslog facility of user-level messages.
+const FACILITY_USER: u8 = 1;
+
+/// How entries are written to the socket.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    /// Journald's native protocol, of one `KEY=value` field per line.
+    Journald,
+
+    /// The traditional syslog protocol, of one line per message.
+    Syslog,
+}
+
+/// A connection to the system journal.
+#[derive(Clone, Debug)]
+pub struct Journal {
+    /// The socket entries are sent to.
+    socket: Arc<UnixDatagram>,
+
+    /// How entries are written to the socket.
+    protocol: Protocol,
+
+    /// The name entries are filed under, usually the application's.
+    pub identifier: String,
+}
+
+impl Journal {
+    /// Connect to journald, or to syslog where there's no journald.
+    ///
+    /// # Errors
+    ///
+    /// Fails if neither is listening.
+    pub fn connect(identifier: String) -> io::Result<Self> {
+        Self::connect_to(
+            Path::new(JOURNALD_SOCKET),
+            Protocol::Journald,
+            identifier.clone(),
+        )
+        .or_else(|_| Self::connect_to(Path::new(SYSLOG_SOCKET), Protocol::Syslog, identifier))
+    }
+
+    /// Connect to the socket at `path`, speaking the given protocol.
+    fn connect_to(path: &Path, protocol: Protocol, identifier: String) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(Self {
+            socket: Arc::new(socket),
+            protocol,
+            identifier,
+        })
+    }
+
+    /// Send a log line with the given level, such as `WARNING`.
+    ///
+    /// This is best-effort, as the log file remains the record of the run: a full or
+    /// restarted journal never stops the application.
+    pub fn send(&self, level: &str, text: &str) {
+        let priority = priority(level);
+        let entry = match self.protocol {
+            Protocol::Journald => {
+                let mut entry = Vec::new();
+                for (key, value) in [
+                    ("PRIORITY", priority.to_string().as_str()),
+                    ("SYSLOG_IDENTIFIER", &self.identifier),
+                    ("CRAFT_LEVEL", level),
+                    ("MESSAGE", text),
+                ] {
+                    write_field(&mut entry, key, value);
+                }
+                entry
+            }
+            Protocol::Syslog => format!(
+                "<{}>{}[{}]: [{level}] {text}",
+                FACILITY_USER * 8 + priority,
+                self.identifier,
+                std::process::id()
+            )
+            .into_bytes(),
+        };
+        _ = self.socket.send(&entry);
+    }
+}
+
+/// The syslog priority of a log level.
+fn priority(level: &str) -> u8 {
+    match level {
+        "ERROR" => 3,
+        "WARNING" => 4,
+        "SUCCESS" => 5,
+        "DEBUG" | "TRACE" => 7,
+        _ => 6,
+    }
+}
+
+/// Write a field of a journald entry.
+///
+/// Values spanning several lines are written with their length up front, as the
+/// protocol requires.
+fn write_field(entry: &mut Vec<u8>, key: &str, value: &str) {
+    entry.extend_from_slice(key.as_bytes());
+    if value.contains('\n') {
+        entry.push(b'\n');
+        entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        entry.push(b'=');
+    }
+    entry.extend_from_slice(value.as_bytes());
+    entry.push(b'\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod journal {
+        use super::*;
+
+        /// Listen on a socket in `dir`, and connect a journal to it.
+        fn listen(dir: &tempfile::TempDir, protocol: Protocol) -> (UnixDatagram, Journal) {
+            let path = dir.path().join("journal.socket");
+            let listener = UnixDatagram::bind(&path).unwrap();
+            let journal = Journal::connect_to(&path, protocol, "testcraft".to_string()).unwrap();
+            (listener, journal)
+        }
+
+        /// Receive a single entry.
+        fn receive(listener: &UnixDatagram) -> Vec<u8> {
+            let mut buf = vec![0; 1024];
+            let len = listener.recv(&mut buf).unwrap();
+            buf.truncate(len);
+            buf
+        }
+
+        #[test]
+        fn journald_fields() {
+            let dir = tempfile::tempdir().unwrap();
+            let (listener, journal) = listen(&dir, Protocol::Journald);
+
+            journal.send("WARNING", "disk\nfull");
+
+            let mut expected =
+                b"PRIORITY=4\nSYSLOG_IDENTIFIER=testcraft\nCRAFT_LEVEL=WARNING\nMESSAGE\n".to_vec();
+            expected.extend_from_slice(&9_u64.to_le_bytes());
+            expected.extend_from_slice(b"disk\nfull\n");
+            assert_eq!(receive(&listener), expected);
+        }
+
+        #[test]
+        fn syslog_line() {
+            let dir = tempfile::tempdir().unwrap();
+            let (listener, journal) = listen(&dir, Protocol::Syslog);
+
+            journal.send("DEBUG", "polling");
+
+            let line = String::from_utf8(receive(&listener)).unwrap();
+            assert!(line.starts_with("<15>testcraft["));
+            assert!(line.ends_with("]: [DEBUG] polling"));
+        }
+    }
+}
@@ -13,16 +13,68 @@
 //! Craft CLI
 //!
 //! The perfect foundation for your CLI situation.
+//!
+//! The Python bindings are built with the `python` feature, which is on by default.
+//! Without it, the emitter can be used directly from Rust.
+//!
+//! With the `tracing` feature, `EmitterLayer` shows `tracing` events and spans through
+//! the emitter.
 
+#[cfg(feature = "python")]
 use pyo3::{prelude::*, pymodule};
 
+mod color;
+#[cfg(feature = "python")]
+mod controller;
+#[cfg(feature = "python")]
 mod craft_cli_utils;
+mod diagnostics;
 mod emitter;
+mod error;
+mod global;
+mod highlight;
+mod journal;
+mod log_bridge;
+#[cfg(feature = "python")]
+mod markdown;
+mod notify;
+#[cfg(feature = "python")]
+mod observer;
 mod printer;
+mod remote;
+mod screen;
+mod sd_notify;
+mod secrets;
+mod settings;
+mod stream;
 mod test_utils;
+#[cfg(feature = "python")]
+mod testing;
+mod theme;
+mod timestamp;
+#[cfg(feature = "tracing")]
+mod tracing_layer;
 mod utils;
+mod wire;
+
+pub use emitter::{Emitter, Scoped};
+pub use error::{Error, Result};
+pub use global::{get_emitter, set_emitter};
+pub use printer::{Message, MessageType, OutputFormat, SpinnerHint, Stats, Target, Verbosity};
+#[cfg(unix)]
+pub use remote::Listener;
+pub use screen::Screen;
+pub use settings::{Attention, Ci, Exclusive, Overflow, Settings};
+pub use stream::Stream;
+pub use theme::Theme;
+pub use timestamp::Clock;
+#[cfg(feature = "tracing")]
+pub use tracing_layer::EmitterLayer;
+pub use utils::{find_similar, humanize_io_error};
+pub use wire::SCHEMA_VERSION;
 
 /// A Python module implemented in Rust.
+#[cfg(feature = "python")]
 #[pymodule]
 mod _rs {
     use crate::utils::fix_imports;
@@ -35,6 +87,9 @@ mod _rs {
     #[pymodule_export]
     use crate::emitter::emitter;
 
+    #[pymodule_export]
+    use crate::testing::testing;
+
     /// Fix syspath for easier importing in Python.
     #[pymodule_init]
     fn init(m: &Bound<'_, PyModule>) -> PyResult<()> {
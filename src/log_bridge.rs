@@ -0,0 +1,258 @@
+//! Forwarding of records from the `log` crate to the emitter.
+//!
+//! Rust dependencies, and this crate itself, log through the `log` facade. Once an
+//! emitter is created, their records are written to its log file like any other
+//! message, and shown as debug or trace messages when the verbosity allows.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write as _},
+    sync::{Arc, Mutex, OnceLock, PoisonError, RwLock},
+};
+
+use crossbeam_channel::Sender;
+
+#[cfg(unix)]
+use crate::journal::Journal;
+use crate::printer::{Fields, Message, MessageType, QueueBudget, SpinnerHint, Target, Verbosity};
+use crate::secrets::Secrets;
+use crate::timestamp::Timestamps;
+
+/// Where records are forwarded to, while an emitter is running.
+static SINK: RwLock<Option<Sink>> = RwLock::new(None);
+
+#[cfg(test)]
+thread_local! {
+    /// Where records from the current thread are forwarded to instead, so that a test
+    /// doesn't see those of the emitters of tests running alongside.
+    static LOCAL: std::cell::RefCell<Option<Sink>> = const { std::cell::RefCell::new(None) };
+}
+
+/// The parts of an emitter that records are forwarded to.
+pub struct Sink {
+    /// The emitter's log file.
+    pub log_handle: Arc<Mutex<BufWriter<File>>>,
+
+    /// A channel to the emitter's printer.
+    pub printer: Sender<Message>,
+
+    /// How much text is queued for the emitter's printer.
+    pub budget: Arc<QueueBudget>,
+
+    /// The emitter's verbosity, deciding which records are shown.
+    pub verbosity: Verbosity,
+
+    /// How the emitter reads and formats timestamps.
+    pub timestamps: Timestamps,
+
+    /// The values the emitter masks in all output.
+    pub secrets: Secrets,
+
+    /// Whether shown messages are timestamped whatever the verbosity, as in continuous
+    /// integration.
+    pub timestamped: bool,
+
+    /// Whether times are shown as a placeholder, so that output is the same on every
+    /// run.
+    #[cfg(feature = "tracing")]
+    pub deterministic: bool,
+
+    /// The system journal the emitter mirrors its log to, if enabled.
+    #[cfg(unix)]
+    pub journal: Option<Journal>,
+}
+
+/// The `log` implementation forwarding to the current sink.
+struct Bridge;
+
+impl log::Log for Bridge {
+    fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        with_sink(|sink| sink.forward(record));
+    }
+
+    fn flush(&self) {
+        with_sink(|sink| {
+            _ = sink
+                .log_handle
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .flush();
+        });
+    }
+}
+
+impl Sink {
+    /// Write a record to the log file, and show it if the verbosity allows.
+    fn forward(&self, record: &log::Record<'_>) {
+        let model = match record.level() {
+            log::Level::Trace => MessageType::Trace(),
+            _ => MessageType::Debug(),
+        };
+        let target = self.target_for(model);
+        self.emit(
+            model,
+            target,
+            &format!("{}: {}", record.target(), record.args()),
+        );
+    }
+
+    /// Where a message of the given type is shown at the emitter's verbosity.
+    pub fn target_for(&self, model: MessageType) -> Target {
+        use Verbosity::*;
+        let shown = match model {
+            MessageType::Trace() => matches!(self.verbosity, Trace),
+            MessageType::Debug() => matches!(self.verbosity, Debug | Trace),
+            MessageType::Info() => matches!(self.verbosity, Verbose | Debug | Trace),
+            _ => !matches!(self.verbosity, Quiet),
+        };
+        if shown { Target::Stderr } else { Target::Null }
+    }
+
+    /// Write a line to the log file tagged with the level of its message, and send the
+    /// message to the printer unless it's hidden.
+    pub fn emit(&self, model: MessageType, target: Target, text: &str) {
+        self.emit_styled(model, target, text, text);
+    }
+
+    /// Like `emit`, but showing `styled` instead of `plain`, such as the same text with
+    /// ANSI sequences that don't belong in the log.
+    pub fn emit_styled(&self, model: MessageType, target: Target, plain: &str, styled: &str) {
+        self.write_log(&model.name().to_uppercase(), &self.secrets.mask(plain));
+        let text = self.secrets.mask(styled);
+
+        if !matches!(target, Target::Null) {
+            // Timestamps are shown like the emitter's, from debug verbosity up
+            let text = if self.timestamped
+                || matches!(self.verbosity, Verbosity::Debug | Verbosity::Trace)
+            {
+                format!("{} {text}", self.timestamps.now())
+            } else {
+                text.into_owned()
+            };
+            let msg = Message {
+                text: text.into(),
+                model,
+                target,
+                spinner: SpinnerHint::Auto,
+                fields: Fields::new(),
+                urgent: false,
+            };
+            if self.budget.admit(&msg) {
+                // The printer only hangs up once the emitter is finishing
+                _ = self.printer.send(msg);
+            }
+        }
+    }
+
+    /// Write a line to the log file, tagged with the time and the given level.
+    pub fn write_log(&self, level: &str, text: &str) {
+        let mut log = self
+            .log_handle
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        // There's nowhere left to report a failure to log to
+        _ = writeln!(log, "{} [{level}] {text}", self.timestamps.now());
+        #[cfg(unix)]
+        if let Some(journal) = &self.journal {
+            journal.send(level, text);
+        }
+    }
+}
+
+/// Run `f` on the sink of the running emitter, if there is one.
+pub fn with_sink(f: impl FnOnce(&Sink)) {
+    #[cfg(test)]
+    if LOCAL.with_borrow(Option::is_some) {
+        LOCAL.with_borrow(|sink| sink.as_ref().map(f));
+        return;
+    }
+    if let Some(sink) = &*SINK.read().unwrap_or_else(PoisonError::into_inner) {
+        f(sink);
+    }
+}
+
+/// Forward records to `sink` from now on, replacing any previous one.
+///
+/// The bridge is installed as the `log` crate's logger the first time, unless the
+/// application already installed another, which is then left alone.
+pub fn attach(sink: Sink) {
+    static BRIDGE: Bridge = Bridge;
+    static INSTALLED: OnceLock<bool> = OnceLock::new();
+
+    if *INSTALLED.get_or_init(|| log::set_logger(&BRIDGE).is_ok()) {
+        // Dependencies can be very chatty at trace level, so those records are only
+        // kept when they would be shown
+        log::set_max_level(match sink.verbosity {
+            Verbosity::Trace => log::LevelFilter::Trace,
+            _ => log::LevelFilter::Debug,
+        });
+    }
+    *SINK.write().unwrap_or_else(PoisonError::into_inner) = Some(sink);
+}
+
+/// Forward the records of the current thread to `sink` instead of the running
+/// emitter's, or stop doing so if `None`.
+#[cfg(all(test, feature = "tracing"))]
+pub fn attach_local(sink: Option<Sink>) {
+    LOCAL.set(sink);
+}
+
+/// Stop forwarding records to the emitter with the given log file, as it's finishing.
+///
+/// Records are left going to another emitter created since.
+pub fn detach(log_handle: &Arc<Mutex<BufWriter<File>>>) {
+    let mut sink = SINK.write().unwrap_or_else(PoisonError::into_inner);
+    if sink
+        .as_ref()
+        .is_some_and(|sink| Arc::ptr_eq(&sink.log_handle, log_handle))
+    {
+        *sink = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::sink;
+
+    mod sink {
+        use super::*;
+
+        #[test]
+        fn forward() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("test.log");
+            let (mut sink, shown) = sink(&path, Verbosity::Debug);
+            sink.secrets.add("hunter2".to_string());
+
+            for (level, text) in [
+                (log::Level::Info, "token is hunter2"),
+                (log::Level::Trace, "polling"),
+            ] {
+                sink.forward(
+                    &log::Record::builder()
+                        .level(level)
+                        .target("ureq")
+                        .args(format_args!("{text}"))
+                        .build(),
+                );
+            }
+            sink.log_handle.lock().unwrap().flush().unwrap();
+
+            let log = std::fs::read_to_string(&path).unwrap();
+            assert!(log.contains("[DEBUG] ureq: token is *****"));
+            assert!(log.contains("[TRACE] ureq: polling"));
+            // Only the debug record is shown at debug verbosity
+            let shown: Vec<Message> = shown.try_iter().collect();
+            assert_eq!(shown.len(), 1);
+            assert!(shown[0].text().ends_with("ureq: token is *****"));
+        }
+    }
+}
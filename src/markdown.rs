@@ -0,0 +1,160 @@
+//! Rendering of a constrained Markdown subset into styled terminal text.
+//!
+//! Supported syntax:
+//! - Headings (`# Title`, `## Subtitle`, ...)
+//! - Bold spans (`**bold**`)
+//! - Code spans (`` `code` ``)
+//! - Bullet lists (`- item` or `* item`)
+//!
+//! Anything else is passed through untouched.
+
+use console::Style;
+
+/// Render Markdown text for stderr, styling it only if stderr supports colors.
+pub fn render(text: &str) -> String {
+    render_with(text, console::colors_enabled_stderr())
+}
+
+/// Render Markdown text, applying terminal styling only if `styled` is true.
+///
+/// When unstyled, the Markdown markers are still stripped so the output reads as
+/// plain text.
+pub fn render_with(text: &str, styled: bool) -> String {
+    text.lines()
+        .map(|line| render_line(line, styled))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render a single line, handling block-level syntax before inline spans.
+fn render_line(line: &str, styled: bool) -> String {
+    let trimmed = line.trim_start();
+    let indent = &line[..line.len() - trimmed.len()];
+
+    // Headings: one or more '#' followed by a space
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    if level > 0
+        && let Some(title) = trimmed[level..].strip_prefix(' ')
+    {
+        let mut style = Style::new().bold().force_styling(styled);
+        if level == 1 {
+            style = style.underlined();
+        }
+        return format!("{indent}{}", style.apply_to(render_inline(title, styled)));
+    }
+
+    // Bullet lists
+    if let Some(item) = trimmed
+        .strip_prefix("- ")
+        .or_else(|| trimmed.strip_prefix("* "))
+    {
+        let bullet = if styled { "•" } else { "-" };
+        return format!("{indent}{bullet} {}", render_inline(item, styled));
+    }
+
+    render_inline(line, styled)
+}
+
+/// Render the inline spans (bold and code) of a piece of text.
+fn render_inline(text: &str, styled: bool) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    loop {
+        let next_code = rest.find('`');
+        let next_bold = rest.find("**");
+
+        match (next_code, next_bold) {
+            // A code span comes first
+            (Some(start), bold) if bold.is_none_or(|b| start < b) => {
+                // Runs of several backticks, like code fences, are kept as is
+                let run = rest[start..].bytes().take_while(|b| *b == b'`').count();
+                if run > 1 {
+                    result.push_str(&rest[..start + run]);
+                    rest = &rest[start + run..];
+                    continue;
+                }
+                let after = &rest[start + 1..];
+                let Some(len) = after.find('`') else {
+                    // A lone backtick is kept as is, and later spans still render
+                    result.push_str(&rest[..=start]);
+                    rest = after;
+                    continue;
+                };
+                result.push_str(&rest[..start]);
+                let style = Style::new().cyan().force_styling(styled);
+                result.push_str(&style.apply_to(&after[..len]).to_string());
+                rest = &after[len + 1..];
+            }
+            // A bold span comes first
+            (_, Some(start)) => {
+                let after = &rest[start + 2..];
+                let Some(len) = after.find("**") else {
+                    result.push_str(&rest[..start + 2]);
+                    rest = after;
+                    continue;
+                };
+                result.push_str(&rest[..start]);
+                let style = Style::new().bold().force_styling(styled);
+                let inner = render_inline(&after[..len], styled);
+                result.push_str(&style.apply_to(inner).to_string());
+                rest = &after[len + 2..];
+            }
+            _ => break,
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod render_with {
+        use super::*;
+
+        #[test]
+        fn plain() {
+            let text = "# Title\nUse `snap` to **install**:\n- first\n  * second\nnot a#heading";
+
+            assert_eq!(
+                render_with(text, false),
+                "Title\nUse snap to install:\n- first\n  - second\nnot a#heading"
+            );
+        }
+
+        #[test]
+        fn styled() {
+            let rendered = render_with("## Sub\n- **bold** `code`", true);
+
+            assert_eq!(
+                rendered,
+                "\u{1b}[1mSub\u{1b}[0m\n• \u{1b}[1mbold\u{1b}[0m \u{1b}[36mcode\u{1b}[0m"
+            );
+        }
+
+        #[test]
+        fn unclosed_spans() {
+            assert_eq!(render_with("a `b and **c", false), "a `b and **c");
+        }
+
+        #[test]
+        fn backtick_runs_kept() {
+            assert_eq!(
+                render_with("```fenced``` and `code`", false),
+                "```fenced``` and code"
+            );
+        }
+
+        #[test]
+        fn spans_after_unclosed_marker() {
+            assert_eq!(render_with("a `b and **c**", false), "a `b and c");
+            assert_eq!(
+                render_with("a ` b **c**", true),
+                "a ` b \u{1b}[1mc\u{1b}[0m"
+            );
+        }
+    }
+}
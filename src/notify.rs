@@ -0,0 +1,115 @@
+//! Desktop notifications for the end of long-running tasks.
+
+use std::{
+    ffi::OsString,
+    process::{Command, Stdio},
+};
+
+/// Whether someone could see a desktop notification about the run.
+///
+/// Runs that aren't attended from a terminal, like in CI, have no one to tell. Over
+/// SSH, the notification would show on the remote machine rather than in front of
+/// the user, so it's only sent if a display was forwarded.
+pub fn wanted() -> bool {
+    decide(
+        |name| std::env::var_os(name),
+        console::user_attended_stderr(),
+    )
+}
+
+/// Decide whether to notify, given the environment and whether stderr is attended.
+fn decide(var: impl Fn(&str) -> Option<OsString>, attended: bool) -> bool {
+    if !attended {
+        return false;
+    }
+    // Empty values count as unset, as with most conventions on environment variables
+    let var = |name| var(name).filter(|value| !value.is_empty());
+    if var("SSH_CONNECTION").is_none() && var("SSH_TTY").is_none() {
+        return true;
+    }
+    // macOS notifications always show on the machine's own desktop
+    !cfg!(target_os = "macos") && (var("DISPLAY").is_some() || var("WAYLAND_DISPLAY").is_some())
+}
+
+/// Fire a desktop notification.
+///
+/// This is best-effort: it shells out to the platform's notification tool and silently
+/// does nothing if that tool is missing or fails, as a missed notification should never
+/// break the application.
+pub fn send(summary: &str, body: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {} with title {}",
+            applescript_quote(body),
+            applescript_quote(summary)
+        );
+        let mut command = Command::new("osascript");
+        command.args(["-e", &script]);
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.args(["--app-name", summary, summary, body]);
+        command
+    };
+
+    // Don't wait for the tool to finish; the notification daemon takes it from here.
+    _ = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+}
+
+/// Quote a string as an `AppleScript` string literal.
+fn applescript_quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::env;
+
+    mod decide {
+        use super::*;
+
+        #[test]
+        fn attended_locally() {
+            assert!(decide(env(&[]), true));
+        }
+
+        #[test]
+        fn unattended() {
+            assert!(!decide(env(&[]), false));
+            assert!(!decide(env(&[("DISPLAY", ":0")]), false));
+        }
+
+        #[test]
+        fn over_ssh() {
+            assert!(!decide(
+                env(&[("SSH_CONNECTION", "10.0.0.1 22 10.0.0.2 22")]),
+                true
+            ));
+            assert!(!decide(
+                env(&[("SSH_TTY", "/dev/pts/0"), ("DISPLAY", "")]),
+                true
+            ));
+            assert_eq!(
+                decide(
+                    env(&[("SSH_TTY", "/dev/pts/0"), ("DISPLAY", "localhost:10.0")]),
+                    true
+                ),
+                cfg!(not(target_os = "macos"))
+            );
+        }
+    }
+
+    mod applescript_quote {
+        use super::*;
+
+        #[test]
+        fn escaped() {
+            assert_eq!(applescript_quote(r#"say "hi" \o/"#), r#""say \"hi\" \\o/""#);
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! Python callbacks that observe every message emitted.
+
+use std::{
+    sync::{Arc, Mutex, PoisonError, mpsc},
+    thread::{self, JoinHandle},
+};
+
+use pyo3::{Py, PyAny, Python};
+
+use crate::printer::Message;
+
+/// A set of Python callbacks invoked for every message emitted.
+///
+/// Callbacks are run from a dedicated thread so that slow observers never hold up
+/// the code emitting the messages.
+#[derive(Default)]
+pub struct Observers {
+    /// The registered callbacks.
+    callbacks: Arc<Mutex<Vec<Py<PyAny>>>>,
+
+    /// A channel to send messages to the observer thread, once started.
+    channel: Mutex<Option<mpsc::Sender<Message>>>,
+
+    /// A handle on the observer thread, once started.
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Observers {
+    /// Register a callback, starting the observer thread if needed.
+    pub fn register(&self, callback: Py<PyAny>) {
+        self.callbacks
+            .lock()
+            .expect("Observer callbacks were poisoned")
+            .push(callback);
+
+        let mut channel = self.channel.lock().unwrap_or_else(PoisonError::into_inner);
+        if channel.is_none() {
+            let (send, recv) = mpsc::channel();
+            let callbacks = Arc::clone(&self.callbacks);
+            *channel = Some(send);
+            *self.handle.lock().unwrap_or_else(PoisonError::into_inner) =
+                Some(thread::spawn(move || Self::run(&recv, &callbacks)));
+        }
+    }
+
+    /// Pass a message on to the observers, if there are any.
+    pub fn notify(&self, msg: &Message) {
+        let channel = self.channel.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(chan) = &*channel {
+            // The observer thread only stops once the channel is closed, so this can't
+            // fail
+            _ = chan.send(msg.clone());
+        }
+    }
+
+    /// Stop the observer thread, waiting for it to handle any pending messages.
+    ///
+    /// The observer thread needs the GIL to run callbacks, so this must not be called
+    /// while holding it.
+    pub fn stop(&self) {
+        _ = self
+            .channel
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        let handle = self
+            .handle
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(handle) = handle {
+            // Any panic came from a callback, which has already been reported
+            _ = handle.join();
+        }
+    }
+
+    /// Invoke the callbacks for every message received until the channel is closed.
+    fn run(recv: &mpsc::Receiver<Message>, callbacks: &Mutex<Vec<Py<PyAny>>>) {
+        for msg in recv {
+            Python::with_gil(|py| {
+                let callbacks: Vec<Py<PyAny>> = callbacks
+                    .lock()
+                    .expect("Observer callbacks were poisoned")
+                    .iter()
+                    .map(|callback| callback.clone_ref(py))
+                    .collect();
+
+                for callback in callbacks {
+                    if let Err(e) = callback.call1(py, (msg.model.name(), &*msg.text)) {
+                        // Report like any other exception raised in a Python callback
+                        e.write_unraisable(py, Some(callback.bind(py)));
+                    }
+                }
+            });
+        }
+    }
+}
+
+impl Drop for Observers {
+    /// Close the channel without joining, as the GIL may be held while dropping.
+    fn drop(&mut self) {
+        _ = self
+            .channel
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::printer::{Fields, MessageType, SpinnerHint, Target};
+    use pyo3::{ffi::c_str, types::PyAnyMethods};
+
+    mod observers {
+        use super::*;
+
+        #[test]
+        fn callbacks_receive_messages() {
+            let observers = Observers::default();
+            let seen = Python::with_gil(|py| {
+                let seen = pyo3::types::PyList::empty(py);
+                let callback = py
+                    .eval(
+                        c_str!("lambda seen: lambda kind, text: seen.append((kind, text))"),
+                        None,
+                        None,
+                    )
+                    .unwrap()
+                    .call1((&seen,))
+                    .unwrap();
+                observers.register(callback.unbind());
+                seen.unbind()
+            });
+
+            observers.notify(&Message {
+                text: "Careful!".into(),
+                model: MessageType::Warning(),
+                target: Target::Stderr,
+                spinner: SpinnerHint::Auto,
+                fields: Fields::new(),
+                urgent: false,
+            });
+            observers.stop();
+
+            Python::with_gil(|py| {
+                let seen: Vec<(String, String)> = seen.extract(py).unwrap();
+                assert_eq!(seen, [("warning".to_string(), "Careful!".to_string())]);
+            });
+        }
+    }
+}
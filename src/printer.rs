@@ -1,19 +1,37 @@
 //! The `Printer` module for handling messages to a terminal.
 
 use std::{
+    any::Any,
+    borrow::Cow,
+    collections::{BTreeMap, VecDeque},
+    fmt,
+    io::{self, Write},
     sync::{
-        LazyLock, OnceLock,
-        mpsc::{self, RecvTimeoutError},
+        Arc, LazyLock, Mutex, PoisonError, RwLock, RwLockReadGuard,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
     },
     thread::{self, JoinHandle},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use pyo3::{PyErr, PyResult, pyclass};
+use crossbeam_channel::{Receiver, RecvError, RecvTimeoutError, Sender, TrySendError};
+#[cfg(feature = "python")]
+use pyo3::{pyclass, pymethods};
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostics;
+use crate::error::{Error, Result};
+use crate::screen::Screen;
+use crate::settings::{Ci, Settings};
+use crate::theme::Theme;
+use crate::wire::{WireMessage, WireType};
 
 /// Representation of which stream should be targeted by a message.
-#[derive(Debug, Clone, Copy)]
-#[pyclass]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "python", pyclass)]
+// The unsafe code is PyO3's glue, which deserialization never goes through
+#[cfg_attr(feature = "python", expect(clippy::unsafe_derive_deserialize))]
 pub enum Target {
     /// Target the stdout stream.
     Stdout,
@@ -35,10 +53,72 @@ impl From<Target> for indicatif::ProgressDrawTarget {
     }
 }
 
+impl Target {
+    /// Get a terminal handle for this target, if it has one.
+    fn term(self) -> Option<console::Term> {
+        match self {
+            Target::Stdout => Some(console::Term::stdout()),
+            Target::Stderr => Some(console::Term::stderr()),
+            Target::Null => None,
+        }
+    }
+}
+
+/// Check whether the terminal behind a stream can render OSC 8 hyperlinks.
+///
+/// There is no reliable way to query this from the terminal itself, so this relies on
+/// the environment variables set by terminals known to support them. Setting
+/// `FORCE_HYPERLINK` overrides the detection either way.
+pub fn supports_hyperlinks(target: Target) -> bool {
+    let is_term = target.term().is_some_and(|term| term.is_term());
+    hyperlinks_decide(|name| std::env::var(name).ok(), is_term)
+}
+
+/// Decide whether a stream can render OSC 8 hyperlinks, given the environment.
+fn hyperlinks_decide(var: impl Fn(&str) -> Option<String>, is_term: bool) -> bool {
+    if let Some(force) = var("FORCE_HYPERLINK") {
+        return force != "0";
+    }
+    if !is_term {
+        return false;
+    }
+    if var("DOMTERM").is_some() || var("WT_SESSION").is_some() || var("KONSOLE_VERSION").is_some() {
+        return true;
+    }
+    if var("VTE_VERSION")
+        .and_then(|v| v.parse::<u32>().ok())
+        .is_some_and(|v| v >= 5000)
+    {
+        return true;
+    }
+    if var("TERM_PROGRAM").is_some_and(|program| {
+        ["Hyper", "iTerm.app", "terminology", "WezTerm", "vscode", "ghostty"]
+            .contains(&program.as_str())
+    }) {
+        return true;
+    }
+    var("TERM").is_some_and(|term| {
+        ["xterm-kitty", "alacritty"].contains(&term.as_str()) || term.starts_with("foot")
+    })
+}
+
+/// Format a hyperlink for a stream, falling back to the plain text if the terminal
+/// can't render it.
+pub fn hyperlink(url: &str, text: &str, target: Target) -> String {
+    if supports_hyperlinks(target) {
+        format!("\x1b]8;;{url}\x1b\\{text}\x1b]8;;\x1b\\")
+    } else {
+        text.to_string()
+    }
+}
+
 /// Types of message for printing.
 #[non_exhaustive]
-#[derive(Clone, Copy, Debug)]
-#[pyclass]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "WireType", try_from = "WireType")]
+#[cfg_attr(feature = "python", pyclass)]
+// The unsafe code is PyO3's glue, which deserialization never goes through
+#[cfg_attr(feature = "python", expect(clippy::unsafe_derive_deserialize))]
 pub enum MessageType {
     /// A persistent progress message that will remain on the console.
     ///
@@ -65,25 +145,173 @@ pub enum MessageType {
     /// An informational message.
     Info(),
 
+    /// A message marking that something was done successfully, usually the end of a
+    /// run.
+    Success(),
+
     /// Signals to create a progress bar.
     ProgBar(Target, u64),
 }
 
+impl MessageType {
+    /// A short, stable name for the message type.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            MessageType::ProgPersistent(..) | MessageType::ProgEphemeral(..) => "progress",
+            MessageType::Warning() => "warning",
+            MessageType::Error() => "error",
+            MessageType::Debug() => "debug",
+            MessageType::Trace() => "trace",
+            MessageType::Info() => "info",
+            MessageType::Success() => "success",
+            MessageType::ProgBar(..) => "progress_bar",
+        }
+    }
+}
+
+/// The Python string forms of message types, for debugging.
+#[cfg(feature = "python")]
+#[pymethods]
+impl MessageType {
+    /// The message type as it's constructed, such as
+    /// `MessageType.ProgEphemeral(Target.Stderr)`.
+    fn __repr__(&self) -> String {
+        match *self {
+            MessageType::ProgPersistent(target) => {
+                format!("MessageType.ProgPersistent(Target.{target:?})")
+            }
+            MessageType::ProgEphemeral(target) => {
+                format!("MessageType.ProgEphemeral(Target.{target:?})")
+            }
+            MessageType::ProgBar(target, total) => {
+                format!("MessageType.ProgBar(Target.{target:?}, {total})")
+            }
+            other => format!("MessageType.{other:?}()"),
+        }
+    }
+
+    /// The short, stable name of the message type, such as `progress`.
+    fn __str__(&self) -> &'static str {
+        self.name()
+    }
+}
+
+/// Counts of what the printer did, for tests and for summaries at the end of a run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+pub struct Stats {
+    /// How many messages of each type were sent to the printer, keyed by the type's
+    /// name.
+    pub messages: BTreeMap<&'static str, u64>,
+
+    /// How many bytes of lines were written out.
+    pub bytes_written: u64,
+
+    /// How many times an ephemeral line was overwritten.
+    pub overwrites: u64,
+
+    /// How many lines were dropped without being written, as they were overwritten
+    /// before they could be drawn, over a rate limit, or shed from a queue that grew
+    /// too large.
+    pub dropped: u64,
+}
+
+/// When to show a spinner for a message that isn't followed by another one.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+// The unsafe code is PyO3's glue, which deserialization never goes through
+#[cfg_attr(feature = "python", expect(clippy::unsafe_derive_deserialize))]
+pub enum SpinnerHint {
+    /// Spin once the next message is a few seconds late.
+    #[default]
+    Auto,
+
+    /// Spin right away, for operations known to be slow.
+    Immediate,
+
+    /// Never spin, for waits where an animation would be misleading, such as waiting
+    /// for user input.
+    Never,
+}
+
+/// Structured key/value fields attached to a message, such as the part it's about.
+pub type Fields = BTreeMap<String, String>;
+
 /// A single message to be sent, and what type of message it is.
-#[derive(Clone, Debug)]
+///
+/// See the `wire` module for how messages are serialized.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(into = "WireMessage", try_from = "WireMessage")]
 pub struct Message {
     /// The message to be printed.
-    pub(crate) text: String,
+    ///
+    /// Shared rather than owned, as messages are cloned as they pass through the
+    /// printer and its observers.
+    pub(crate) text: Arc<str>,
 
     /// The type of message to send.
     pub(crate) model: MessageType,
 
     /// Where the message should be sent.
     pub(crate) target: Target,
+
+    /// When to show a spinner while waiting for the next message.
+    pub(crate) spinner: SpinnerHint,
+
+    /// Structured fields attached to the message.
+    pub(crate) fields: Fields,
+
+    /// Whether the message is an error counted as urgent when sent to the printer, so
+    /// that it's only counted down once handled.
+    pub(crate) urgent: bool,
 }
 
 impl Message {
+    /// The text of the message.
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The type of the message.
+    #[must_use]
+    pub fn model(&self) -> MessageType {
+        self.model
+    }
+
+    /// Where the message should be sent.
+    #[must_use]
+    pub fn target(&self) -> Target {
+        self.target
+    }
+
+    /// When to show a spinner while waiting for the next message.
+    #[must_use]
+    pub fn spinner(&self) -> SpinnerHint {
+        self.spinner
+    }
+
+    /// Structured fields attached to the message.
+    #[must_use]
+    pub fn fields(&self) -> &Fields {
+        &self.fields
+    }
+
+    /// Send the message to another stream, keeping its type.
+    pub fn retarget(&mut self, target: Target) {
+        self.target = target;
+        self.model = match self.model {
+            MessageType::ProgPersistent(_) => MessageType::ProgPersistent(target),
+            MessageType::ProgEphemeral(_) => MessageType::ProgEphemeral(target),
+            MessageType::ProgBar(_, total) => MessageType::ProgBar(target, total),
+            other => other,
+        };
+    }
+
     /// Calculate which stream a message should go to based on its model.
+    #[must_use]
     pub fn determine_stream(&self, mode: Verbosity) -> Option<Target> {
         use self::Target::*;
         use self::Verbosity::*;
@@ -92,6 +320,7 @@ impl Message {
             | MessageType::ProgEphemeral(target)
             | MessageType::ProgBar(target, ..) => target.into(),
             MessageType::Warning() | MessageType::Error() => Stderr.into(),
+            MessageType::Success() => Stdout.into(),
             MessageType::Debug() | MessageType::Trace() | MessageType::Info() => match mode {
                 Verbose => Stdout.into(),
                 _ => None,
@@ -102,8 +331,8 @@ impl Message {
 
 /// Verbosity modes.
 #[non_exhaustive]
-#[derive(Clone, Copy)]
-#[pyclass]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "python", pyclass)]
 pub enum Verbosity {
     /// Quiet output. Most messages should not be output at all.
     Quiet,
@@ -124,41 +353,510 @@ pub enum Verbosity {
     Trace,
 }
 
+impl Verbosity {
+    /// The name of the mode, as given on the command line.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Verbosity::Quiet => "quiet",
+            Verbosity::Brief => "brief",
+            Verbosity::Verbose => "verbose",
+            Verbosity::Debug => "debug",
+            Verbosity::Trace => "trace",
+        }
+    }
+}
+
+/// The Python string forms of verbosity modes, for debugging.
+#[cfg(feature = "python")]
+#[pymethods]
+impl Verbosity {
+    /// The mode as it's accessed, such as `Verbosity.Brief`.
+    #[expect(clippy::trivially_copy_pass_by_ref)] // Required by PyO3
+    fn __repr__(&self) -> String {
+        format!("Verbosity.{self:?}")
+    }
+
+    /// The name of the mode, as given on the command line, such as `brief`.
+    #[expect(clippy::trivially_copy_pass_by_ref)] // Required by PyO3
+    fn __str__(&self) -> &'static str {
+        self.name()
+    }
+}
+
+/// How messages are written to the terminal.
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "python", pyclass)]
+pub enum OutputFormat {
+    /// Rendered for people to read, with styling, spinners and overwritten lines.
+    #[default]
+    Human,
+
+    /// Every message is written to stdout as a line of JSON, for programs to read.
+    ///
+    /// See the `wire` module for the schema.
+    Json,
+}
+
+/// Commands to control the printer, sent separately from the messages to print.
+///
+/// Controls are only applied once every message sent before them has been handled.
+pub enum Control {
+    /// Change the verbosity mode.
+    SetMode(Verbosity),
+
+    /// Write every message from now on as JSON lines to the given writer, releasing
+    /// the terminal.
+    Redirect(Box<dyn Write + Send>),
+
+    /// Update the current progress bar.
+    Bar(BarUpdate),
+
+    /// Restyle the current progress bar.
+    BarStyle(indicatif::ProgressStyle),
+
+    /// Write out everything pending, then acknowledge through the given channel.
+    Flush(Sender<()>),
+
+    /// Write out everything pending and leave the terminal as it was found, as the
+    /// program was interrupted, then acknowledge through the given channel.
+    Interrupt(Sender<()>),
+
+    /// Write a line to stderr as it is, for a continuous integration system to pick up.
+    Marker(String),
+
+    /// Write out everything pending and stop drawing, leaving the terminal to someone
+    /// else, then acknowledge through the given channel. Messages sent from then on are
+    /// held back until resuming.
+    Pause(Sender<()>),
+
+    /// Take the terminal back after pausing, and show the messages held back.
+    Resume,
+
+    /// Change the text of the ephemeral progress message spun on, or show the message
+    /// as any other if there's no spinner.
+    UpdateProgress(Message),
+}
+
+/// An update to the current progress bar.
+#[derive(Clone, Copy, Debug)]
+pub enum BarUpdate {
+    /// Move the bar forward, for producers that report increments.
+    Advance(u64),
+
+    /// Move the bar to a position, for producers that report cumulative progress.
+    Set(u64),
+
+    /// Remove the bar, leaving its message behind.
+    Finish,
+}
+
+/// Something that happened while the printer was waiting.
+enum Event {
+    /// A message to print was received.
+    Message(Message),
+
+    /// A control command was received.
+    Control(Control),
+
+    /// Nothing happened before the timeout.
+    Timeout,
+
+    /// The message channel was closed, so printing is over.
+    Disconnected,
+}
+
+/// The terminal's output streams, written so that messages keep their order across
+/// them.
+///
+/// Whatever is pending on one stream is flushed before switching to the other, so
+/// that buffering along the way can't reorder messages when both streams end up in
+/// the same place, such as a file or pipe.
+///
+/// A stream found closed, such as a pipe into a program that has exited, is left
+/// alone from then on rather than failing every message after.
+struct OrderedWriter {
+    /// A handle on stdout.
+    stdout: Handle,
+
+    /// A handle on stderr.
+    stderr: Handle,
+
+    /// The stream last written to, if any.
+    last: Option<Target>,
+
+    /// Whether stdout was found closed.
+    stdout_closed: bool,
+
+    /// Whether stderr was found closed.
+    stderr_closed: bool,
+
+    /// Whether either stream was found closed, shared with the `Printer`.
+    closed: Arc<AtomicBool>,
+}
+
+impl OrderedWriter {
+    /// Get handles on the terminal's streams, or on a virtual terminal standing in for
+    /// both.
+    fn new(screen: Option<&Screen>) -> Self {
+        let (stdout, stderr) = match screen {
+            Some(screen) => (
+                Handle::Screen(screen.clone()),
+                Handle::Screen(screen.clone()),
+            ),
+            None => (
+                Handle::Term(console::Term::stdout()),
+                Handle::Term(console::Term::stderr()),
+            ),
+        };
+        Self {
+            stdout,
+            stderr,
+            last: None,
+            stdout_closed: false,
+            stderr_closed: false,
+            closed: Arc::default(),
+        }
+    }
+
+    /// Run an operation on the handle for a stream, if it has one that's still open,
+    /// flushing the other stream first if switching from it.
+    fn with_term(
+        &mut self,
+        target: Target,
+        op: impl FnOnce(&Handle) -> io::Result<()>,
+    ) -> Result<()> {
+        if self.is_closed(target) {
+            return Ok(());
+        }
+        match self.last.replace(target) {
+            Some(Target::Stdout) if matches!(target, Target::Stderr) => {
+                let flushed = self.stdout.flush();
+                self.check(Target::Stdout, flushed)?;
+            }
+            Some(Target::Stderr) if matches!(target, Target::Stdout) => {
+                let flushed = self.stderr.flush();
+                self.check(Target::Stderr, flushed)?;
+            }
+            _ => {}
+        }
+        let result = match target {
+            Target::Stdout => op(&self.stdout),
+            Target::Stderr => op(&self.stderr),
+            Target::Null => return Ok(()),
+        };
+        self.check(target, result)
+    }
+
+    /// Whether a stream can't be written to, either because it was found closed or
+    /// because there's no stream at all.
+    fn is_closed(&self, target: Target) -> bool {
+        match target {
+            Target::Stdout => self.stdout_closed,
+            Target::Stderr => self.stderr_closed,
+            Target::Null => true,
+        }
+    }
+
+    /// Pass on the result of writing to a stream, unless it failed because the stream
+    /// was closed, in which case it stops being written to.
+    fn check(&mut self, target: Target, result: io::Result<()>) -> Result<()> {
+        match result {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                match target {
+                    Target::Stdout => self.stdout_closed = true,
+                    Target::Stderr => self.stderr_closed = true,
+                    Target::Null => {}
+                }
+                diagnostics::note(|| format!("{target:?} was closed"));
+                self.closed.store(true, Ordering::Relaxed);
+                Ok(())
+            }
+            result => Ok(result?),
+        }
+    }
+
+    /// Get the handle for a stream that's an interactive terminal, to control the
+    /// cursor with, if there is one.
+    fn cursor_term(&self) -> Option<&Handle> {
+        [&self.stderr, &self.stdout]
+            .into_iter()
+            .find(|term| term.is_term())
+    }
+
+    /// Flush both streams.
+    fn flush(&mut self) -> Result<()> {
+        let flushed = self.stdout.flush();
+        self.check(Target::Stdout, flushed)?;
+        let flushed = self.stderr.flush();
+        self.check(Target::Stderr, flushed)
+    }
+
+    /// Whether a stream is an interactive terminal, whose lines can be rewritten.
+    fn is_term(&self, target: Target) -> bool {
+        match target {
+            Target::Stdout => self.stdout.is_term(),
+            Target::Stderr => self.stderr.is_term(),
+            Target::Null => false,
+        }
+    }
+
+    /// How many columns wide a stream's terminal is, if it's an interactive one.
+    fn width(&self, target: Target) -> Option<usize> {
+        let handle = match target {
+            Target::Stdout => &self.stdout,
+            Target::Stderr => &self.stderr,
+            Target::Null => return None,
+        };
+        handle.is_term().then(|| handle.width())
+    }
+
+    /// Get where spinners and progress bars draw for a stream.
+    fn draw_target(&self, target: Target) -> indicatif::ProgressDrawTarget {
+        match (target, &self.stdout, &self.stderr) {
+            (Target::Stdout, Handle::Screen(screen), _)
+            | (Target::Stderr, _, Handle::Screen(screen)) => {
+                indicatif::ProgressDrawTarget::term_like(Box::new(screen.clone()))
+            }
+            _ => target.into(),
+        }
+    }
+}
+
+/// A handle on one of the terminal's streams.
+enum Handle {
+    /// The real stream.
+    Term(console::Term),
+
+    /// A virtual terminal standing in for it.
+    Screen(Screen),
+}
+
+impl Handle {
+    /// Whether the stream is an interactive terminal.
+    fn is_term(&self) -> bool {
+        match self {
+            Handle::Term(term) => term.is_term(),
+            Handle::Screen(_) => true,
+        }
+    }
+
+    /// How many columns wide the terminal is.
+    fn width(&self) -> usize {
+        match self {
+            Handle::Term(term) => usize::from(term.size().1),
+            Handle::Screen(screen) => usize::from(screen.size().1),
+        }
+    }
+
+    /// Write a line of text.
+    fn write_line(&self, line: &str) -> io::Result<()> {
+        match self {
+            Handle::Term(term) => term.write_line(line),
+            Handle::Screen(screen) => {
+                screen.write_line(line);
+                Ok(())
+            }
+        }
+    }
+
+    /// Clear the `n` lines above the cursor.
+    fn clear_last_lines(&self, n: usize) -> io::Result<()> {
+        match self {
+            Handle::Term(term) => term.clear_last_lines(n),
+            Handle::Screen(screen) => {
+                screen.clear_last_lines(n);
+                Ok(())
+            }
+        }
+    }
+
+    /// Write text without ending the line, such as an escape sequence.
+    fn write_str(&self, text: &str) -> io::Result<()> {
+        match self {
+            Handle::Term(term) => term.write_str(text),
+            Handle::Screen(screen) => {
+                screen.write_str(text);
+                Ok(())
+            }
+        }
+    }
+
+    /// Hide the cursor.
+    fn hide_cursor(&self) -> io::Result<()> {
+        match self {
+            Handle::Term(term) => term.hide_cursor(),
+            Handle::Screen(_) => Ok(()),
+        }
+    }
+
+    /// Show the cursor.
+    fn show_cursor(&self) -> io::Result<()> {
+        match self {
+            Handle::Term(term) => term.show_cursor(),
+            Handle::Screen(_) => Ok(()),
+        }
+    }
+
+    /// Flush what was written.
+    fn flush(&self) -> io::Result<()> {
+        match self {
+            Handle::Term(term) => term.flush(),
+            Handle::Screen(_) => Ok(()),
+        }
+    }
+}
+
 /// An internal printer object meant to print from a separate thread.
 struct InnerPrinter {
     /// A channel upon which messages can be read.
     ///
     /// If this channel is found to be closed, the program is over and this struct
     /// should begin to destruct itself.
-    channel: mpsc::Receiver<Message>,
+    channel: Receiver<Message>,
 
-    /// A handle on stdout.
-    stdout: console::Term,
+    /// A channel upon which control commands can be read.
+    control: Receiver<Control>,
 
-    /// A handle on stderr.
-    stderr: console::Term,
+    /// The terminal's output streams.
+    out: OrderedWriter,
 
     /// Printing verbosity mode.
     mode: Verbosity,
 
-    /// A flag indicating if the previous line should be overwritten when printing
-    /// the next.
-    needs_overwrite: bool,
+    /// The styles to print each type of message with.
+    theme: Theme,
+
+    /// How spinners, redraws and the cursor are handled.
+    settings: Settings,
+
+    /// How messages are written.
+    format: OutputFormat,
+
+    /// Where JSON lines are written in the JSON output format.
+    json_out: Box<dyn Write + Send>,
+
+    /// The stream holding rows that should be overwritten when printing the next,
+    /// and how many, if any.
+    overwrite: Option<(Target, usize)>,
+
+    /// How many terminal rows the last message written took up, once wrapped.
+    drawn: usize,
+
+    /// A message that was received early while coalescing ephemeral messages, and
+    /// still needs handling.
+    backlog: Option<Message>,
+
+    /// How many errors were sent that haven't been handled yet, shared with the
+    /// `Printer`. Ephemeral messages ahead of them are skipped.
+    urgent: Arc<AtomicUsize>,
+
+    /// Whether the queue overflowed, so queued ephemeral messages are dropped until
+    /// it's caught up on, shared with the `Printer`.
+    shedding: Arc<AtomicBool>,
+
+    /// How many ephemeral messages were dropped to catch up on the queue, shared with
+    /// the `Printer`.
+    shed: Arc<AtomicUsize>,
+
+    /// How much text is queued, shared with every sender.
+    budget: Arc<QueueBudget>,
+
+    /// Counts of what was printed, shared with the `Printer`.
+    stats: Arc<Mutex<Stats>>,
+
+    /// The spinner shown while waiting on a slow operation, if any.
+    spinner: Option<indicatif::ProgressBar>,
+
+    /// The progress bar currently shown, if any, with the message that started it.
+    bar: Option<(indicatif::ProgressBar, Message)>,
+
+    /// The most recently printed message, in case a spinner needs to be shown for it.
+    prv_msg: Option<Message>,
+
+    /// When an ephemeral progress message was last drawn, if ever.
+    last_redraw: Option<Instant>,
+
+    /// Where to write what's still being worked on while spinning, if anywhere.
+    heartbeat: Option<Heartbeat>,
+
+    /// How many heartbeats were written since the spinner started.
+    beats: u64,
+
+    /// Whether the cursor was hidden, and needs showing again when done.
+    cursor_hidden: bool,
+
+    /// Whether the terminal's title was saved to show progress in, and needs restoring
+    /// when done.
+    title_saved: bool,
+
+    /// Whether drawing is paused, holding back messages until resumed.
+    paused: bool,
+
+    /// The last line written, if it can still be repeated, and how many times in a row
+    /// it was.
+    repeated: Option<(Message, usize)>,
+
+    /// How many lines of each rate-limited message type were shown and left out this
+    /// second, keyed by the type's name.
+    limits: BTreeMap<&'static str, RateLimit>,
 }
 
 impl InnerPrinter {
     /// Instantiate a new `InnerPrinter`.
-    pub fn new(mode: Verbosity, channel: mpsc::Receiver<Message>) -> Self {
-        let result = Self {
-            stdout: console::Term::stdout(),
-            stderr: console::Term::stderr(),
+    pub fn new(
+        mode: Verbosity,
+        theme: Theme,
+        settings: Settings,
+        channel: Receiver<Message>,
+        control: Receiver<Control>,
+    ) -> Self {
+        let mut result = Self {
+            out: OrderedWriter::new(settings.screen()),
             channel,
+            control,
             mode,
-            needs_overwrite: false,
+            theme,
+            format: settings.format(),
+            settings,
+            json_out: Box::new(std::io::stdout()),
+            overwrite: None,
+            drawn: 0,
+            backlog: None,
+            spinner: None,
+            bar: None,
+            prv_msg: None,
+            last_redraw: None,
+            heartbeat: None,
+            beats: 0,
+            cursor_hidden: false,
+            title_saved: false,
+            paused: false,
+            urgent: Arc::default(),
+            shedding: Arc::default(),
+            shed: Arc::default(),
+            budget: Arc::default(),
+            stats: Arc::default(),
+            repeated: None,
+            limits: BTreeMap::new(),
         };
 
-        // Hide the terminal cursor while taking control
-        result.stdout.hide_cursor().unwrap();
+        // Hide the terminal cursor while taking control, if there's a terminal to hide
+        // it on. Failing to hide it is only cosmetic.
+        if let OutputFormat::Human = result.format
+            && result.settings.cursor_control()
+            && let Some(term) = result.out.cursor_term()
+        {
+            result.cursor_hidden = term.hide_cursor().is_ok();
+        }
+        // Terminals that can't save their title ignore the request, and are left with
+        // the last progress shown in it
+        if let OutputFormat::Human = result.format
+            && result.settings.title()
+            && let Some(term) = result.out.cursor_term()
+        {
+            result.title_saved = term.write_str(SAVE_TITLE).is_ok();
+        }
 
         result
     }
@@ -168,59 +866,24 @@ impl InnerPrinter {
     /// This method will block execution until the the corresponding `Sender` for
     /// `self.channel` is closed. As such, it is strongly recommended to only invoke
     /// this from a dedicated thread.
-    pub fn listen(&mut self) -> PyResult<()> {
-        static MAIN_STYLE: LazyLock<indicatif::ProgressStyle> = LazyLock::new(|| {
-            indicatif::ProgressStyle::with_template("{spinner} {msg} ({elapsed})").unwrap()
-        });
-        let mut spinner: Option<indicatif::ProgressBar> = None;
-
-        let mut maybe_prv_msg: Option<Message> = None;
-
+    pub fn listen(&mut self) -> Result<()> {
+        let spinner_after = self.settings.spinner_after();
         loop {
-            // Wait the standard 3 seconds for a message
-            match self.await_message(Duration::from_secs(3)) {
-                Ok(msg) => {
-                    // If we were spinning, stop
-                    if let Some(s) = spinner.take()
-                        && let Some(mut prv_msg) = maybe_prv_msg.take()
-                    {
-                        s.finish_and_clear();
-                        self.needs_overwrite = false;
-                        let dur = indicatif::HumanDuration(s.elapsed());
-                        prv_msg.text = format!("{} (took {:#})", prv_msg.text, dur);
-                        self.handle_message(&prv_msg)?;
-                    }
-                    // Store the most recently received message in case we need to
-                    // begin displaying a spin loader
-                    maybe_prv_msg = Some(msg.clone());
-                    self.handle_message(&msg)?;
-                }
+            // Wait for a message, until the operation counts as slow
+            match self.await_event(spinner_after) {
+                Event::Message(msg) => self.receive(msg)?,
+                Event::Control(control) => self.apply_control(control)?,
                 // Break out of this loop if the channel is closed
-                Err(RecvTimeoutError::Disconnected) => break,
-                // If the three seconds elapsed, spin
-                Err(RecvTimeoutError::Timeout) => {
-                    // If we're already spinning on a message, keep waiting
-                    if spinner.is_some() {
-                        continue;
+                Event::Disconnected => break,
+                // If it's slow, spin
+                Event::Timeout => {
+                    if let OutputFormat::Human = self.format
+                        && !self.paused
+                    {
+                        self.end_limits()?;
+                        self.start_spinner(spinner_after)?;
+                        self.heartbeat();
                     }
-                    // If there's a previous message to spin on, then,
-                    spinner = maybe_prv_msg.as_ref().and_then(|prv_msg| {
-                        // If there is a stream to print to,
-                        prv_msg.determine_stream(self.mode).map(|target| {
-                            // Construct a spinner
-                            let s = indicatif::ProgressBar::with_draw_target(None, target.into())
-                                .with_message(prv_msg.text.clone())
-                                .with_style(MAIN_STYLE.clone())
-                                .with_elapsed(Duration::from_secs(3));
-
-                            // It doesn't matter which stream we clear, the line we're about to
-                            // spin is wiped either way
-                            self.stdout.clear_last_lines(1).unwrap();
-                            // Start spinning
-                            s.enable_steady_tick(Duration::from_millis(100));
-                            s
-                        })
-                    });
                 }
             }
         }
@@ -228,71 +891,901 @@ impl InnerPrinter {
         Ok(())
     }
 
-    /// Helper method for receiving a message from `self.channel`
-    fn await_message(
-        &mut self,
-        timeout: Duration,
-    ) -> ::std::result::Result<Message, RecvTimeoutError> {
-        self.channel.recv_timeout(timeout)
-    }
-
-    /// Routing method for sending a message to the proper printing logic for a given
-    /// message type.
-    fn handle_message(&mut self, msg: &Message) -> PyResult<()> {
-        use self::MessageType::*;
-        if let Target::Null = msg.target {
-            return Ok(());
+    /// Handle a newly received message.
+    fn receive(&mut self, msg: Message) -> Result<()> {
+        if msg.urgent {
+            self.urgent.fetch_sub(1, Ordering::AcqRel);
         }
-        match msg.model {
-            Info() => self.print(msg),
-            Error() => self.error(msg),
-            ProgEphemeral(..) => self.progress(msg, false),
-            ProgPersistent(..) => self.progress(msg, true),
-            _ => unimplemented!(),
+        if let OutputFormat::Json = self.format {
+            return self.write_json(&msg);
         }
-    }
 
-    /// Handle the need (or lackthereof) to overwrite the previous line.
-    fn handle_overwrite(&mut self) -> PyResult<()> {
-        if self.needs_overwrite {
-            self.stdout.clear_last_lines(1)?;
+        self.stop_spinner()?;
+        self.handle_message(&msg)?;
+        let spin_now = msg.spinner == SpinnerHint::Immediate;
+        // Store the most recently received message in case we need to
+        // begin displaying a spin loader
+        self.prv_msg = Some(msg);
+        if spin_now {
+            self.start_spinner(Duration::ZERO)?;
         }
         Ok(())
     }
 
-    /// Print a simple message to stdout.
-    fn print(&mut self, message: &Message) -> PyResult<()> {
-        self.stdout.write_line(&message.text)?;
+    /// Stop spinning, if spinning, and write the message that was spun on back in its
+    /// place.
+    fn stop_spinner(&mut self) -> Result<()> {
+        if let Some(s) = self.spinner.take()
+            && let Some(mut prv_msg) = self.prv_msg.take()
+        {
+            s.finish_and_clear();
+            self.overwrite = None;
+            if let Some(suffix) = self.theme.duration_suffix(s.elapsed()) {
+                prv_msg.text = format!("{}{suffix}", prv_msg.text).into();
+            }
+            self.handle_message(&prv_msg)?;
+        }
         Ok(())
     }
 
-    /// Print a simple message to stderr.
-    fn error(&mut self, message: &Message) -> PyResult<()> {
-        self.handle_overwrite()?;
-        self.stderr.write_line(&message.text)?;
+    /// Change the text of the ephemeral progress message spun on in place, rather than
+    /// clearing it and starting over, or handle the message as any other if there's no
+    /// spinner on one.
+    fn update_progress(&mut self, msg: Message) -> Result<()> {
+        let Some(spinner) = &self.spinner else {
+            return self.receive(msg);
+        };
+        let spun_ephemeral = self
+            .prv_msg
+            .as_ref()
+            .is_some_and(|prv_msg| matches!(prv_msg.model, MessageType::ProgEphemeral(..)));
+        if !spun_ephemeral || !matches!(msg.model, MessageType::ProgEphemeral(..)) {
+            return self.receive(msg);
+        }
+        spinner.set_message(self.render(&msg));
+        if self.title_saved
+            && let Some(term) = self.out.cursor_term()
+        {
+            term.write_str(&title_sequence(&msg.text))?;
+        }
+        self.prv_msg = Some(msg);
         Ok(())
     }
 
-    /// Print progress on a task.
-    fn progress(&mut self, message: &Message, permanent: bool) -> PyResult<()> {
-        self.handle_overwrite()?;
-        self.needs_overwrite = !permanent;
-        self.print(message)?;
+    /// Write a message to stdout as a line of JSON, unless it's meant to be hidden.
+    fn write_json(&mut self, msg: &Message) -> Result<()> {
+        if let Target::Null = msg.target {
+            return Ok(());
+        }
+        let line = serde_json::to_string(msg).expect("Messages always serialize");
+        self.stats().bytes_written += line.len() as u64 + 1;
+        match writeln!(self.json_out, "{line}").and_then(|()| self.json_out.flush()) {
+            // Whatever was reading the messages has gone, so stop writing them
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                self.json_out = Box::new(io::sink());
+                self.out.closed.store(true, Ordering::Relaxed);
+                Ok(())
+            }
+            result => Ok(result?),
+        }
+    }
+
+    /// Start spinning on the most recent message, if there's one to spin on.
+    ///
+    /// The spinner counts up from `waited`, the time already spent waiting.
+    fn start_spinner(&mut self, waited: Duration) -> Result<()> {
+        static MAIN_STYLE: LazyLock<indicatif::ProgressStyle> = LazyLock::new(|| {
+            indicatif::ProgressStyle::with_template("{spinner} {msg} ({elapsed})").unwrap()
+        });
+
+        // If spinners are off, or we're already spinning on a message or showing a
+        // bar, keep waiting
+        if !self.settings.spinners() || self.spinner.is_some() || self.bar.is_some() {
+            return Ok(());
+        }
+        diagnostics::note(|| format!("Spinning after {waited:?}"));
+        // If there's a previous message to spin on, that can be spun on, and a stream
+        // to print it to,
+        let Some(prv_msg) = &self.prv_msg else {
+            return Ok(());
+        };
+        if prv_msg.spinner == SpinnerHint::Never {
+            return Ok(());
+        }
+        let Some(target) = prv_msg.determine_stream(self.mode) else {
+            return Ok(());
+        };
+        // Construct a spinner
+        let s = indicatif::ProgressBar::with_draw_target(None, self.out.draw_target(target))
+            .with_message(self.render(prv_msg))
+            .with_style(MAIN_STYLE.clone())
+            .with_elapsed(waited);
+
+        // Wipe the lines about to be spun, on a terminal where they can be
+        if self.out.is_term(target) {
+            let drawn = self.drawn;
+            self.out
+                .with_term(target, |term| term.clear_last_lines(drawn))?;
+        }
+        // Start spinning
+        s.enable_steady_tick(Duration::from_millis(100));
+        self.spinner = Some(s);
+        // The spun line was wiped, so it's not there to repeat
+        self.repeated = None;
+        self.beats = 0;
         Ok(())
     }
 
-    #[expect(unused)]
-    /// Handle an incremental progress bar.
-    fn progress_bar(&mut self, message: &Message) -> PyResult<()> {
-        unimplemented!()
+    /// Say what's still being worked on, once every `HEARTBEAT_INTERVAL` of spinning,
+    /// so that a log cut short shows where the time went.
+    fn heartbeat(&mut self) {
+        let (Some(spinner), Some(prv_msg), Some(heartbeat)) =
+            (&self.spinner, &self.prv_msg, &self.heartbeat)
+        else {
+            return;
+        };
+        let elapsed = spinner.elapsed();
+        let beats = elapsed.as_secs() / HEARTBEAT_INTERVAL.as_secs();
+        if beats > self.beats {
+            self.beats = beats;
+            heartbeat(&format!(
+                "still working on: {} ({}s elapsed)",
+                console::strip_ansi_codes(&prv_msg.text),
+                elapsed.as_secs()
+            ));
+        }
     }
-}
 
-impl Drop for InnerPrinter {
+    /// Apply a control command, after handling every message sent before it.
+    ///
+    /// While paused, messages are only handled once resuming, or when interrupted.
+    fn apply_control(&mut self, control: Control) -> Result<()> {
+        if !self.paused || matches!(control, Control::Resume | Control::Interrupt(_)) {
+            while let Some(msg) = self.backlog.take().or_else(|| self.try_dequeue().ok()) {
+                self.receive(msg)?;
+            }
+        }
+
+        match control {
+            Control::SetMode(mode) => {
+                diagnostics::note(|| format!("Verbosity set to {mode:?}"));
+                self.mode = mode;
+            }
+            Control::Redirect(out) => {
+                diagnostics::note(|| "Redirected to JSON lines".to_string());
+                if let OutputFormat::Human = self.format {
+                    self.release_terminal()?;
+                }
+                self.format = OutputFormat::Json;
+                self.json_out = out;
+            }
+            Control::Flush(ack) => {
+                self.out.flush()?;
+                // The sender may have given up waiting, which is fine
+                _ = ack.send(());
+            }
+            Control::Interrupt(ack) => {
+                if let OutputFormat::Human = self.format {
+                    self.release_terminal()?;
+                }
+                self.out.flush()?;
+                _ = ack.send(());
+            }
+            Control::Pause(ack) => {
+                if let OutputFormat::Human = self.format
+                    && !self.paused
+                {
+                    self.pause()?;
+                }
+                self.out.flush()?;
+                _ = ack.send(());
+            }
+            Control::Resume => {
+                if let OutputFormat::Human = self.format
+                    && self.paused
+                {
+                    self.resume();
+                }
+                self.paused = false;
+            }
+            Control::Marker(line) => {
+                if let OutputFormat::Human = self.format {
+                    self.handle_overwrite()?;
+                    self.end_repeats()?;
+                    self.out
+                        .with_term(Target::Stderr, |term| term.write_line(&line))?;
+                }
+            }
+            Control::BarStyle(style) => {
+                if let Some((bar, _)) = &self.bar {
+                    bar.set_style(self.bar_style(style));
+                }
+            }
+            Control::UpdateProgress(msg) => self.update_progress(msg)?,
+            Control::Bar(update) => match (update, &self.bar) {
+                (BarUpdate::Advance(amount), Some((bar, _))) => bar.inc(amount),
+                (BarUpdate::Set(position), Some((bar, _))) => bar.set_position(position),
+                (BarUpdate::Finish, _) => self.finish_bar()?,
+                // The bar is hidden, or was already finished
+                (_, None) => {}
+            },
+        }
+        Ok(())
+    }
+
+    /// Helper method for waiting on either channel.
+    ///
+    /// While paused, only control commands are waited on, leaving messages queued.
+    fn await_event(&mut self, timeout: Duration) -> Event {
+        if self.paused {
+            return match self.control.recv_timeout(timeout) {
+                Ok(control) => Event::Control(control),
+                Err(RecvTimeoutError::Timeout) => Event::Timeout,
+                // Stopping while paused, so show what was held back after all
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.paused = false;
+                    self.await_event(timeout)
+                }
+            };
+        }
+        if let Some(msg) = self.backlog.take() {
+            return Event::Message(self.coalesce(msg));
+        }
+
+        // Once caught up, say what was left out of a queue that grew too large
+        if self.channel.is_empty()
+            && let Some((count, summary)) = self.budget.summary()
+        {
+            self.stats().dropped += count;
+            return Event::Message(summary);
+        }
+
+        crossbeam_channel::select! {
+            recv(self.channel) -> msg => match msg.map(|msg| self.dequeued(msg)) {
+                Ok(msg) => Event::Message(self.coalesce(msg)),
+                Err(RecvError) => Event::Disconnected,
+            },
+            recv(self.control) -> control => if let Ok(control) = control {
+                Event::Control(control)
+            } else {
+                // Without controls, keep going until the message channel closes too
+                self.control = crossbeam_channel::never();
+                self.await_event(timeout)
+            },
+            default(timeout) => Event::Timeout,
+        }
+    }
+
+    /// Take the next message off the queue, if there is one.
+    fn try_dequeue(&self) -> std::result::Result<Message, crossbeam_channel::TryRecvError> {
+        self.channel.try_recv().map(|msg| self.dequeued(msg))
+    }
+
+    /// Account for a message taken off the queue.
+    fn dequeued(&self, msg: Message) -> Message {
+        *self.stats().messages.entry(msg.model.name()).or_default() += 1;
+        self.budget.release(msg)
+    }
+
+    /// Lock the counts of what was printed.
+    fn stats(&self) -> std::sync::MutexGuard<'_, Stats> {
+        self.stats.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Coalesce a burst of ephemeral progress messages into the newest one, as each
+    /// would be overwritten by the next before anyone could read it.
+    ///
+    /// Ephemeral messages are held back until the next redraw is due, so that the
+    /// terminal is redrawn at most at the configured rate however fast they come.
+    ///
+    /// While an error is on its way, ephemeral messages are skipped without waiting, so
+    /// that it's shown straight away however much progress is queued ahead of it. Once
+    /// the queue overflowed, every queued ephemeral message but the newest is dropped,
+    /// oldest first, until the queue is empty.
+    fn coalesce(&mut self, mut msg: Message) -> Message {
+        while self.shedding.load(Ordering::Acquire)
+            && let MessageType::ProgEphemeral(..) = msg.model
+        {
+            if let Ok(next) = self.try_dequeue() {
+                self.shed.fetch_add(1, Ordering::Relaxed);
+                self.stats().dropped += 1;
+                msg = next;
+            } else {
+                self.shedding.store(false, Ordering::Release);
+            }
+        }
+
+        while self.urgent.load(Ordering::Acquire) > 0
+            && let MessageType::ProgEphemeral(..) = msg.model
+        {
+            // The error was counted before being sent, so it's coming
+            match self.channel.recv().map(|msg| self.dequeued(msg)) {
+                Ok(next) => {
+                    self.stats().dropped += 1;
+                    msg = next;
+                }
+                Err(RecvError) => break,
+            }
+        }
+
+        while let MessageType::ProgEphemeral(..) = msg.model {
+            let next = match self.last_redraw {
+                Some(last) if self.urgent.load(Ordering::Acquire) == 0 => self
+                    .channel
+                    .recv_deadline(last + self.settings.redraw_interval())
+                    .map(|msg| self.dequeued(msg)),
+                _ => self.try_dequeue().map_err(|_| RecvTimeoutError::Timeout),
+            };
+            match next {
+                Ok(next) if matches!(next.model, MessageType::ProgEphemeral(..)) => {
+                    self.stats().dropped += 1;
+                    msg = next;
+                }
+                Ok(next) => {
+                    self.backlog = Some(next);
+                    break;
+                }
+                Err(_) => break,
+            }
+        }
+
+        if let MessageType::ProgEphemeral(..) = msg.model {
+            self.last_redraw = Some(Instant::now());
+        }
+        msg
+    }
+
+    /// Handle a message, keeping it clear of the progress bar if one is shown.
+    fn handle_message(&mut self, msg: &Message) -> Result<()> {
+        if let Some((bar, _)) = &self.bar
+            && !matches!(msg.model, MessageType::ProgBar(..))
+        {
+            let bar = bar.clone();
+            return bar.suspend(|| self.route_message(msg));
+        }
+        self.route_message(msg)
+    }
+
+    /// Routing method for sending a message to the proper printing logic for a given
+    /// message type.
+    fn route_message(&mut self, msg: &Message) -> Result<()> {
+        use self::MessageType::*;
+        if let Target::Null = msg.target {
+            return Ok(());
+        }
+        match msg.model {
+            Debug() | Trace() => self.write_limited(msg),
+            Info() | Success() | Warning() | Error() => {
+                self.end_limits()?;
+                self.write(msg)
+            }
+            ProgEphemeral(..) => self.progress(msg, false),
+            ProgPersistent(..) => self.progress(msg, true),
+            ProgBar(_, total) => self.progress_bar(msg, total),
+        }
+    }
+
+    /// Leave the terminal as it was found: no spinner, no line left to be overwritten
+    /// and a visible cursor.
+    fn release_terminal(&mut self) -> Result<()> {
+        self.end_limits()?;
+        self.finish_bar()?;
+        self.end_repeats()?;
+        if let Some(s) = self.spinner.take() {
+            s.finish_and_clear();
+            self.overwrite = None;
+        }
+        self.handle_overwrite()?;
+        if std::mem::take(&mut self.cursor_hidden)
+            && let Some(term) = self.out.cursor_term()
+        {
+            term.show_cursor()?;
+        }
+        if std::mem::take(&mut self.title_saved)
+            && let Some(term) = self.out.cursor_term()
+        {
+            term.write_str(RESTORE_TITLE)?;
+        }
+        Ok(())
+    }
+
+    /// Stop drawing, leaving what's on screen as it is for something else to take over
+    /// the terminal, such as a prompt.
+    fn pause(&mut self) -> Result<()> {
+        self.end_limits()?;
+        self.end_repeats()?;
+        self.stop_spinner()?;
+        // The last line stays on screen, and whatever comes next starts a new one
+        self.overwrite = None;
+        if let Some((bar, _)) = &self.bar {
+            bar.set_draw_target(indicatif::ProgressDrawTarget::hidden());
+        }
+        if std::mem::take(&mut self.cursor_hidden)
+            && let Some(term) = self.out.cursor_term()
+        {
+            term.show_cursor()?;
+        }
+        self.paused = true;
+        Ok(())
+    }
+
+    /// Take the terminal back after pausing, drawing the progress bar again on a new
+    /// line and hiding the cursor.
+    fn resume(&mut self) {
+        self.paused = false;
+        if self.settings.cursor_control()
+            && let Some(term) = self.out.cursor_term()
+        {
+            self.cursor_hidden = term.hide_cursor().is_ok();
+        }
+        if let Some((bar, message)) = &self.bar
+            && !self.settings.plain()
+        {
+            bar.set_draw_target(self.out.draw_target(message.target));
+            bar.tick();
+        }
+    }
+
+    /// Handle the need (or lackthereof) to overwrite the previous rows.
+    fn handle_overwrite(&mut self) -> Result<()> {
+        if let Some((target, rows)) = self.overwrite.take() {
+            self.out
+                .with_term(target, |term| term.clear_last_lines(rows))?;
+            self.stats().overwrites += 1;
+        }
+        Ok(())
+    }
+
+    /// Render the text of a message with the style of its type, made safe to print and
+    /// highlighted if asked for.
+    ///
+    /// On GitHub Actions, warnings and errors are annotations instead, if marked.
+    fn render(&self, message: &Message) -> String {
+        let text = sanitize(&message.text);
+        if let Some(Ci::GitHubActions) = self.settings.ci_markers()
+            && let MessageType::Warning() | MessageType::Error() = message.model
+        {
+            return format!(
+                "::{}::{}",
+                message.model.name(),
+                escape_workflow_command(&text)
+            );
+        }
+        if self.settings.highlight() {
+            let highlighted = crate::highlight::highlight(&text, message.target);
+            return self
+                .theme
+                .apply(message.model, message.target, &highlighted);
+        }
+        self.theme.apply(message.model, message.target, &text)
+    }
+
+    /// Print a message to the stream it targets.
+    ///
+    /// A progress, warning, debug or trace message repeating the line just written is
+    /// collapsed into it, with a count of how many times it was repeated. Plain
+    /// terminals and streams that aren't terminals can't rewrite the line, so the count
+    /// is written once the repeats end instead. Results of the command, like info and
+    /// success messages, are always written as they are.
+    fn write(&mut self, message: &Message) -> Result<()> {
+        self.handle_overwrite()?;
+        let foldable = matches!(
+            message.model,
+            MessageType::ProgPersistent(..)
+                | MessageType::ProgEphemeral(..)
+                | MessageType::Warning()
+                | MessageType::Debug()
+                | MessageType::Trace()
+        );
+        if foldable
+            && let Some((last, count)) = &mut self.repeated
+            && last.model == message.model
+            && last.target == message.target
+            && last.text == message.text
+        {
+            *count += 1;
+            let count = *count;
+            if !self.rewrites(message.target) {
+                return Ok(());
+            }
+            let (drawn, lines) = (self.drawn, self.counted_lines(message, count));
+            self.stats().bytes_written += written_bytes(&lines);
+            self.drawn = self.rows(message.target, &lines);
+            return self.out.with_term(message.target, |term| {
+                term.clear_last_lines(drawn)?;
+                lines.iter().try_for_each(|line| term.write_line(line))
+            });
+        }
+
+        self.end_repeats()?;
+        let lines = self.lines(message);
+        self.stats().bytes_written += written_bytes(&lines);
+        self.out.with_term(message.target, |term| {
+            lines.iter().try_for_each(|line| term.write_line(line))
+        })?;
+        self.drawn = self.rows(message.target, &lines);
+        self.repeated = foldable.then(|| (message.clone(), 1));
+        Ok(())
+    }
+
+    /// Whether lines written to a stream can be rewritten as they change.
+    fn rewrites(&self, target: Target) -> bool {
+        !self.settings.plain() && self.out.is_term(target)
+    }
+
+    /// How many rows `lines` take up on a stream's terminal, wrapping those wider than
+    /// it onto more.
+    fn rows(&self, target: Target, lines: &[String]) -> usize {
+        match self.out.width(target).filter(|&width| width > 0) {
+            Some(width) => lines
+                .iter()
+                .map(|line| console::measure_text_width(line).div_ceil(width).max(1))
+                .sum(),
+            None => lines.len(),
+        }
+    }
+
+    /// The lines a message is drawn on, one for each line of its text.
+    ///
+    /// Continuation lines are indented under the first, except for the results of
+    /// the command, which may be piped or copied as they are.
+    fn lines(&self, message: &Message) -> Vec<String> {
+        let indent = !matches!(message.model, MessageType::Info() | MessageType::Success());
+        let rendered = self.render(message);
+        let mut lines: Vec<String> = rendered
+            .split('\n')
+            .enumerate()
+            .map(|(i, line)| match i {
+                0 => line.to_string(),
+                _ if indent => format!("{CONTINUATION_INDENT}{line}"),
+                _ => line.to_string(),
+            })
+            .collect();
+        // Ephemeral progress is redrawn often, so it's kept to a row per line rather
+        // than wrapping
+        if let MessageType::ProgEphemeral(..) = message.model
+            && !self.settings.plain()
+            && let Some(width) = self.out.width(message.target)
+        {
+            for line in &mut lines {
+                *line = console::truncate_str(line, width, "…").into_owned();
+            }
+        }
+        lines
+    }
+
+    /// The lines of a message repeated `count` times in a row, with the count after
+    /// its last line.
+    fn counted_lines(&self, message: &Message, count: usize) -> Vec<String> {
+        let mut lines = self.lines(message);
+        if let Some(last) = lines.last_mut() {
+            *last = format!("{last} (x{count})");
+        }
+        lines
+    }
+
+    /// Write how many times the last line was repeated, for plain terminals and streams
+    /// that aren't terminals, where it couldn't be shown as it happened.
+    fn end_repeats(&mut self) -> Result<()> {
+        match self.repeated.take() {
+            Some((last, count)) if count > 1 && !self.rewrites(last.target) => {
+                let lines = self.counted_lines(&last, count);
+                self.out.with_term(last.target, |term| {
+                    lines.iter().try_for_each(|line| term.write_line(line))
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Print a message, unless its type already filled its share of lines this second.
+    fn write_limited(&mut self, message: &Message) -> Result<()> {
+        let Some(max) = self.settings.max_debug_rate() else {
+            return self.write(message);
+        };
+        let now = Instant::now();
+        let limit = self
+            .limits
+            .entry(message.model.name())
+            .or_insert_with(|| RateLimit::new(now));
+        let summary = limit.restart_after(now, Duration::from_secs(1));
+        let shown = limit.admit(message, max);
+        if let Some(summary) = summary {
+            self.write(&summary)?;
+        }
+        if shown {
+            self.write(message)
+        } else {
+            self.stats().dropped += 1;
+            Ok(())
+        }
+    }
+
+    /// Summarize the lines left out by every rate limit so far, as the flood is over.
+    fn end_limits(&mut self) -> Result<()> {
+        let now = Instant::now();
+        let summaries: Vec<Message> = self
+            .limits
+            .values_mut()
+            .filter_map(|limit| limit.restart_after(now, Duration::ZERO))
+            .collect();
+        for summary in &summaries {
+            self.write(summary)?;
+        }
+        Ok(())
+    }
+
+    /// Print progress on a task.
+    fn progress(&mut self, message: &Message, permanent: bool) -> Result<()> {
+        self.write(message)?;
+        if self.title_saved
+            && let Some(term) = self.out.cursor_term()
+        {
+            term.write_str(&title_sequence(&message.text))?;
+        }
+        if !permanent && !self.settings.plain() {
+            self.overwrite = Some((message.target, self.drawn));
+            // The line is about to be overwritten, so it's not there to repeat
+            self.repeated = None;
+        }
+        Ok(())
+    }
+
+    /// Start showing a progress bar, replacing any current one.
+    fn progress_bar(&mut self, message: &Message, total: u64) -> Result<()> {
+        static BAR_STYLE: LazyLock<indicatif::ProgressStyle> = LazyLock::new(|| {
+            indicatif::ProgressStyle::with_template("{msg} [{bar:40}] {pos}/{len} ({eta} left)")
+                .unwrap()
+        });
+
+        diagnostics::note(|| format!("Progress bar of {total} started"));
+        self.finish_bar()?;
+        self.handle_overwrite()?;
+        // Plain terminals can't redraw the bar, so only its message is shown, once
+        let target = if self.settings.plain() {
+            self.write(message)?;
+            indicatif::ProgressDrawTarget::hidden()
+        } else {
+            // The bar draws on its own handle, so it has to be switched to here
+            self.out.with_term(message.target, |_| Ok(()))?;
+            self.out.draw_target(message.target)
+        };
+        let bar = indicatif::ProgressBar::with_draw_target(Some(total), target)
+            .with_style(self.bar_style(BAR_STYLE.clone()))
+            .with_message(self.render(message));
+        bar.tick();
+        self.bar = Some((bar, message.clone()));
+        Ok(())
+    }
+
+    /// Finish a progress bar's style with the parts every bar shares, whatever its
+    /// template.
+    fn bar_style(&self, style: indicatif::ProgressStyle) -> indicatif::ProgressStyle {
+        style
+            .progress_chars("#>-")
+            .with_key("eta", Eta::new(self.settings.eta_window()))
+    }
+
+    /// Remove the current progress bar, if any, leaving its message behind.
+    fn finish_bar(&mut self) -> Result<()> {
+        if let Some((bar, message)) = self.bar.take() {
+            bar.finish_and_clear();
+            if !self.settings.plain() {
+                self.progress(&message, true)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How much message text is queued for the printer, shared by everything sending to it
+/// so that it can be kept under a cap.
+///
+/// A subprocess streaming output faster than the terminal takes it would otherwise grow
+/// the queue without bound.
+#[derive(Debug, Default)]
+pub struct QueueBudget {
+    /// The most bytes of text queued at once, if limited.
+    cap: Option<usize>,
+
+    /// How many bytes of text are queued.
+    queued: AtomicUsize,
+
+    /// How many messages were left out for going over the cap since the last summary,
+    /// and how many bytes of text they had.
+    left_out: Mutex<(u64, usize)>,
+}
+
+impl QueueBudget {
+    /// Keep at most `cap` bytes of text queued, if set.
+    #[must_use]
+    pub fn new(cap: Option<usize>) -> Self {
+        Self {
+            cap,
+            ..Self::default()
+        }
+    }
+
+    /// Count a message about to be queued, returning whether it fits under the cap.
+    ///
+    /// Only ephemeral progress, debug and trace messages are left out over the cap, as
+    /// the rest is what the user is meant to read.
+    pub fn admit(&self, msg: &Message) -> bool {
+        let len = msg.text.len();
+        let queued = self.queued.fetch_add(len, Ordering::AcqRel) + len;
+        if self.cap.is_none_or(|cap| queued <= cap)
+            || !matches!(
+                msg.model,
+                MessageType::ProgEphemeral(_) | MessageType::Debug() | MessageType::Trace()
+            )
+        {
+            return true;
+        }
+        self.queued.fetch_sub(len, Ordering::AcqRel);
+        let mut left_out = self.left_out.lock().unwrap_or_else(PoisonError::into_inner);
+        left_out.0 += 1;
+        left_out.1 += len;
+        false
+    }
+
+    /// Count a message taken off the queue, or not queued after all.
+    pub fn release(&self, msg: Message) -> Message {
+        self.queued.fetch_sub(msg.text.len(), Ordering::AcqRel);
+        msg
+    }
+
+    /// How many messages were left out since the last summary, and a summary of them,
+    /// if any were.
+    fn summary(&self) -> Option<(u64, Message)> {
+        let mut left_out = self.left_out.lock().unwrap_or_else(PoisonError::into_inner);
+        let (count, bytes) = std::mem::take(&mut *left_out);
+        let summary = Message {
+            text: format!(
+                "… {count} more lines ({}) left out as output came faster than it could be shown, see log",
+                indicatif::HumanBytes(bytes as u64)
+            )
+            .into(),
+            model: MessageType::ProgPersistent(Target::Stderr),
+            target: Target::Stderr,
+            spinner: SpinnerHint::Auto,
+            fields: Fields::new(),
+            urgent: false,
+        };
+        (count > 0).then_some((count, summary))
+    }
+}
+
+/// Counts of the lines of a message type shown and left out since a second started.
+struct RateLimit {
+    /// When the current second started.
+    started: Instant,
+
+    /// How many lines were shown since.
+    shown: u32,
+
+    /// How many lines were left out since, and the last of them.
+    left_out: Option<(u64, Message)>,
+}
+
+impl RateLimit {
+    /// Start counting from `now`.
+    fn new(now: Instant) -> Self {
+        Self {
+            started: now,
+            shown: 0,
+            left_out: None,
+        }
+    }
+
+    /// Count a message, returning whether it's still within `max` lines this second.
+    fn admit(&mut self, message: &Message, max: u32) -> bool {
+        if self.shown < max {
+            self.shown += 1;
+            return true;
+        }
+        let count = self.left_out.as_ref().map_or(0, |(count, _)| *count);
+        self.left_out = Some((count + 1, message.clone()));
+        false
+    }
+
+    /// Start counting again from `now` if at least `after` passed since the current
+    /// second started, returning a summary of the lines left out, if any.
+    fn restart_after(&mut self, now: Instant, after: Duration) -> Option<Message> {
+        if now.duration_since(self.started) < after {
+            return None;
+        }
+        let left_out = std::mem::replace(self, Self::new(now)).left_out;
+        let (count, last) = left_out?;
+        Some(Message {
+            text: format!("… {count} more lines, see log").into(),
+            fields: Fields::new(),
+            ..last
+        })
+    }
+}
+
+/// A smoothed estimate of the time left on a progress bar.
+///
+/// The rate of progress is measured across a sliding window rather than between the
+/// last two updates, so producers updating in bursts don't make the estimate jump.
+#[derive(Clone)]
+struct Eta {
+    /// How far back updates are taken into account.
+    window: Duration,
+
+    /// Recent positions of the bar, oldest first, with when they were reached.
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl Eta {
+    /// Start estimating over the given window.
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Record the position of the bar at a point in time.
+    fn record(&mut self, now: Instant, pos: u64) {
+        self.samples.push_back((now, pos));
+        // One sample from before the window is kept, so that the window is covered
+        while self
+            .samples
+            .get(1)
+            .is_some_and(|(time, _)| now.duration_since(*time) >= self.window)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// The time left to reach `total`, if any progress is being made.
+    // Bars would need totals past 2^52 to lose any precision that matters
+    #[expect(clippy::cast_precision_loss)]
+    fn estimate(&self, total: u64) -> Option<Duration> {
+        let (&(start, from), &(end, to)) = (self.samples.front()?, self.samples.back()?);
+        let elapsed = end.duration_since(start).as_secs_f64();
+        if to <= from || elapsed == 0.0 {
+            return None;
+        }
+        let rate = (to - from) as f64 / elapsed;
+        Duration::try_from_secs_f64(total.saturating_sub(to) as f64 / rate).ok()
+    }
+}
+
+impl indicatif::style::ProgressTracker for Eta {
+    fn clone_box(&self) -> Box<dyn indicatif::style::ProgressTracker> {
+        Box::new(self.clone())
+    }
+
+    fn tick(&mut self, state: &indicatif::ProgressState, now: Instant) {
+        self.record(now, state.pos());
+    }
+
+    fn reset(&mut self, _state: &indicatif::ProgressState, _now: Instant) {
+        self.samples.clear();
+    }
+
+    fn write(&self, state: &indicatif::ProgressState, w: &mut dyn fmt::Write) {
+        // Drawing can't fail on a string buffer
+        _ = match state.len().and_then(|total| self.estimate(total)) {
+            Some(left) => write!(w, "{:#}", indicatif::HumanDuration(left)),
+            None => w.write_str("?"),
+        };
+    }
+}
+
+impl Drop for InnerPrinter {
     /// Restore the cursor when releasing control of the terminal.
+    ///
+    /// This also runs when the printer thread panics, so that the terminal isn't left
+    /// without a cursor or with a stray progress line.
     fn drop(&mut self) {
-        self.handle_overwrite().unwrap();
-        self.stdout.show_cursor().unwrap();
+        if let OutputFormat::Human = self.format {
+            // Panicking again while unwinding would abort, and there's nothing else
+            // left to try anyway
+            _ = self.release_terminal();
+        }
     }
 }
 
@@ -300,11 +1793,37 @@ impl Drop for InnerPrinter {
 /// printing from, and a channel to send messages.
 #[derive(Default)]
 pub struct Printer {
-    /// A handle on the thread running the `InnerPrinter` instance.
-    handle: OnceLock<JoinHandle<PyResult<()>>>,
+    /// A handle on the thread running the `InnerPrinter` instance, until stopped.
+    handle: Mutex<Option<JoinHandle<Result<()>>>>,
+
+    /// A channel to send messages to the `InnerPrinter` instance, until stopped.
+    channel: RwLock<Option<Sender<Message>>>,
+
+    /// A channel to send control commands to the `InnerPrinter` instance, until
+    /// stopped.
+    control: RwLock<Option<Sender<Control>>>,
+
+    /// Whether an output stream was found closed while printing.
+    closed: Arc<AtomicBool>,
+
+    /// How many errors were sent that the `InnerPrinter` hasn't handled yet.
+    urgent: Arc<AtomicUsize>,
 
-    /// A channel to send messages to the `InnerPrinter` instance.
-    channel: OnceLock<mpsc::Sender<Message>>,
+    /// Whether the `InnerPrinter` is dropping queued ephemeral messages to catch up.
+    shedding: Arc<AtomicBool>,
+
+    /// How many ephemeral messages the `InnerPrinter` dropped to catch up.
+    shed: Arc<AtomicUsize>,
+
+    /// How much text is queued for the `InnerPrinter`.
+    budget: Arc<QueueBudget>,
+
+    /// Counts of what the `InnerPrinter` printed.
+    stats: Arc<Mutex<Stats>>,
+
+    /// Where the `InnerPrinter` writes what's still being worked on while spinning,
+    /// until it's started.
+    heartbeat: Option<Heartbeat>,
 }
 
 impl Printer {
@@ -314,47 +1833,405 @@ impl Printer {
     }
 
     /// Spawn a thread to begin listening for messages to print.
-    pub fn start(&mut self, mode: Verbosity) {
-        let (send, recv) = mpsc::channel();
+    pub fn start(&mut self, mode: Verbosity, settings: Settings) {
+        let (send, recv) = match settings.queue_capacity() {
+            Some(capacity) => crossbeam_channel::bounded(capacity),
+            None => crossbeam_channel::unbounded(),
+        };
+        let (control_send, control_recv) = crossbeam_channel::unbounded();
 
         assert!(
-            self.channel.set(send).is_ok(),
+            self.channel().is_none() && self.control().is_none(),
             "Printer was already started!"
         );
+        *self
+            .channel
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner) = Some(send);
+        *self
+            .control
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner) = Some(control_send);
 
-        let handle = thread::spawn(move || -> PyResult<()> {
-            let mut printer = InnerPrinter::new(mode, recv);
+        let closed = Arc::clone(&self.closed);
+        let urgent = Arc::clone(&self.urgent);
+        let shedding = Arc::clone(&self.shedding);
+        let shed = Arc::clone(&self.shed);
+        self.budget = Arc::new(QueueBudget::new(settings.max_queued_bytes()));
+        let budget = Arc::clone(&self.budget);
+        let stats = Arc::clone(&self.stats);
+        let heartbeat = self.heartbeat.take();
+        let theme = settings.theme().clone();
+        let handle = thread::spawn(move || -> Result<()> {
+            diagnostics::note(|| format!("Printer started with {settings:?}"));
+            let mut printer = InnerPrinter::new(mode, theme, settings, recv, control_recv);
+            printer.out.closed = closed;
+            printer.urgent = urgent;
+            printer.shedding = shedding;
+            printer.shed = shed;
+            printer.budget = budget;
+            printer.stats = stats;
+            printer.heartbeat = heartbeat;
             printer.listen()?;
+            diagnostics::note(|| "Printer stopped".to_string());
             Ok(())
         });
 
-        self.handle.set(handle).unwrap();
+        *self
+            .handle
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner) = Some(handle);
+    }
+
+    /// Write what's still being worked on with `heartbeat` every so often while
+    /// spinning, such as to the log. This must be set before starting.
+    pub fn set_heartbeat(&mut self, heartbeat: impl Fn(&str) + Send + Sync + 'static) {
+        self.heartbeat = Some(Box::new(heartbeat));
     }
 
     /// Stop printing.
     ///
-    /// This ends the `InnerPrinter` instance's thread.
-    pub fn stop(&mut self) -> PyResult<()> {
+    /// This ends the `InnerPrinter` instance's thread. Anything sent afterwards, such
+    /// as by other threads still emitting, is left out.
+    pub fn stop(&self) -> Result<()> {
         // Dropping the channel closes it, which will be seen by the other thread as a
         // stopping condition
-        _ = self.channel.take();
-        if let Some(handle) = self.handle.take()
-            && let Err(e) = handle.join()
-        {
-            // PyErr is guaranteed as the return type, so we can blindly
-            // downcast
-            return Err(*e.downcast::<PyErr>().unwrap());
+        _ = self
+            .channel
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        _ = self
+            .control
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        let handle = self
+            .handle
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        let result = match handle.map(JoinHandle::join) {
+            Some(Ok(result)) => result,
+            Some(Err(payload)) => Err(Error::PrinterPanicked(panic_message(&*payload))),
+            None => Ok(()),
+        };
+        if let Err(e) = &result {
+            diagnostics::dump(&e.to_string());
         }
+        result
+    }
 
-        Ok(())
+    /// Whether an output stream was found closed while printing, such as a pipe into
+    /// a program that has exited.
+    ///
+    /// Nothing more is written to a closed stream, but printing otherwise carries on.
+    pub fn output_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
     }
 
     /// Send a message to the `InnerPrinter` for displaying
-    pub fn send(&self, msg: Message) {
-        match self.channel.get() {
-            Some(chan) => chan.send(msg).unwrap(),
-            None => panic!("Receiver closed early?"),
+    ///
+    /// Errors jump ahead of any ephemeral progress still queued, so that failures are
+    /// seen straight away even under heavy output. Ephemeral progress, debug and trace
+    /// messages that would take the queue over its cap are left out, and summarized
+    /// once it's caught up on.
+    pub fn send(&self, mut msg: Message) {
+        let channel = self.channel();
+        let Some(chan) = &*channel else {
+            return;
+        };
+        if !self.budget.admit(&msg) {
+            return;
+        }
+        if let MessageType::Error() = msg.model {
+            msg.urgent = true;
+            self.urgent.fetch_add(1, Ordering::AcqRel);
         }
+        // The printer only hangs up early if it failed, which `stop` reports
+        _ = chan.send(msg);
+    }
+
+    /// Send a message to the `InnerPrinter` if there's room in its queue, or give it
+    /// back if it's full.
+    ///
+    /// # Errors
+    ///
+    /// Returns the message if the queue is full.
+    pub fn try_send(&self, mut msg: Message) -> std::result::Result<(), Message> {
+        let channel = self.channel();
+        let Some(chan) = &*channel else {
+            return Ok(());
+        };
+        if !self.budget.admit(&msg) {
+            return Ok(());
+        }
+        if let MessageType::Error() = msg.model {
+            msg.urgent = true;
+            self.urgent.fetch_add(1, Ordering::AcqRel);
+        }
+        match chan.try_send(msg) {
+            Err(TrySendError::Full(mut msg)) => {
+                if msg.urgent {
+                    msg.urgent = false;
+                    self.urgent.fetch_sub(1, Ordering::AcqRel);
+                }
+                Err(self.budget.release(msg))
+            }
+            // The printer only hangs up early if it failed, which `stop` reports
+            Ok(()) | Err(TrySendError::Disconnected(_)) => Ok(()),
+        }
+    }
+
+    /// Have the `InnerPrinter` drop the ephemeral messages in its queue, oldest first,
+    /// until it has caught up, to make room in a full queue.
+    ///
+    /// Other messages are never dropped.
+    pub fn shed_ephemeral(&self) {
+        self.shedding.store(true, Ordering::Release);
+    }
+
+    /// How many ephemeral messages were dropped to make room in a full queue.
+    pub fn shed(&self) -> usize {
+        self.shed.load(Ordering::Relaxed)
+    }
+
+    /// Counts of what was printed so far.
+    pub fn stats(&self) -> Stats {
+        self.stats
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// A channel to send messages to the printer on, for senders outside the emitter.
+    pub fn sender(&self) -> Option<Sender<Message>> {
+        self.channel().clone()
+    }
+
+    /// How much text is queued for the printer, for senders outside the emitter to
+    /// count the messages they send against.
+    pub fn budget(&self) -> Arc<QueueBudget> {
+        Arc::clone(&self.budget)
+    }
+
+    /// Change the verbosity mode of the `InnerPrinter`.
+    pub fn set_mode(&self, mode: Verbosity) {
+        self.send_control(Control::SetMode(mode));
+    }
+
+    /// Write every message from now on as JSON lines to a writer instead of the
+    /// terminal.
+    pub fn redirect(&self, out: Box<dyn Write + Send>) {
+        self.send_control(Control::Redirect(out));
+        // Messages sent next could otherwise be picked up before the redirect
+        self.flush();
+    }
+
+    /// Stop drawing, once every message sent so far has been written out, so that
+    /// something else can use the terminal.
+    ///
+    /// Messages sent while paused are held back until resuming.
+    pub fn pause(&self) {
+        let (ack, done) = crossbeam_channel::bounded(1);
+        self.send_control(Control::Pause(ack));
+        // An error means the printer has stopped, so there's nothing left to wait for
+        _ = done.recv();
+    }
+
+    /// Take the terminal back after pausing, and show the messages held back.
+    pub fn resume(&self) {
+        self.send_control(Control::Resume);
+    }
+
+    /// Write a line to stderr as it is, in order with the messages sent before it, for
+    /// a continuous integration system to pick up.
+    pub fn marker(&self, line: String) {
+        self.send_control(Control::Marker(line));
+    }
+
+    /// Restyle the current progress bar.
+    pub fn bar_style(&self, style: indicatif::ProgressStyle) {
+        self.send_control(Control::BarStyle(style));
+    }
+
+    /// Change the text of the ephemeral progress message spun on, in order with the
+    /// messages sent before it, or show the message as any other if there's no spinner.
+    pub fn update_progress(&self, msg: Message) {
+        self.send_control(Control::UpdateProgress(msg));
+    }
+
+    /// Update the current progress bar.
+    pub fn bar(&self, update: BarUpdate) {
+        self.send_control(Control::Bar(update));
+    }
+
+    /// Wait until every message sent so far has been written out.
+    pub fn flush(&self) {
+        let (ack, done) = crossbeam_channel::bounded(1);
+        self.send_control(Control::Flush(ack));
+        // An error means the printer has stopped, so there's nothing left to wait for
+        _ = done.recv();
+    }
+
+    /// Get a function that cleans up the terminal when the program is interrupted,
+    /// which can be called from any thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the printer wasn't started.
+    pub fn interrupter(&self) -> impl Fn() + Send + 'static {
+        let control = self.control().clone().expect("Printer wasn't started");
+        move || {
+            let (ack, done) = crossbeam_channel::bounded(1);
+            // The printer may have stopped already, leaving nothing to clean up
+            if control.send(Control::Interrupt(ack)).is_ok() {
+                _ = done.recv();
+            }
+        }
+    }
+
+    /// Send a control command to the `InnerPrinter`, unless it was stopped.
+    fn send_control(&self, control: Control) {
+        if let Some(chan) = &*self.control() {
+            // The printer only hangs up early if it failed, which `stop` reports
+            _ = chan.send(control);
+        }
+    }
+
+    /// The channel to send messages on, or `None` once stopped.
+    fn channel(&self) -> RwLockReadGuard<'_, Option<Sender<Message>>> {
+        self.channel.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The channel to send control commands on, or `None` once stopped.
+    fn control(&self) -> RwLockReadGuard<'_, Option<Sender<Control>>> {
+        self.control.read().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Where the printer writes what's still being worked on while spinning.
+type Heartbeat = Box<dyn Fn(&str) + Send + Sync>;
+
+/// How often what's still being worked on is written while spinning.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_mins(1);
+
+/// The indentation of the lines after the first in a message of several.
+const CONTINUATION_INDENT: &str = "  ";
+
+/// How many bytes writing each of `lines` takes, with its line ending.
+fn written_bytes(lines: &[String]) -> u64 {
+    lines.iter().map(|line| line.len() as u64 + 1).sum()
+}
+
+/// Save the terminal's title on its stack of titles, as xterm and most terminals
+/// after it can.
+const SAVE_TITLE: &str = "\x1b[22;0t";
+
+/// Restore the terminal's title from its stack of titles.
+const RESTORE_TITLE: &str = "\x1b[23;0t";
+
+/// The OSC 0 sequence setting the terminal's title to the first line of `text`, which
+/// can't hold escape sequences or control characters.
+fn title_sequence(text: &str) -> String {
+    let line = console::strip_ansi_codes(text.trim_start().lines().next().unwrap_or_default())
+        .replace(char::is_control, "");
+    format!("\x1b]0;{line}\x07")
+}
+
+/// How many columns apart tab stops are.
+const TAB_WIDTH: usize = 8;
+
+/// Make text safe to print, so that output from subprocesses can't move the cursor or
+/// clear the screen.
+///
+/// Tabs are expanded to spaces, and a carriage return drops what came before it on its
+/// line, as the terminal would have drawn over it. Other control characters and escape
+/// sequences are removed, except for styles and hyperlinks, which can't draw anywhere
+/// but in place.
+fn sanitize(text: &str) -> Cow<'_, str> {
+    if !text.contains(|c: char| c.is_control() && c != '\n') {
+        return Cow::Borrowed(text);
+    }
+    let lines: Vec<String> = text.split('\n').map(sanitize_line).collect();
+    Cow::Owned(lines.join("\n"))
+}
+
+/// Make a single line safe to print, as by `sanitize`.
+fn sanitize_line(line: &str) -> String {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let line = line.rsplit('\r').next().unwrap_or_default();
+    let mut clean = String::with_capacity(line.len());
+    let mut column = 0;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\t' => {
+                let spaces = TAB_WIDTH - column % TAB_WIDTH;
+                clean.extend(std::iter::repeat_n(' ', spaces));
+                column += spaces;
+            }
+            '\x1b' => match chars.next() {
+                Some('[') => {
+                    let mut sequence = String::from("\x1b[");
+                    for c in chars.by_ref() {
+                        sequence.push(c);
+                        if ('\x40'..='\x7e').contains(&c) {
+                            break;
+                        }
+                    }
+                    if sequence.ends_with('m') {
+                        clean.push_str(&sequence);
+                    }
+                }
+                Some(']') => {
+                    let mut sequence = String::from("\x1b]");
+                    while let Some(c) = chars.next() {
+                        sequence.push(c);
+                        if c == '\x07' {
+                            break;
+                        }
+                        if c == '\x1b' && chars.next_if_eq(&'\\').is_some() {
+                            sequence.push('\\');
+                            break;
+                        }
+                    }
+                    if sequence.starts_with("\x1b]8;") {
+                        clean.push_str(&sequence);
+                    }
+                }
+                // Intermediate bytes lead up to the final one of the sequence
+                Some(' '..='/') => {
+                    while chars.next_if(|c| (' '..='/').contains(c)).is_some() {}
+                    chars.next();
+                }
+                _ => {}
+            },
+            c if c.is_control() => {}
+            c => {
+                column += console::measure_text_width(c.encode_utf8(&mut [0; 4]));
+                clean.push(c);
+            }
+        }
+    }
+    clean
+}
+
+/// Escape the text of a GitHub Actions workflow command, which has to fit on a line.
+fn escape_workflow_command(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Get the message a thread panicked with, if it was given one.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        (*msg).to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        String::from("unknown cause")
     }
 }
 
@@ -363,3 +2240,812 @@ impl Drop for Printer {
         self.stop().expect("An error was encountered while logging. Tear down the printer properly to view the error.");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a message for testing.
+    fn message(text: &str, model: MessageType) -> Message {
+        Message {
+            text: text.into(),
+            model,
+            target: Target::Stderr,
+            spinner: SpinnerHint::Auto,
+            fields: Fields::new(),
+            urgent: false,
+        }
+    }
+
+    mod eta {
+        use super::*;
+
+        #[test]
+        fn smoothed_over_window() {
+            let start = Instant::now();
+            let mut eta = Eta::new(Duration::from_secs(10));
+            // A burst of progress, then a stall
+            eta.record(start, 0);
+            eta.record(start + Duration::from_secs(1), 50);
+            eta.record(start + Duration::from_secs(5), 50);
+
+            // 50 done in 5 seconds leaves 10 seconds for the other 100
+            assert_eq!(eta.estimate(150), Some(Duration::from_secs(10)));
+        }
+
+        #[test]
+        fn forgets_old_samples() {
+            let start = Instant::now();
+            let mut eta = Eta::new(Duration::from_secs(2));
+            eta.record(start, 0);
+            eta.record(start + Duration::from_secs(1), 90);
+            eta.record(start + Duration::from_secs(4), 95);
+            eta.record(start + Duration::from_secs(5), 100);
+
+            // Only the last 4 seconds count, at 2.5 per second
+            assert_eq!(eta.estimate(110), Some(Duration::from_secs(4)));
+        }
+
+        #[test]
+        fn no_progress() {
+            let start = Instant::now();
+            let mut eta = Eta::new(Duration::from_secs(2));
+            eta.record(start, 10);
+            eta.record(start + Duration::from_secs(1), 10);
+
+            assert_eq!(eta.estimate(100), None);
+        }
+    }
+
+    mod output {
+        use super::*;
+
+        /// A writer whose reader has gone away.
+        struct Closed;
+
+        impl Write for Closed {
+            fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+        }
+
+        #[test]
+        fn closed_pipe() {
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default().with_format(OutputFormat::Json),
+                crossbeam_channel::never(),
+                crossbeam_channel::never(),
+            );
+            printer.json_out = Box::new(Closed);
+
+            printer
+                .receive(message("one", MessageType::Info()))
+                .unwrap();
+            printer
+                .receive(message("two", MessageType::Info()))
+                .unwrap();
+            assert!(printer.out.closed.load(Ordering::Relaxed));
+        }
+    }
+
+    mod cursor {
+        use super::*;
+
+        #[test]
+        fn left_alone_without_control() {
+            let printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default().with_cursor_control(false),
+                crossbeam_channel::never(),
+                crossbeam_channel::never(),
+            );
+
+            assert!(!printer.cursor_hidden);
+        }
+    }
+
+    mod write {
+        use super::*;
+
+        #[test]
+        fn counts_repeats() {
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default().with_plain(true),
+                crossbeam_channel::never(),
+                crossbeam_channel::never(),
+            );
+
+            let persistent = MessageType::ProgPersistent(Target::Stderr);
+            for text in ["retrying", "retrying", "retrying"] {
+                printer.write(&message(text, persistent)).unwrap();
+            }
+            assert!(matches!(printer.repeated, Some((_, 3))));
+
+            // The same text of another type isn't a repeat
+            printer
+                .write(&message("retrying", MessageType::Warning()))
+                .unwrap();
+            assert!(matches!(printer.repeated, Some((_, 1))));
+        }
+
+        #[test]
+        fn results_never_folded() {
+            let screen = Screen::new(6, 40);
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default().with_screen(screen.clone()),
+                crossbeam_channel::never(),
+                crossbeam_channel::never(),
+            );
+
+            for model in [MessageType::Info(), MessageType::Success()] {
+                printer.write(&message("ok", model)).unwrap();
+                printer.write(&message("ok", model)).unwrap();
+                assert!(printer.repeated.is_none());
+            }
+            printer.release_terminal().unwrap();
+
+            assert_eq!(screen.contents(), "ok\nok\n+ ok\n+ ok");
+        }
+
+        #[test]
+        fn rewritten_on_terminal() {
+            let screen = Screen::new(4, 40);
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default().with_screen(screen.clone()),
+                crossbeam_channel::never(),
+                crossbeam_channel::never(),
+            );
+
+            for _ in 0..3 {
+                printer
+                    .write(&message("careful", MessageType::Warning()))
+                    .unwrap();
+            }
+            printer.release_terminal().unwrap();
+
+            assert_eq!(screen.contents(), "careful (x3)");
+        }
+    }
+
+    mod screen {
+        use super::*;
+
+        #[test]
+        fn ephemeral_truncated_and_overwritten() {
+            let screen = Screen::new(4, 20);
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default()
+                    .with_spinners(false)
+                    .with_screen(screen.clone()),
+                crossbeam_channel::never(),
+                crossbeam_channel::never(),
+            );
+
+            let ephemeral = MessageType::ProgEphemeral(Target::Stderr);
+            printer
+                .receive(message("Pulling a very long image name", ephemeral))
+                .unwrap();
+            assert_eq!(screen.contents(), "Pulling a very long…");
+
+            printer.receive(message("Pulled base", ephemeral)).unwrap();
+            printer
+                .receive(message("Packed hello.snap", MessageType::Info()))
+                .unwrap();
+            printer.release_terminal().unwrap();
+
+            assert_eq!(screen.contents(), "Packed hello.snap");
+        }
+
+        #[test]
+        fn spinner_updated_in_place() {
+            let screen = Screen::new(4, 40);
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default().with_screen(screen.clone()),
+                crossbeam_channel::never(),
+                crossbeam_channel::never(),
+            );
+
+            let ephemeral = MessageType::ProgEphemeral(Target::Stderr);
+            let mut compiling = message("Compiling", ephemeral);
+            compiling.spinner = SpinnerHint::Immediate;
+            printer.receive(compiling).unwrap();
+            assert!(printer.spinner.is_some());
+
+            printer
+                .apply_control(Control::UpdateProgress(message("Linking", ephemeral)))
+                .unwrap();
+            assert!(printer.spinner.is_some());
+            assert_eq!(printer.prv_msg.as_ref().unwrap().text(), "Linking");
+
+            printer
+                .receive(message("Built", MessageType::Info()))
+                .unwrap();
+            printer.release_terminal().unwrap();
+            assert_eq!(screen.contents(), "Built");
+
+            printer
+                .apply_control(Control::UpdateProgress(message("Packing", ephemeral)))
+                .unwrap();
+            assert!(printer.spinner.is_none());
+            assert_eq!(screen.contents(), "Built\nPacking");
+        }
+
+        #[test]
+        fn heartbeats_while_spinning() {
+            let beats = Arc::new(Mutex::new(Vec::new()));
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default().with_screen(Screen::new(4, 40)),
+                crossbeam_channel::never(),
+                crossbeam_channel::never(),
+            );
+            let written = Arc::clone(&beats);
+            printer.heartbeat = Some(Box::new(move |text: &str| {
+                written.lock().unwrap().push(text.to_string());
+            }));
+
+            let ephemeral = MessageType::ProgEphemeral(Target::Stderr);
+            printer.receive(message("Compiling", ephemeral)).unwrap();
+            printer.heartbeat();
+            printer.start_spinner(Duration::from_secs(61)).unwrap();
+            printer.heartbeat();
+            printer.heartbeat();
+
+            assert_eq!(
+                *beats.lock().unwrap(),
+                ["still working on: Compiling (61s elapsed)"]
+            );
+        }
+
+        #[test]
+        fn multiline_overwritten() {
+            let screen = Screen::new(6, 20);
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default()
+                    .with_spinners(false)
+                    .with_screen(screen.clone()),
+                crossbeam_channel::never(),
+                crossbeam_channel::never(),
+            );
+
+            let ephemeral = MessageType::ProgEphemeral(Target::Stderr);
+            printer
+                .receive(message("Building\nstep 1\nstep 2", ephemeral))
+                .unwrap();
+            assert_eq!(screen.contents(), "Building\n  step 1\n  step 2");
+
+            printer.receive(message("Built", ephemeral)).unwrap();
+            printer
+                .receive(message("name: hello\nversion: 1", MessageType::Info()))
+                .unwrap();
+            printer.release_terminal().unwrap();
+
+            assert_eq!(screen.contents(), "name: hello\nversion: 1");
+        }
+
+        #[test]
+        fn wrapped_rows_cleared() {
+            let screen = Screen::new(6, 10);
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default()
+                    .with_spinners(false)
+                    .with_screen(screen.clone()),
+                crossbeam_channel::never(),
+                crossbeam_channel::never(),
+            );
+
+            let wrapping = message(
+                "0123456789abcde",
+                MessageType::ProgPersistent(Target::Stderr),
+            );
+            printer.receive(wrapping.clone()).unwrap();
+            printer.receive(wrapping).unwrap();
+            printer.release_terminal().unwrap();
+
+            assert_eq!(screen.contents(), "0123456789\nabcde (x2)");
+        }
+    }
+
+    mod sanitize {
+        use super::*;
+
+        #[test]
+        fn tabs_to_stops() {
+            assert_eq!(sanitize("a\tbc\td"), "a       bc      d");
+            assert_eq!(sanitize("界\tx"), "界      x");
+        }
+
+        #[test]
+        fn cursor_left_alone() {
+            let text = "\x1b[2J\x1b[Hcleared\x07 \x1b(Bdone\x08\r\n50%\r100%\r\n";
+            assert_eq!(sanitize(text), "cleared done\n100%\n");
+
+            let styled = "\x1b[32mok\x1b[0m \x1b]8;;file:///log\x1b\\log\x1b]8;;\x1b\\";
+            assert_eq!(sanitize(styled), styled);
+            assert_eq!(sanitize("\x1b]0;title\x07plain"), "plain");
+        }
+    }
+
+    mod markers {
+        use super::*;
+
+        #[test]
+        fn github_annotations() {
+            let screen = Screen::new(4, 40);
+            let settings = Settings::default()
+                .with_ci_markers(true)
+                .with_screen(screen.clone())
+                .resolve_with(|name| (name == "GITHUB_ACTIONS").then(|| "true".into()))
+                .unwrap();
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                settings,
+                crossbeam_channel::never(),
+                crossbeam_channel::never(),
+            );
+
+            printer
+                .apply_control(Control::Marker("::group::Build".to_string()))
+                .unwrap();
+            printer
+                .receive(message("100% full\nof disk", MessageType::Warning()))
+                .unwrap();
+
+            assert_eq!(
+                screen.contents(),
+                "::group::Build\n::warning::100%25 full%0Aof disk"
+            );
+        }
+    }
+
+    mod pause {
+        use super::*;
+
+        #[test]
+        fn holds_back_messages() {
+            let screen = Screen::new(4, 20);
+            let (send, recv) = crossbeam_channel::unbounded();
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default()
+                    .with_spinners(false)
+                    .with_screen(screen.clone()),
+                recv,
+                crossbeam_channel::never(),
+            );
+
+            let ephemeral = MessageType::ProgEphemeral(Target::Stderr);
+            send.send(message("Pulling base", ephemeral)).unwrap();
+            let (ack, _done) = crossbeam_channel::bounded(1);
+            printer.apply_control(Control::Pause(ack)).unwrap();
+            screen.write_line("Continue? [y/N] y");
+
+            send.send(message("Pulled base", ephemeral)).unwrap();
+            assert!(matches!(
+                printer.await_event(Duration::ZERO),
+                Event::Timeout
+            ));
+            printer.apply_control(Control::Resume).unwrap();
+
+            assert_eq!(
+                screen.contents(),
+                "Pulling base\nContinue? [y/N] y\nPulled base"
+            );
+        }
+    }
+
+    mod interrupt {
+        use super::*;
+
+        #[test]
+        fn terminal_released() {
+            let screen = Screen::new(4, 20);
+            let (send, recv) = crossbeam_channel::unbounded();
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default().with_screen(screen.clone()),
+                recv,
+                crossbeam_channel::never(),
+            );
+            assert!(printer.cursor_hidden);
+
+            let persistent = MessageType::ProgPersistent(Target::Stderr);
+            send.send(message("Pulling base", persistent)).unwrap();
+            let (ack, done) = crossbeam_channel::bounded(1);
+            printer.apply_control(Control::Interrupt(ack)).unwrap();
+
+            assert!(done.try_recv().is_ok());
+            assert!(!printer.cursor_hidden);
+            assert!(printer.spinner.is_none());
+            assert_eq!(screen.contents(), "Pulling base");
+        }
+    }
+
+    mod title {
+        use super::*;
+
+        #[test]
+        fn first_line_without_escapes() {
+            assert_eq!(
+                title_sequence("  \x1b[1mPulling\x1b[0m base\x07\nfrom the store"),
+                "\x1b]0;Pulling base\x07"
+            );
+        }
+    }
+
+    mod rate_limit {
+        use super::*;
+
+        #[test]
+        fn summarizes_left_out() {
+            let now = Instant::now();
+            let mut limit = RateLimit::new(now);
+
+            let shown: Vec<bool> = (0..5)
+                .map(|i| limit.admit(&message(&i.to_string(), MessageType::Trace()), 2))
+                .collect();
+            assert_eq!(shown, [true, true, false, false, false]);
+
+            // Nothing is summarized until the second is over
+            assert!(limit.restart_after(now, Duration::from_secs(1)).is_none());
+            let summary = limit
+                .restart_after(now + Duration::from_secs(1), Duration::from_secs(1))
+                .unwrap();
+            assert_eq!(summary.text(), "… 3 more lines, see log");
+            assert_eq!(summary.model(), MessageType::Trace());
+            assert!(limit.admit(&message("5", MessageType::Trace()), 2));
+        }
+    }
+
+    mod hyperlinks {
+        use super::*;
+
+        /// Decide for a terminal with the given environment.
+        fn decide(vars: &[(&str, &str)], is_term: bool) -> bool {
+            let env = crate::test_utils::env(vars);
+            hyperlinks_decide(|name| env(name)?.into_string().ok(), is_term)
+        }
+
+        #[test]
+        fn known_terminals() {
+            assert!(decide(&[("WT_SESSION", "1")], true));
+            assert!(decide(&[("VTE_VERSION", "6003")], true));
+            assert!(decide(&[("TERM_PROGRAM", "WezTerm")], true));
+            assert!(decide(&[("TERM", "foot-extra")], true));
+            assert!(!decide(&[("VTE_VERSION", "4600")], true));
+            assert!(!decide(&[("TERM", "xterm-256color")], true));
+        }
+
+        #[test]
+        fn forced() {
+            assert!(decide(&[("FORCE_HYPERLINK", "1")], false));
+            assert!(!decide(
+                &[("FORCE_HYPERLINK", "0"), ("WT_SESSION", "1")],
+                true
+            ));
+        }
+
+        #[test]
+        fn not_a_terminal() {
+            assert!(!decide(&[("WT_SESSION", "1")], false));
+            assert_eq!(
+                hyperlink("file:///tmp/app.log", "app.log", Target::Null),
+                "app.log"
+            );
+        }
+    }
+
+    mod printer {
+        use super::*;
+
+        #[test]
+        fn panic_reported() {
+            let printer = Printer::new();
+            *printer.handle.lock().unwrap() = Some(thread::spawn(|| panic!("terminal went away")));
+
+            let err = printer.stop().unwrap_err();
+            assert!(matches!(err, Error::PrinterPanicked(msg) if msg == "terminal went away"));
+        }
+    }
+
+    mod await_event {
+        use super::*;
+
+        #[test]
+        fn coalesces_ephemeral() {
+            let (send, recv) = crossbeam_channel::unbounded();
+            for text in ["one", "two", "three"] {
+                send.send(message(text, MessageType::ProgEphemeral(Target::Stderr)))
+                    .unwrap();
+            }
+            send.send(message("done", MessageType::Info())).unwrap();
+            send.send(message("four", MessageType::ProgEphemeral(Target::Stderr)))
+                .unwrap();
+            drop(send);
+
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default(),
+                recv,
+                crossbeam_channel::never(),
+            );
+            let received: Vec<_> =
+                std::iter::from_fn(|| match printer.await_event(Duration::ZERO) {
+                    Event::Message(msg) => Some(msg.text.to_string()),
+                    _ => None,
+                })
+                .collect();
+
+            assert_eq!(received, ["three", "done", "four"]);
+        }
+    }
+
+    mod stats {
+        use super::*;
+
+        #[test]
+        fn counted() {
+            let (send, recv) = crossbeam_channel::unbounded();
+            let ephemeral = MessageType::ProgEphemeral(Target::Stderr);
+            send.send(message("one", ephemeral)).unwrap();
+            send.send(message("two", ephemeral)).unwrap();
+            send.send(message("done", MessageType::ProgPersistent(Target::Stderr)))
+                .unwrap();
+            drop(send);
+
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default().with_screen(Screen::new(4, 20)),
+                recv,
+                crossbeam_channel::never(),
+            );
+            while let Event::Message(msg) = printer.await_event(Duration::ZERO) {
+                printer.receive(msg).unwrap();
+            }
+
+            let stats = printer.stats().clone();
+            assert_eq!(
+                stats,
+                Stats {
+                    messages: [("progress", 3)].into(),
+                    bytes_written: 9,
+                    overwrites: 1,
+                    dropped: 1,
+                }
+            );
+        }
+    }
+
+    mod budget {
+        use super::*;
+
+        #[test]
+        fn summarizes_left_out() {
+            let (send, recv) = crossbeam_channel::unbounded();
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default(),
+                recv,
+                crossbeam_channel::never(),
+            );
+            printer.budget = Arc::new(QueueBudget::new(Some(10)));
+            let persistent = MessageType::ProgPersistent(Target::Stderr);
+            for msg in [
+                message("compiling", persistent),
+                message("linking", MessageType::ProgEphemeral(Target::Stderr)),
+                message("installed", persistent),
+                message("careful", MessageType::Warning()),
+            ] {
+                if printer.budget.admit(&msg) {
+                    send.send(msg).unwrap();
+                }
+            }
+            drop(send);
+
+            let received: Vec<_> =
+                std::iter::from_fn(|| match printer.await_event(Duration::ZERO) {
+                    Event::Message(msg) => Some(msg.text.to_string()),
+                    _ => None,
+                })
+                .collect();
+
+            assert_eq!(
+                received,
+                [
+                    "compiling",
+                    "installed",
+                    "careful",
+                    "… 1 more lines (7 B) left out as output came faster than it could be shown, see log"
+                ]
+            );
+            assert_eq!(printer.budget.queued.load(Ordering::Relaxed), 0);
+        }
+    }
+
+    mod coalesce {
+        use super::*;
+
+        #[test]
+        fn sheds_older_ephemeral() {
+            let (send, recv) = crossbeam_channel::unbounded();
+            let ephemeral = MessageType::ProgEphemeral(Target::Stderr);
+            send.send(message("one", ephemeral)).unwrap();
+            send.send(message("two", ephemeral)).unwrap();
+            send.send(message("careful", MessageType::Warning()))
+                .unwrap();
+            send.send(message("three", ephemeral)).unwrap();
+            send.send(message("four", ephemeral)).unwrap();
+            drop(send);
+
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default(),
+                recv,
+                crossbeam_channel::never(),
+            );
+            printer.shedding.store(true, Ordering::Relaxed);
+            let received: Vec<_> =
+                std::iter::from_fn(|| match printer.await_event(Duration::ZERO) {
+                    Event::Message(msg) => Some(msg.text.to_string()),
+                    _ => None,
+                })
+                .collect();
+
+            assert_eq!(received, ["careful", "four"]);
+            assert_eq!(printer.shed.load(Ordering::Relaxed), 3);
+            assert!(!printer.shedding.load(Ordering::Relaxed));
+        }
+
+        #[test]
+        fn skips_ahead_to_errors() {
+            let (send, recv) = crossbeam_channel::unbounded();
+            let ephemeral = MessageType::ProgEphemeral(Target::Stderr);
+            send.send(message("one", ephemeral)).unwrap();
+            send.send(message("two", ephemeral)).unwrap();
+            send.send(message("done", MessageType::Info())).unwrap();
+            send.send(message("three", ephemeral)).unwrap();
+            let failed = Message {
+                urgent: true,
+                ..message("failed", MessageType::Error())
+            };
+            send.send(failed).unwrap();
+            drop(send);
+
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default().with_max_redraw_rate(1),
+                recv,
+                crossbeam_channel::never(),
+            );
+            printer.last_redraw = Some(Instant::now());
+            printer.urgent.store(1, Ordering::Relaxed);
+            let received: Vec<_> =
+                std::iter::from_fn(|| match printer.await_event(Duration::ZERO) {
+                    Event::Message(msg) => Some(msg.text.to_string()),
+                    _ => None,
+                })
+                .collect();
+
+            // Persistent messages stay in order, without waiting for a redraw
+            assert_eq!(received, ["done", "failed"]);
+        }
+
+        #[test]
+        fn only_counted_errors_handled() {
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default().with_format(OutputFormat::Json),
+                crossbeam_channel::never(),
+                crossbeam_channel::never(),
+            );
+            printer.json_out = Box::new(io::sink());
+            printer.urgent.store(1, Ordering::Relaxed);
+
+            // As from a stream's reader, which sends straight to the queue
+            printer
+                .receive(message("make: failed", MessageType::Error()))
+                .unwrap();
+            assert_eq!(printer.urgent.load(Ordering::Relaxed), 1);
+
+            let counted = Message {
+                urgent: true,
+                ..message("Build failed", MessageType::Error())
+            };
+            printer.receive(counted).unwrap();
+            assert_eq!(printer.urgent.load(Ordering::Relaxed), 0);
+        }
+
+        #[test]
+        fn throttles_redraws() {
+            let (send, recv) = crossbeam_channel::unbounded();
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default().with_max_redraw_rate(10),
+                recv,
+                crossbeam_channel::never(),
+            );
+            printer.last_redraw = Some(Instant::now());
+
+            let sender = thread::spawn(move || {
+                for i in 0..5 {
+                    send.send(message(
+                        &i.to_string(),
+                        MessageType::ProgEphemeral(Target::Stderr),
+                    ))
+                    .unwrap();
+                    thread::sleep(Duration::from_millis(10));
+                }
+            });
+            let first = printer.channel.recv().unwrap();
+            let drawn = printer.coalesce(first);
+            sender.join().unwrap();
+
+            // Every message arrived before the next redraw was due
+            assert_eq!(&*drawn.text, "4");
+        }
+    }
+
+    mod bar {
+        use super::*;
+
+        #[test]
+        fn advance_and_set() {
+            let mut printer = InnerPrinter::new(
+                Verbosity::Brief,
+                Theme::no_color(),
+                Settings::default(),
+                crossbeam_channel::never(),
+                crossbeam_channel::never(),
+            );
+            let mut msg = message("Uploading", MessageType::ProgBar(Target::Null, 100));
+            msg.target = Target::Null;
+            printer.progress_bar(&msg, 100).unwrap();
+
+            let position = |printer: &InnerPrinter| printer.bar.as_ref().unwrap().0.position();
+            printer
+                .apply_control(Control::Bar(BarUpdate::Advance(10)))
+                .unwrap();
+            printer
+                .apply_control(Control::Bar(BarUpdate::Advance(5)))
+                .unwrap();
+            assert_eq!(position(&printer), 15);
+
+            printer
+                .apply_control(Control::Bar(BarUpdate::Set(80)))
+                .unwrap();
+            assert_eq!(position(&printer), 80);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Shipping messages between processes over a Unix socket or FIFO.
+//!
+//! This lets a host process render the live progress of a build running elsewhere,
+//! such as in a container: the build's emitter writes its messages to the socket as
+//! JSON lines, and the host re-emits them on its own emitter.
+#![cfg(unix)]
+
+use std::{
+    fs,
+    io::{self, BufReader, Write},
+    os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "python")]
+use pyo3::{PyResult, pyclass, pymethods};
+
+use crate::emitter::Emitter;
+use crate::error::Result;
+
+/// Check whether a path is an existing FIFO.
+fn is_fifo(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|meta| meta.file_type().is_fifo())
+}
+
+/// Open a Unix socket or FIFO to write messages to.
+///
+/// # Errors
+///
+/// Fails if nothing is listening on the socket, or the FIFO can't be opened.
+pub fn connect(path: &Path) -> Result<Box<dyn Write + Send>> {
+    if is_fifo(path) {
+        Ok(Box::new(fs::OpenOptions::new().write(true).open(path)?))
+    } else {
+        Ok(Box::new(UnixStream::connect(path)?))
+    }
+}
+
+/// Where a listener reads messages from.
+enum Source {
+    /// A Unix socket, bound by the listener.
+    Socket(UnixListener, PathBuf),
+
+    /// A FIFO created ahead of time.
+    Fifo(PathBuf),
+}
+
+/// A Unix socket or FIFO that messages from another process can be received on.
+///
+/// The listener should be set up before the other process is started, so that it
+/// has something to connect to.
+#[cfg_attr(feature = "python", pyclass(name = "RemoteListener", frozen))]
+pub struct Listener {
+    /// Where messages are read from.
+    source: Source,
+}
+
+impl Listener {
+    /// Listen for messages on a path.
+    ///
+    /// If the path is an existing FIFO, messages are read from it. Otherwise, a Unix
+    /// socket is created there, and removed again when the listener is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the socket can't be created.
+    pub fn bind(path: PathBuf) -> Result<Self> {
+        let source = if is_fifo(&path) {
+            Source::Fifo(path)
+        } else {
+            Source::Socket(UnixListener::bind(&path)?, path)
+        };
+        Ok(Self { source })
+    }
+
+    /// Re-emit every message from the other process, until it's done writing.
+    ///
+    /// For a socket, this waits for a single connection.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the socket or FIFO can't be read, or a message can't be logged.
+    pub fn relay(&self, emitter: &Emitter) -> Result<()> {
+        let reader: Box<dyn io::Read> = match &self.source {
+            Source::Socket(listener, _) => Box::new(listener.accept()?.0),
+            Source::Fifo(path) => Box::new(fs::File::open(path)?),
+        };
+        emitter.passthrough(BufReader::new(reader))
+    }
+}
+
+impl Drop for Listener {
+    /// Remove the socket file, which would otherwise stop the path from being reused.
+    fn drop(&mut self) {
+        if let Source::Socket(_, path) = &self.source {
+            _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Listener {
+    /// Listen for messages on a path.
+    ///
+    /// If the path is an existing FIFO, messages are read from it. Otherwise, a Unix
+    /// socket is created there.
+    #[new]
+    fn py_new(path: PathBuf) -> PyResult<Self> {
+        Ok(Self::bind(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead as _;
+
+    mod listener {
+        use super::*;
+
+        #[test]
+        fn socket_lifecycle() {
+            let path = std::env::temp_dir().join(format!("craft-cli-{}.sock", std::process::id()));
+            let listener = Listener::bind(path.clone()).unwrap();
+
+            let mut out = connect(&path).unwrap();
+            writeln!(out, "hello").unwrap();
+            let Source::Socket(socket, _) = &listener.source else {
+                panic!("Expected a socket");
+            };
+            let mut line = String::new();
+            BufReader::new(socket.accept().unwrap().0)
+                .read_line(&mut line)
+                .unwrap();
+            assert_eq!(line, "hello\n");
+
+            drop(listener);
+            assert!(!path.exists());
+        }
+    }
+}
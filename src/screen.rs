@@ -0,0 +1,384 @@
+//! An in-memory terminal for snapshot tests of what the printer draws.
+//!
+//! The printer normally writes to the real terminal, where redraws, overwritten lines
+//! and truncation can only be checked by eye. When given a screen instead, it draws on
+//! a fixed-size grid of cells that interprets the same escape sequences a terminal
+//! would, so the final contents can be compared as plain text.
+
+use std::{
+    fmt, io,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+#[cfg(feature = "python")]
+use pyo3::{pyclass, pymethods};
+
+/// The cell to the right of a double-width character, which it covers.
+const COVERED: char = '\0';
+
+/// A virtual terminal of a fixed size, shared between the printer drawing on it and
+/// the test reading it.
+///
+/// Styles are dropped, and output past the bottom row scrolls the top one away, as on a
+/// real terminal.
+#[derive(Clone)]
+#[cfg_attr(feature = "python", pyclass(frozen, module = "craft_cli._rs.testing"))]
+pub struct Screen {
+    /// The cells and cursor, locked while drawing.
+    grid: Arc<Mutex<Grid>>,
+}
+
+/// The state of a screen.
+struct Grid {
+    /// The rows of cells, top to bottom.
+    cells: Vec<Vec<char>>,
+
+    /// How many columns each row has.
+    cols: usize,
+
+    /// The row the cursor is on.
+    row: usize,
+
+    /// The column the cursor is on.
+    col: usize,
+
+    /// Whether the last column was just written, so the next character goes on a new
+    /// line.
+    wrap_pending: bool,
+}
+
+impl Screen {
+    /// Make a blank screen of `rows` by `cols` cells.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either dimension is zero.
+    #[must_use]
+    pub fn new(rows: u16, cols: u16) -> Self {
+        assert!(rows > 0 && cols > 0, "A screen needs at least one cell");
+        let cols = usize::from(cols);
+        Self {
+            grid: Arc::new(Mutex::new(Grid {
+                cells: vec![vec![' '; cols]; usize::from(rows)],
+                cols,
+                row: 0,
+                col: 0,
+                wrap_pending: false,
+            })),
+        }
+    }
+
+    /// The text on the screen, one line per row with trailing blanks trimmed, and
+    /// without the blank rows at the bottom.
+    #[must_use]
+    pub fn contents(&self) -> String {
+        let grid = self.lock();
+        let mut lines: Vec<String> = grid
+            .cells
+            .iter()
+            .map(|row| {
+                let line: String = row.iter().filter(|&&cell| cell != COVERED).collect();
+                line.trim_end().to_string()
+            })
+            .collect();
+        while lines.last().is_some_and(String::is_empty) {
+            lines.pop();
+        }
+        lines.join("\n")
+    }
+
+    /// How many rows and columns the screen has.
+    #[must_use]
+    pub fn size(&self) -> (u16, u16) {
+        let grid = self.lock();
+        // The dimensions were given as `u16`s
+        #[expect(clippy::cast_possible_truncation)]
+        (grid.cells.len() as u16, grid.cols as u16)
+    }
+
+    /// Write text to the screen, interpreting control characters and escape sequences.
+    pub fn write_str(&self, text: &str) {
+        self.lock().write(text);
+    }
+
+    /// Write a line of text, moving to the start of the next one.
+    pub fn write_line(&self, text: &str) {
+        let mut grid = self.lock();
+        grid.write(text);
+        grid.write("\n");
+    }
+
+    /// Clear the `n` lines above the cursor, leaving it at the start of the first.
+    pub fn clear_last_lines(&self, n: usize) {
+        let mut grid = self.lock();
+        grid.move_up(n);
+        for row in grid.row..(grid.row + n).min(grid.cells.len()) {
+            grid.cells[row].fill(' ');
+        }
+    }
+
+    /// Lock the grid, even if a thread panicked while drawing on it.
+    fn lock(&self) -> std::sync::MutexGuard<'_, Grid> {
+        self.grid.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for Screen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (rows, cols) = self.size();
+        f.debug_struct("Screen")
+            .field("rows", &rows)
+            .field("cols", &cols)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The Python bindings for screens, passed to an emitter's `screen` argument.
+#[cfg(feature = "python")]
+#[pymethods]
+impl Screen {
+    /// Make a blank screen of `rows` by `cols` cells.
+    #[new]
+    fn py_new(rows: u16, cols: u16) -> crate::error::Result<Self> {
+        if rows == 0 || cols == 0 {
+            return Err(crate::error::Error::InvalidArgument(
+                "A screen needs at least one cell".to_string(),
+            ));
+        }
+        Ok(Self::new(rows, cols))
+    }
+
+    /// The text on the screen, one line per row with trailing blanks trimmed.
+    #[pyo3(name = "contents")]
+    fn py_contents(&self) -> String {
+        self.contents()
+    }
+}
+
+impl Grid {
+    /// Write text at the cursor.
+    fn write(&mut self, text: &str) {
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\n' => {
+                    // Terminals translate newlines into a carriage return too
+                    self.col = 0;
+                    self.line_feed();
+                }
+                '\r' => {
+                    self.col = 0;
+                    self.wrap_pending = false;
+                }
+                '\t' => {
+                    self.col = ((self.col / 8 + 1) * 8).min(self.cols - 1);
+                    self.wrap_pending = false;
+                }
+                '\x1b' => match chars.next() {
+                    Some('[') => {
+                        let mut params = String::new();
+                        for c in chars.by_ref() {
+                            if ('\x40'..='\x7e').contains(&c) {
+                                self.control(&params, c);
+                                break;
+                            }
+                            params.push(c);
+                        }
+                    }
+                    // Operating system commands, such as hyperlinks, end with a bell
+                    // or a string terminator
+                    Some(']') => {
+                        while let Some(c) = chars.next() {
+                            if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                                break;
+                            }
+                        }
+                    }
+                    _ => {}
+                },
+                c if c.is_control() => {}
+                c => self.put(c),
+            }
+        }
+    }
+
+    /// Put a printable character at the cursor, wrapping onto the next line when the
+    /// current one is full.
+    fn put(&mut self, c: char) {
+        let width = console::measure_text_width(c.encode_utf8(&mut [0; 4]));
+        if width == 0 {
+            return;
+        }
+        if self.wrap_pending || self.col + width > self.cols {
+            self.col = 0;
+            self.line_feed();
+        }
+        let row = &mut self.cells[self.row];
+        row[self.col] = c;
+        if width == 2 && self.col + 1 < self.cols {
+            row[self.col + 1] = COVERED;
+        }
+        self.col += width;
+        if self.col >= self.cols {
+            self.col = self.cols - 1;
+            self.wrap_pending = true;
+        }
+    }
+
+    /// Move the cursor down a line, scrolling when it's on the bottom row.
+    fn line_feed(&mut self) {
+        self.wrap_pending = false;
+        if self.row + 1 < self.cells.len() {
+            self.row += 1;
+        } else {
+            self.cells.remove(0);
+            self.cells.push(vec![' '; self.cols]);
+        }
+    }
+
+    /// Move the cursor up `n` lines, stopping at the top.
+    fn move_up(&mut self, n: usize) {
+        self.row = self.row.saturating_sub(n);
+        self.wrap_pending = false;
+    }
+
+    /// Apply a control sequence, made of its parameters and final character.
+    ///
+    /// Only the cursor movements and erasures that progress output relies on are
+    /// applied. Styles and modes, such as hiding the cursor, have nothing to draw.
+    fn control(&mut self, params: &str, action: char) {
+        if params.starts_with('?') {
+            return;
+        }
+        let mut args = params.split(';').map(|arg| arg.parse::<usize>().ok());
+        let first = args.next().flatten();
+        let count = first.unwrap_or(1).max(1);
+        match action {
+            'A' => self.move_up(count),
+            'B' => {
+                self.row = (self.row + count).min(self.cells.len() - 1);
+                self.wrap_pending = false;
+            }
+            'C' => {
+                self.col = (self.col + count).min(self.cols - 1);
+                self.wrap_pending = false;
+            }
+            'D' => {
+                self.col = self.col.saturating_sub(count);
+                self.wrap_pending = false;
+            }
+            'G' => {
+                self.col = (count - 1).min(self.cols - 1);
+                self.wrap_pending = false;
+            }
+            'H' => {
+                let col = args.next().flatten().unwrap_or(1).max(1);
+                self.row = (count - 1).min(self.cells.len() - 1);
+                self.col = (col - 1).min(self.cols - 1);
+                self.wrap_pending = false;
+            }
+            'K' => {
+                let row = &mut self.cells[self.row];
+                match first.unwrap_or(0) {
+                    0 => row[self.col..].fill(' '),
+                    1 => row[..=self.col].fill(' '),
+                    _ => row.fill(' '),
+                }
+            }
+            'J' => {
+                let (row, col) = (self.row, self.col);
+                match first.unwrap_or(0) {
+                    0 => {
+                        self.cells[row][col..].fill(' ');
+                        self.cells[row + 1..].iter_mut().for_each(|r| r.fill(' '));
+                    }
+                    1 => {
+                        self.cells[..row].iter_mut().for_each(|r| r.fill(' '));
+                        self.cells[row][..=col].fill(' ');
+                    }
+                    _ => self.cells.iter_mut().for_each(|r| r.fill(' ')),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Screens can be drawn on by progress bars and spinners.
+impl indicatif::TermLike for Screen {
+    fn width(&self) -> u16 {
+        self.size().1
+    }
+
+    fn height(&self) -> u16 {
+        self.size().0
+    }
+
+    fn move_cursor_up(&self, n: usize) -> io::Result<()> {
+        self.lock().move_up(n);
+        Ok(())
+    }
+
+    fn move_cursor_down(&self, n: usize) -> io::Result<()> {
+        self.write_str(&format!("\x1b[{n}B"));
+        Ok(())
+    }
+
+    fn move_cursor_right(&self, n: usize) -> io::Result<()> {
+        self.write_str(&format!("\x1b[{n}C"));
+        Ok(())
+    }
+
+    fn move_cursor_left(&self, n: usize) -> io::Result<()> {
+        self.write_str(&format!("\x1b[{n}D"));
+        Ok(())
+    }
+
+    fn write_line(&self, s: &str) -> io::Result<()> {
+        Screen::write_line(self, s);
+        Ok(())
+    }
+
+    fn write_str(&self, s: &str) -> io::Result<()> {
+        Screen::write_str(self, s);
+        Ok(())
+    }
+
+    fn clear_line(&self) -> io::Result<()> {
+        self.write_str("\r\x1b[2K");
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod screen {
+        use super::*;
+
+        #[test]
+        fn wraps_and_scrolls() {
+            let screen = Screen::new(3, 5);
+            screen.write_line("hello world");
+            screen.write_str("\x1b[32mab\x1b[0m");
+
+            assert_eq!(screen.contents(), " worl\nd\nab");
+        }
+
+        #[test]
+        fn redraws_lines() {
+            let screen = Screen::new(4, 20);
+            screen.write_line("Pulling base");
+            screen.clear_last_lines(1);
+            screen.write_line("Pulled 界");
+            screen.write_str("50%\r\x1b[2K100%");
+
+            assert_eq!(screen.contents(), "Pulled 界\n100%");
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! Status reports to systemd, for applications run as services.
+//!
+//! systemd hands services a socket to report their state on through `NOTIFY_SOCKET`.
+//! When it's set, the emitter reports being ready once created and stopping once
+//! finished, and mirrors its progress messages as the service's status, so that
+//! `systemctl status` shows what a long-running craft tool is doing.
+#![cfg(unix)]
+
+use std::{
+    ffi::OsStr,
+    io,
+    os::unix::{ffi::OsStrExt as _, net::UnixDatagram},
+};
+
+/// A connection to the service manager's notification socket.
+#[derive(Debug)]
+pub struct Notifier {
+    /// The socket states are sent to.
+    socket: UnixDatagram,
+}
+
+impl Notifier {
+    /// Connect to the socket in `NOTIFY_SOCKET`, if running under a service manager
+    /// that set it.
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var_os("NOTIFY_SOCKET")?;
+        Self::connect(&path).ok()
+    }
+
+    /// Connect to the socket at `path`, which is in the abstract namespace if it starts
+    /// with `@`.
+    fn connect(path: &OsStr) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        match path.as_bytes().strip_prefix(b"@") {
+            #[cfg(target_os = "linux")]
+            Some(name) => {
+                use std::os::{linux::net::SocketAddrExt as _, unix::net::SocketAddr};
+                socket.connect_addr(&SocketAddr::from_abstract_name(name)?)?;
+            }
+            #[cfg(not(target_os = "linux"))]
+            Some(_) => return Err(io::ErrorKind::Unsupported.into()),
+            None => socket.connect(path)?,
+        }
+        Ok(Self { socket })
+    }
+
+    /// Report that the application is up and running.
+    pub fn ready(&self) {
+        self.send("READY=1");
+    }
+
+    /// Report that the application is finishing.
+    pub fn stopping(&self) {
+        self.send("STOPPING=1");
+    }
+
+    /// Report what the application is doing, from the first line of `text`.
+    pub fn status(&self, text: &str) {
+        let line = text.lines().next().unwrap_or_default().trim();
+        self.send(&format!("STATUS={}", console::strip_ansi_codes(line)));
+    }
+
+    /// Send a state to the service manager.
+    ///
+    /// This is best-effort, as the application runs the same whether or not its
+    /// status is seen.
+    fn send(&self, state: &str) {
+        _ = self.socket.send(state.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod notifier {
+        use super::*;
+
+        #[test]
+        fn sends_states() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("notify.socket");
+            let listener = UnixDatagram::bind(&path).unwrap();
+            let notifier = Notifier::connect(path.as_os_str()).unwrap();
+
+            notifier.ready();
+            notifier.status("  Pulling \x1b[1mbase\x1b[0m\nfrom the store");
+
+            let mut buf = [0; 64];
+            let len = listener.recv(&mut buf).unwrap();
+            assert_eq!(&buf[..len], b"READY=1");
+            let len = listener.recv(&mut buf).unwrap();
+            assert_eq!(&buf[..len], b"STATUS=Pulling base");
+        }
+    }
+}
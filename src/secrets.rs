@@ -0,0 +1,64 @@
+//! Masking of secret values in all output.
+
+use std::borrow::Cow;
+
+/// The text that replaces secrets in output.
+const MASK: &str = "*****";
+
+/// A set of secret values that must never be output.
+#[derive(Clone, Debug, Default)]
+pub struct Secrets {
+    /// The secret values, longest first so that secrets containing other secrets are
+    /// masked whole.
+    values: Vec<String>,
+}
+
+impl Secrets {
+    /// Register a secret value. Empty values are ignored.
+    pub fn add(&mut self, value: String) {
+        if value.is_empty() || self.values.contains(&value) {
+            return;
+        }
+        self.values.push(value);
+        self.values.sort_by_key(|v| std::cmp::Reverse(v.len()));
+    }
+
+    /// Replace every secret in a piece of text with a mask.
+    pub fn mask<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut result = Cow::Borrowed(text);
+        for secret in &self.values {
+            if result.contains(secret.as_str()) {
+                result = Cow::Owned(result.replace(secret.as_str(), MASK));
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod mask {
+        use super::*;
+
+        #[test]
+        fn basic() {
+            let mut secrets = Secrets::default();
+            secrets.add("hunter2".into());
+
+            assert_eq!(secrets.mask("password is hunter2!"), "password is *****!");
+            assert!(matches!(secrets.mask("nothing here"), Cow::Borrowed(_)));
+        }
+
+        #[test]
+        fn overlapping() {
+            let mut secrets = Secrets::default();
+            secrets.add("token".into());
+            secrets.add("token-123".into());
+            secrets.add(String::new());
+
+            assert_eq!(secrets.mask("token-123 and token"), "***** and *****");
+        }
+    }
+}
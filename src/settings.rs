@@ -0,0 +1,1268 @@
+//! Settings for an emitter, resolved once when it's created.
+
+use std::{ffi::OsString, time::Duration};
+
+#[cfg(feature = "python")]
+use pyo3::{pyclass, pymethods};
+
+use crate::error::{Error, Result};
+use crate::printer::OutputFormat;
+use crate::screen::Screen;
+use crate::theme::Theme;
+use crate::timestamp::Clock;
+
+/// The most bytes of message text queued for the printer by default.
+pub const DEFAULT_MAX_QUEUED_BYTES: usize = 64 * 1024 * 1024;
+
+/// The message pointing to the log when finishing by default, with `{logpath}`
+/// standing for where it is.
+pub const DEFAULT_FINISH_MESSAGE: &str = "Full execution log at '{logpath}'";
+
+/// What the time a scope or section took is shown as in deterministic mode.
+pub const ELAPSED_PLACEHOLDER: &str = "<elapsed>";
+
+/// How the user's attention is drawn when a long run ends, for users who leave long
+/// builds running in the background.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+pub enum Attention {
+    /// Ring the terminal's bell, which most terminals flag the tab or window for.
+    Bell,
+
+    /// Send a desktop notification through the terminal, with OSC 9.
+    Notification,
+
+    /// Send a desktop notification with the platform's tool, `notify-send` or
+    /// `osascript`, for terminals without OSC 9. It's only sent if there's a desktop in
+    /// front of the user to show it on.
+    Desktop,
+}
+
+/// What happens to a message emitted while the printer's queue is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+pub enum Overflow {
+    /// Wait for the printer to make room.
+    #[default]
+    Block,
+
+    /// Drop the ephemeral progress still queued, oldest first, which would soon be
+    /// overwritten anyway, and wait for room. Nothing else is ever dropped, and how
+    /// many messages were is written to the log.
+    DropEphemeral,
+
+    /// Fail with an error, leaving the producer to decide what to do.
+    Error,
+}
+
+/// What happens when an emitter is created while another one controls the terminal.
+///
+/// Only emitters drawing on the real terminal in the human format take control of it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+pub enum Exclusive {
+    /// Create it anyway, leaving both printers to draw over each other.
+    #[default]
+    Off,
+
+    /// Fail with an error.
+    Error,
+
+    /// Give back the emitter in control, if it was created from Python with this same
+    /// setting. Otherwise, fail with an error.
+    Reuse,
+}
+
+/// A continuous integration system, whose logs are read after the fact rather than
+/// watched on a terminal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ci {
+    /// GitHub Actions.
+    GitHubActions,
+
+    /// GitLab CI/CD.
+    GitLab,
+
+    /// Any other system, or one that was only declared with `CRAFT_CLI_CI`.
+    Other,
+}
+
+impl Ci {
+    /// Detect the system running the application from the variables it sets, if any.
+    fn detect(var: impl Fn(&'static str) -> Option<String>) -> Option<Self> {
+        if var("GITHUB_ACTIONS").is_some_and(|value| value == "true") {
+            return Some(Ci::GitHubActions);
+        }
+        if var("GITLAB_CI").is_some() {
+            return Some(Ci::GitLab);
+        }
+        let others = [
+            "BUILDKITE",
+            "CIRCLECI",
+            "TRAVIS",
+            "JENKINS_URL",
+            "TF_BUILD",
+            "TEAMCITY_VERSION",
+            "BITBUCKET_BUILD_NUMBER",
+            "APPVEYOR",
+        ];
+        let declared = var("CI").is_some_and(|value| !matches!(value.as_str(), "0" | "false"));
+        (declared || others.into_iter().any(|name| var(name).is_some())).then_some(Ci::Other)
+    }
+
+    /// The name of the system, as shown in Python.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Ci::GitHubActions => "github-actions",
+            Ci::GitLab => "gitlab",
+            Ci::Other => "other",
+        }
+    }
+}
+
+/// Settings for an emitter and its printer.
+///
+/// Each setting is taken from, in order of precedence:
+///
+/// 1. The environment, so that users can override the choices of an application.
+/// 2. What the application set.
+/// 3. The defaults.
+///
+/// The environment variables are:
+///
+/// - `CRAFT_MANAGED_MODE`: whether running in a build environment managed by a host
+///   process, which owns the terminal. Spinners and cursor control are then off.
+/// - `CRAFT_CLI_SPINNERS`: whether spinners are shown on slow operations.
+/// - `CRAFT_CLI_SPINNER_AFTER`: how many seconds without a message make an operation
+///   slow enough for a spinner.
+/// - `TERM`: on Unix, terminals set to `dumb` or not set at all can't be relied on to
+///   move the cursor, so every message is written plainly on its own line.
+/// - `NO_COLOR`, `FORCE_COLOR`, `CLICOLOR_FORCE` and `CLICOLOR`: whether output is
+///   colored, following their usual conventions.
+/// - `CRAFT_CLI_CLOCK`: the clock timestamps are read from, `local` or `utc`.
+/// - `CRAFT_CLI_TIMESTAMP_FORMAT`: the strftime format of timestamps.
+/// - `CRAFT_CLI_REDRAW_RATE`: the most times a second progress is redrawn.
+/// - `CRAFT_CLI_JOURNAL`: whether log lines are mirrored to the system journal.
+/// - `CRAFT_CLI_DEBUG_RATE`: the most debug and trace lines shown a second, or `0` for
+///   no limit.
+/// - `CRAFT_CLI_CI`: whether running in continuous integration, overriding the
+///   detection from the variables CI systems set, such as `CI` and `GITHUB_ACTIONS`.
+/// - `CRAFT_CLI_CI_MARKERS`: whether sections, warnings and errors are marked for the
+///   continuous integration system to show, where it supports that.
+/// - `CRAFT_CLI_ATTENTION`: how to draw the user's attention when a long run ends,
+///   `bell`, `notification`, `desktop` or `off`.
+/// - `CRAFT_CLI_TITLE`: whether the terminal's title shows the current progress.
+/// - `CRAFT_CLI_HIGHLIGHT`: whether URLs and file paths in messages are highlighted.
+/// - `CRAFT_CLI_REPORT_DURATION`: whether finishing reports how long the whole run
+///   took.
+/// - `CRAFT_CLI_PROBLEM_SUMMARY`: whether finishing sums up the warnings and errors of
+///   the run.
+/// - `CRAFT_CLI_MAX_QUEUED_BYTES`: the most bytes of message text queued for the
+///   terminal before more is left out, or `0` for no limit.
+/// - `CRAFT_CLI_DETERMINISTIC`: the timestamp to show on every message, turning on the
+///   deterministic mode of golden-output tests.
+///
+/// Switches take `1`, `true`, `yes` or `on` to turn them on, and `0`, `false`, `no` or
+/// `off` to turn them off.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "python", pyclass(frozen))]
+// Each switch is independent, so there's no state machine to replace them with
+#[expect(clippy::struct_excessive_bools)]
+pub struct Settings {
+    /// How messages are written.
+    format: OutputFormat,
+
+    /// How messages are styled.
+    theme: Theme,
+
+    /// Whether running in a build environment managed by a host process.
+    managed: bool,
+
+    /// Whether spinners are shown on slow operations.
+    spinners: bool,
+
+    /// How long without a message makes an operation slow enough for a spinner.
+    spinner_after: Duration,
+
+    /// Whether every message is written on its own line, without ANSI sequences.
+    plain: bool,
+
+    /// Whether output is colored, if not left to the terminal.
+    colors: Option<bool>,
+
+    /// The clock timestamps are read from.
+    clock: Clock,
+
+    /// The strftime format of timestamps, if not the clock's default.
+    timestamp_format: Option<String>,
+
+    /// The most times a second ephemeral progress is redrawn.
+    max_redraw_rate: u32,
+
+    /// Whether the cursor is hidden while messages are shown on a terminal.
+    cursor_control: bool,
+
+    /// How far back progress bar updates are averaged over to estimate the time left.
+    eta_window: Duration,
+
+    /// The most lines of each of the debug and trace types shown a second, if limited.
+    max_debug_rate: Option<u32>,
+
+    /// Whether log lines are mirrored to the system journal.
+    journal: bool,
+
+    /// The virtual terminal drawn on instead of the real one, if any.
+    screen: Option<Screen>,
+
+    /// The timestamp shown on every message in deterministic mode, if on.
+    deterministic: Option<String>,
+
+    /// Whether running in continuous integration, if not left to detection.
+    ci_requested: Option<bool>,
+
+    /// The continuous integration system running the application, once resolved.
+    ci: Option<Ci>,
+
+    /// Whether sections, warnings and errors are marked for the continuous integration
+    /// system to show.
+    ci_markers: bool,
+
+    /// Whether the terminal's title shows the current progress.
+    title: bool,
+
+    /// Whether URLs and file paths in messages are highlighted.
+    highlight: bool,
+
+    /// Whether finishing reports how long the whole run took.
+    report_duration: bool,
+
+    /// Whether finishing sums up the warnings and errors of the run.
+    problem_summary: bool,
+
+    /// The message pointing to the log when finishing, if any.
+    finish_message: Option<String>,
+
+    /// How the user's attention is drawn when a long run ends, if at all.
+    attention: Option<Attention>,
+
+    /// How long a run has to take for the user's attention to be drawn when it ends.
+    attention_after: Duration,
+
+    /// How many messages can be queued for the printer, if limited.
+    queue_capacity: Option<usize>,
+
+    /// What happens to a message emitted while the queue is full.
+    overflow: Overflow,
+
+    /// The most bytes of message text queued for the printer, if limited.
+    max_queued_bytes: Option<usize>,
+
+    /// What happens when the emitter is created while another controls the terminal.
+    exclusive: Exclusive,
+
+    /// The name of the application, if not taken from how it was invoked.
+    appname: Option<String>,
+
+    /// The version of the application, if given.
+    version: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            format: OutputFormat::Human,
+            theme: Theme::default(),
+            managed: false,
+            spinners: true,
+            spinner_after: Duration::from_secs(3),
+            plain: false,
+            colors: None,
+            clock: Clock::Local,
+            timestamp_format: None,
+            max_redraw_rate: 20,
+            cursor_control: true,
+            eta_window: Duration::from_secs(5),
+            max_debug_rate: None,
+            journal: false,
+            screen: None,
+            deterministic: None,
+            ci_requested: None,
+            ci: None,
+            ci_markers: false,
+            title: false,
+            highlight: false,
+            report_duration: false,
+            problem_summary: false,
+            finish_message: Some(DEFAULT_FINISH_MESSAGE.to_string()),
+            attention: None,
+            attention_after: Duration::from_secs(30),
+            queue_capacity: None,
+            overflow: Overflow::Block,
+            max_queued_bytes: Some(DEFAULT_MAX_QUEUED_BYTES),
+            exclusive: Exclusive::Off,
+            appname: None,
+            version: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Write messages in the given format.
+    #[must_use]
+    pub fn with_format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Style messages with `theme`, rather than the default theme.
+    #[must_use]
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Run as if in a build environment managed by a host process, which owns the
+    /// terminal.
+    #[must_use]
+    pub fn with_managed(mut self, managed: bool) -> Self {
+        self.managed = managed;
+        self
+    }
+
+    /// Show spinners on slow operations, or never.
+    #[must_use]
+    pub fn with_spinners(mut self, spinners: bool) -> Self {
+        self.spinners = spinners;
+        self
+    }
+
+    /// Write every message on its own line without ANSI sequences, as for terminals
+    /// that can't move the cursor.
+    #[must_use]
+    pub fn with_plain(mut self, plain: bool) -> Self {
+        self.plain = plain;
+        self
+    }
+
+    /// Always or never color output, rather than only on terminals.
+    #[must_use]
+    pub fn with_colors(mut self, colors: bool) -> Self {
+        self.colors = Some(colors);
+        self
+    }
+
+    /// Read timestamps from the given clock. Timestamps are in local time by default.
+    #[must_use]
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Format timestamps with the given strftime format, such as `"%H:%M:%S%.6f"`.
+    #[must_use]
+    pub fn with_timestamp_format(mut self, format: String) -> Self {
+        self.timestamp_format = Some(format);
+        self
+    }
+
+    /// Redraw ephemeral progress messages at most `per_second` times a second,
+    /// skipping the ones in between.
+    ///
+    /// Lower rates spare slow terminals the flicker and CPU of redrawing for every
+    /// message in a fast burst.
+    #[must_use]
+    pub fn with_max_redraw_rate(mut self, per_second: u32) -> Self {
+        self.max_redraw_rate = per_second.max(1);
+        self
+    }
+
+    /// Hide the cursor while messages are shown, or leave it alone entirely.
+    ///
+    /// The cursor is only ever hidden on interactive terminals.
+    #[must_use]
+    pub fn with_cursor_control(mut self, enabled: bool) -> Self {
+        self.cursor_control = enabled;
+        self
+    }
+
+    /// Show a spinner once an operation went `after` without a message.
+    ///
+    /// Some applications consider a second already slow, and others would rather not
+    /// see spinners before ten.
+    #[must_use]
+    pub fn with_spinner_after(mut self, after: Duration) -> Self {
+        // Checking for silence any more often would keep the printer busy
+        self.spinner_after = after.max(Duration::from_millis(100));
+        self
+    }
+
+    /// Estimate the time left on progress bars from the updates over the past
+    /// `window`.
+    ///
+    /// Longer windows give steadier estimates for producers that update in bursts, at
+    /// the cost of reacting slower to real changes in speed.
+    #[must_use]
+    pub fn with_eta_window(mut self, window: Duration) -> Self {
+        self.eta_window = window;
+        self
+    }
+
+    /// Show at most `per_second` debug lines and as many trace lines a second, or
+    /// `None` for no limit.
+    ///
+    /// The lines over the limit are still logged, and summarized on the terminal with
+    /// how many were left out. This keeps interactive terminals responsive through
+    /// extremely verbose runs.
+    #[must_use]
+    pub fn with_max_debug_rate(mut self, per_second: Option<u32>) -> Self {
+        self.max_debug_rate = per_second.filter(|&rate| rate > 0);
+        self
+    }
+
+    /// Mirror every log line to systemd-journald, or to syslog where there's no
+    /// journald, as for tools running as services.
+    ///
+    /// This only has an effect on Unix.
+    #[must_use]
+    pub fn with_journal(mut self, journal: bool) -> Self {
+        self.journal = journal;
+        self
+    }
+
+    /// Draw on `screen` instead of the terminal, to check the final contents of the
+    /// screen in snapshot tests.
+    ///
+    /// The screen is treated as a capable interactive terminal whatever `TERM` says,
+    /// so spinners, redraws and progress bars are drawn on it.
+    #[must_use]
+    pub fn with_screen(mut self, screen: Screen) -> Self {
+        self.screen = Some(screen);
+        self
+    }
+
+    /// Make output the same on every run, for golden-output tests of applications, or
+    /// `None` to leave it live.
+    ///
+    /// Spinners are turned off, every timestamp is shown as `timestamp` and the time
+    /// scopes and sections took is shown as `<elapsed>`.
+    #[must_use]
+    pub fn with_deterministic(mut self, timestamp: Option<String>) -> Self {
+        self.deterministic = timestamp;
+        self
+    }
+
+    /// Whether to write output for continuous integration logs, rather than detecting
+    /// it from the environment.
+    ///
+    /// In continuous integration, every message is written plainly on its own line and
+    /// timestamped, as spinners and redraws turn logs into a mess of escape sequences.
+    #[must_use]
+    pub fn with_ci(mut self, ci: bool) -> Self {
+        self.ci_requested = Some(ci);
+        self
+    }
+
+    /// Mark sections, warnings and errors for the continuous integration system to show
+    /// in its interface, where it supports that.
+    ///
+    /// On GitHub Actions, outermost sections become collapsible groups of the log, and
+    /// warnings and errors become annotations of the run. On GitLab, sections become
+    /// collapsible and timed sections of the job log.
+    #[must_use]
+    pub fn with_ci_markers(mut self, markers: bool) -> Self {
+        self.ci_markers = markers;
+        self
+    }
+
+    /// Show the current progress message in the terminal's title, as for users keeping
+    /// an eye on a long build from another tab.
+    ///
+    /// The title the terminal had is restored when the emitter finishes, on terminals
+    /// that can save it.
+    #[must_use]
+    pub fn with_title(mut self, title: bool) -> Self {
+        self.title = title;
+        self
+    }
+
+    /// Highlight the URLs and existing file paths in messages, underlining them and
+    /// making them hyperlinks where the terminal supports it, so they stand out of long
+    /// error output.
+    #[must_use]
+    pub fn with_highlight(mut self, highlight: bool) -> Self {
+        self.highlight = highlight;
+        self
+    }
+
+    /// Report how long the whole run took when finishing, as a progress message that
+    /// always makes it to the log.
+    #[must_use]
+    pub fn with_report_duration(mut self, report_duration: bool) -> Self {
+        self.report_duration = report_duration;
+        self
+    }
+
+    /// Sum up the warnings and errors of the run when finishing, in brief and verbose
+    /// modes, so the ones scrolled away by progress output aren't missed.
+    #[must_use]
+    pub fn with_problem_summary(mut self, problem_summary: bool) -> Self {
+        self.problem_summary = problem_summary;
+        self
+    }
+
+    /// Point to the log with `message` when finishing, where `{logpath}` stands for
+    /// where it is, or with nothing if `None`, for applications printing their own
+    /// closing summary.
+    #[must_use]
+    pub fn with_finish_message(mut self, message: Option<String>) -> Self {
+        self.finish_message = message;
+        self
+    }
+
+    /// Draw the user's attention with `attention` when a run that took at least `after`
+    /// finishes or fails, or `None` to leave the user alone.
+    #[must_use]
+    pub fn with_attention(mut self, attention: Option<Attention>, after: Duration) -> Self {
+        self.attention = attention;
+        self.attention_after = after;
+        self
+    }
+
+    /// Queue at most `capacity` messages for the printer, or as many as are emitted if
+    /// `None`.
+    ///
+    /// A limit keeps memory in check when messages are emitted faster than the terminal
+    /// can take them, as from a producer streaming logs. What happens once the queue
+    /// is full is chosen with `with_overflow`.
+    #[must_use]
+    pub fn with_queue_capacity(mut self, capacity: Option<usize>) -> Self {
+        // A zero-capacity channel would wait on the printer for every message
+        self.queue_capacity = capacity.map(|capacity| capacity.max(1));
+        self
+    }
+
+    /// Handle messages emitted while the printer's queue is full with `overflow`.
+    #[must_use]
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Queue at most `bytes` of message text for the printer, or as much as is emitted
+    /// if `None`.
+    ///
+    /// Messages over the limit are still logged, but left out of the terminal with a
+    /// summary once it caught up, protecting against a runaway subprocess streaming
+    /// gigabytes of output. Only ephemeral progress, debug and trace messages are ever
+    /// left out.
+    #[must_use]
+    pub fn with_max_queued_bytes(mut self, bytes: Option<usize>) -> Self {
+        self.max_queued_bytes = bytes.filter(|&bytes| bytes > 0);
+        self
+    }
+
+    /// Handle another emitter controlling the terminal when creating this one with
+    /// `exclusive`, rather than having two printers fight over the cursor.
+    #[must_use]
+    pub fn with_exclusive(mut self, exclusive: Exclusive) -> Self {
+        self.exclusive = exclusive;
+        self
+    }
+
+    /// Name the application `appname`, or `None` to take its name from how it was
+    /// invoked.
+    ///
+    /// Giving a name or a version starts the log with a header about the run, for
+    /// whoever triages a problem from it.
+    #[must_use]
+    pub fn with_appname(mut self, appname: Option<String>) -> Self {
+        self.appname = appname;
+        self
+    }
+
+    /// Give the version of the application, written in the log header.
+    #[must_use]
+    pub fn with_version(mut self, version: Option<String>) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Apply the environment on top of these settings.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an environment variable is set to a value that can't be used.
+    pub fn resolve(self) -> Result<Self> {
+        self.resolve_with(|name| std::env::var_os(name))
+    }
+
+    /// Apply an environment on top of these settings.
+    pub(crate) fn resolve_with(mut self, var: impl Fn(&str) -> Option<OsString>) -> Result<Self> {
+        // Empty values count as unset, as with most conventions on environment
+        // variables
+        let var = |name: &'static str| {
+            var(name)
+                .filter(|value| !value.is_empty())
+                .map(|value| (name, value.to_string_lossy().into_owned()))
+        };
+
+        if let Some((name, value)) = var("CRAFT_MANAGED_MODE") {
+            self.managed = parse_switch(name, &value)?;
+        }
+        if let Some((name, value)) = var("CRAFT_CLI_SPINNERS") {
+            self.spinners = parse_switch(name, &value)?;
+        }
+        if let Some((name, value)) = var("CRAFT_CLI_CLOCK") {
+            self.clock = match value.to_lowercase().as_str() {
+                "local" => Clock::Local,
+                "utc" => Clock::Utc,
+                _ => return Err(invalid(name, &value)),
+            };
+        }
+        if let Some((_, value)) = var("CRAFT_CLI_TIMESTAMP_FORMAT") {
+            self.timestamp_format = Some(value);
+        }
+        if let Some((name, value)) = var("CRAFT_CLI_SPINNER_AFTER") {
+            let after = value
+                .parse()
+                .ok()
+                .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                .ok_or_else(|| invalid(name, &value))?;
+            self = self.with_spinner_after(after);
+        }
+        if let Some((name, value)) = var("CRAFT_CLI_REDRAW_RATE") {
+            let rate = value.parse().map_err(|_| invalid(name, &value))?;
+            self = self.with_max_redraw_rate(rate);
+        }
+        if let Some((name, value)) = var("CRAFT_CLI_JOURNAL") {
+            self.journal = parse_switch(name, &value)?;
+        }
+        if let Some((name, value)) = var("CRAFT_CLI_DEBUG_RATE") {
+            let rate = value.parse().map_err(|_| invalid(name, &value))?;
+            self = self.with_max_debug_rate(Some(rate));
+        }
+        if let Some((name, value)) = var("CRAFT_CLI_MAX_QUEUED_BYTES") {
+            let bytes = value.parse().map_err(|_| invalid(name, &value))?;
+            self = self.with_max_queued_bytes(Some(bytes));
+        }
+        if let Some((_, value)) = var("CRAFT_CLI_DETERMINISTIC") {
+            self.deterministic = Some(value);
+        }
+        if let Some((name, value)) = var("CRAFT_CLI_ATTENTION") {
+            self.attention = match value.to_lowercase().as_str() {
+                "bell" => Some(Attention::Bell),
+                "notification" => Some(Attention::Notification),
+                "desktop" => Some(Attention::Desktop),
+                "off" => None,
+                _ => return Err(invalid(name, &value)),
+            };
+        }
+        if let Some((name, value)) = var("CRAFT_CLI_TITLE") {
+            self.title = parse_switch(name, &value)?;
+        }
+        if let Some((name, value)) = var("CRAFT_CLI_HIGHLIGHT") {
+            self.highlight = parse_switch(name, &value)?;
+        }
+        if let Some((name, value)) = var("CRAFT_CLI_REPORT_DURATION") {
+            self.report_duration = parse_switch(name, &value)?;
+        }
+        if let Some((name, value)) = var("CRAFT_CLI_PROBLEM_SUMMARY") {
+            self.problem_summary = parse_switch(name, &value)?;
+        }
+        if let Some((name, value)) = var("CRAFT_CLI_CI_MARKERS") {
+            self.ci_markers = parse_switch(name, &value)?;
+        }
+        if let Some((name, value)) = var("CRAFT_CLI_CI") {
+            self.ci_requested = Some(parse_switch(name, &value)?);
+        }
+        let detected = Ci::detect(|name| var(name).map(|(_, value)| value));
+        self.ci = match self.ci_requested {
+            Some(true) => Some(detected.unwrap_or(Ci::Other)),
+            Some(false) => None,
+            None => detected,
+        };
+        Ok(self.adjusted(var("TERM").map(|(_, term)| term)))
+    }
+
+    /// Turn off what can't be shown where the output ends up, on a terminal of type
+    /// `term` if the environment gives one.
+    fn adjusted(mut self, term: Option<String>) -> Self {
+        // Embedded consoles in editors and build tools often leave TERM unset
+        if self.screen.is_none() && cfg!(unix) && term.is_none_or(|term| term == "dumb") {
+            self.plain = true;
+        }
+
+        // Nobody watches the log as it's written, so there's no point animating it
+        if self.ci.is_some() {
+            self.plain = true;
+        }
+
+        // The host process draws everything, so there's nothing to animate
+        if self.managed {
+            self.spinners = false;
+            self.cursor_control = false;
+            self.title = false;
+            self.attention = None;
+        }
+        // Spinners redraw on a timer, which no two runs agree on
+        if self.deterministic.is_some() {
+            self.spinners = false;
+        }
+        // Spinners, the cursor, the title and highlights need escape sequences
+        if self.plain {
+            self.spinners = false;
+            self.cursor_control = false;
+            self.title = false;
+            self.highlight = false;
+        }
+        self
+    }
+
+    /// How messages are written.
+    #[must_use]
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// How messages are styled.
+    #[must_use]
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Whether running in a build environment managed by a host process.
+    #[must_use]
+    pub fn managed(&self) -> bool {
+        self.managed
+    }
+
+    /// Whether spinners are shown on slow operations.
+    #[must_use]
+    pub fn spinners(&self) -> bool {
+        self.spinners
+    }
+
+    /// Whether every message is written on its own line, without ANSI sequences.
+    #[must_use]
+    pub fn plain(&self) -> bool {
+        self.plain
+    }
+
+    /// Whether output is colored, if not left to the environment and terminal.
+    #[must_use]
+    pub fn colors(&self) -> Option<bool> {
+        self.colors
+    }
+
+    /// The clock timestamps are read from.
+    #[must_use]
+    pub fn clock(&self) -> Clock {
+        self.clock
+    }
+
+    /// The strftime format of timestamps, if not the clock's default.
+    #[must_use]
+    pub fn timestamp_format(&self) -> Option<&str> {
+        self.timestamp_format.as_deref()
+    }
+
+    /// The shortest time between redraws of ephemeral progress messages.
+    #[must_use]
+    pub fn redraw_interval(&self) -> Duration {
+        Duration::from_secs(1) / self.max_redraw_rate
+    }
+
+    /// Whether the cursor is hidden while messages are shown on a terminal.
+    #[must_use]
+    pub fn cursor_control(&self) -> bool {
+        self.cursor_control
+    }
+
+    /// How long without a message makes an operation slow enough for a spinner.
+    #[must_use]
+    pub fn spinner_after(&self) -> Duration {
+        self.spinner_after
+    }
+
+    /// How far back progress bar updates are averaged over to estimate the time left.
+    #[must_use]
+    pub fn eta_window(&self) -> Duration {
+        self.eta_window
+    }
+
+    /// The most lines of each of the debug and trace types shown a second, if limited.
+    #[must_use]
+    pub fn max_debug_rate(&self) -> Option<u32> {
+        self.max_debug_rate
+    }
+
+    /// Whether log lines are mirrored to the system journal.
+    #[must_use]
+    pub fn journal(&self) -> bool {
+        self.journal
+    }
+
+    /// The virtual terminal drawn on instead of the real one, if any.
+    #[must_use]
+    pub fn screen(&self) -> Option<&Screen> {
+        self.screen.as_ref()
+    }
+
+    /// The timestamp shown on every message in deterministic mode, if on.
+    #[must_use]
+    pub fn deterministic(&self) -> Option<&str> {
+        self.deterministic.as_deref()
+    }
+
+    /// The continuous integration system running the application, if any.
+    #[must_use]
+    pub fn ci(&self) -> Option<Ci> {
+        self.ci
+    }
+
+    /// Whether the terminal's title shows the current progress.
+    #[must_use]
+    pub fn title(&self) -> bool {
+        self.title
+    }
+
+    /// Whether URLs and file paths in messages are highlighted.
+    #[must_use]
+    pub fn highlight(&self) -> bool {
+        self.highlight
+    }
+
+    /// Whether finishing reports how long the whole run took.
+    #[must_use]
+    pub fn report_duration(&self) -> bool {
+        self.report_duration
+    }
+
+    /// Whether finishing sums up the warnings and errors of the run.
+    #[must_use]
+    pub fn problem_summary(&self) -> bool {
+        self.problem_summary
+    }
+
+    /// The message pointing to the log when finishing, if any.
+    #[must_use]
+    pub fn finish_message(&self) -> Option<&str> {
+        self.finish_message.as_deref()
+    }
+
+    /// How the user's attention is drawn when a long run ends, if at all.
+    #[must_use]
+    pub fn attention(&self) -> Option<Attention> {
+        self.attention
+    }
+
+    /// How long a run has to take for the user's attention to be drawn when it ends.
+    #[must_use]
+    pub fn attention_after(&self) -> Duration {
+        self.attention_after
+    }
+
+    /// How many messages can be queued for the printer, if limited.
+    #[must_use]
+    pub fn queue_capacity(&self) -> Option<usize> {
+        self.queue_capacity
+    }
+
+    /// What happens to a message emitted while the printer's queue is full.
+    #[must_use]
+    pub fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    /// The most bytes of message text queued for the printer, if limited.
+    #[must_use]
+    pub fn max_queued_bytes(&self) -> Option<usize> {
+        self.max_queued_bytes
+    }
+
+    /// What happens when the emitter is created while another controls the terminal.
+    #[must_use]
+    pub fn exclusive(&self) -> Exclusive {
+        self.exclusive
+    }
+
+    /// The name of the application, if not taken from how it was invoked.
+    #[must_use]
+    pub fn appname(&self) -> Option<&str> {
+        self.appname.as_deref()
+    }
+
+    /// The version of the application, if given.
+    #[must_use]
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// The continuous integration system that sections, warnings and errors are marked
+    /// for, if any.
+    #[must_use]
+    pub fn ci_markers(&self) -> Option<Ci> {
+        self.ci.filter(|_| self.ci_markers)
+    }
+
+    /// Whether every message on stderr is timestamped, whatever the verbosity.
+    #[must_use]
+    pub fn timestamped(&self) -> bool {
+        self.ci.is_some()
+    }
+}
+
+/// The settings are only exposed to Python to read, to help debug where they came
+/// from.
+#[cfg(feature = "python")]
+#[pymethods]
+impl Settings {
+    /// How messages are written.
+    #[getter(format)]
+    fn py_format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// How messages are styled.
+    #[getter(theme)]
+    fn py_theme(&self) -> Theme {
+        self.theme.clone()
+    }
+
+    /// Whether running in a build environment managed by a host process.
+    #[getter(managed)]
+    fn py_managed(&self) -> bool {
+        self.managed
+    }
+
+    /// Whether spinners are shown on slow operations.
+    #[getter(spinners)]
+    fn py_spinners(&self) -> bool {
+        self.spinners
+    }
+
+    /// Whether every message is written on its own line, without ANSI sequences.
+    #[getter(plain)]
+    fn py_plain(&self) -> bool {
+        self.plain
+    }
+
+    /// Whether output is colored, or `None` if left to the environment and terminal.
+    #[getter(colors)]
+    fn py_colors(&self) -> Option<bool> {
+        self.colors
+    }
+
+    /// The clock timestamps are read from.
+    #[getter(clock)]
+    fn py_clock(&self) -> Clock {
+        self.clock
+    }
+
+    /// The strftime format of timestamps, or `None` for the clock's default.
+    #[getter(timestamp_format)]
+    fn py_timestamp_format(&self) -> Option<String> {
+        self.timestamp_format.clone()
+    }
+
+    /// The most times a second ephemeral progress is redrawn.
+    #[getter(max_redraw_rate)]
+    fn py_max_redraw_rate(&self) -> u32 {
+        self.max_redraw_rate
+    }
+
+    /// Whether the cursor is hidden while messages are shown on a terminal.
+    #[getter(cursor_control)]
+    fn py_cursor_control(&self) -> bool {
+        self.cursor_control
+    }
+
+    /// How many seconds without a message make an operation slow enough for a spinner.
+    #[getter(spinner_after)]
+    fn py_spinner_after(&self) -> f64 {
+        self.spinner_after.as_secs_f64()
+    }
+
+    /// How many seconds of progress bar updates are averaged over to estimate the
+    /// time left.
+    #[getter(eta_window)]
+    fn py_eta_window(&self) -> f64 {
+        self.eta_window.as_secs_f64()
+    }
+
+    /// The most lines of each of the debug and trace types shown a second, or `None`
+    /// if not limited.
+    #[getter(max_debug_rate)]
+    fn py_max_debug_rate(&self) -> Option<u32> {
+        self.max_debug_rate
+    }
+
+    /// Whether log lines are mirrored to the system journal.
+    #[getter(journal)]
+    fn py_journal(&self) -> bool {
+        self.journal
+    }
+
+    /// The timestamp shown on every message in deterministic mode, or `None` if off.
+    #[getter(deterministic)]
+    fn py_deterministic(&self) -> Option<String> {
+        self.deterministic.clone()
+    }
+
+    /// The continuous integration system running the application, such as
+    /// `"github-actions"` or `"gitlab"`, or `None` if not running in one.
+    #[getter(ci)]
+    fn py_ci(&self) -> Option<&'static str> {
+        self.ci.map(Ci::name)
+    }
+
+    /// Whether the terminal's title shows the current progress.
+    #[getter(title)]
+    fn py_title(&self) -> bool {
+        self.title
+    }
+
+    /// Whether URLs and file paths in messages are highlighted.
+    #[getter(highlight)]
+    fn py_highlight(&self) -> bool {
+        self.highlight
+    }
+
+    /// Whether finishing reports how long the whole run took.
+    #[getter(report_duration)]
+    fn py_report_duration(&self) -> bool {
+        self.report_duration
+    }
+
+    /// Whether finishing sums up the warnings and errors of the run.
+    #[getter(problem_summary)]
+    fn py_problem_summary(&self) -> bool {
+        self.problem_summary
+    }
+
+    /// The message pointing to the log when finishing, or `None` if there's none.
+    #[getter(finish_message)]
+    fn py_finish_message(&self) -> Option<String> {
+        self.finish_message.clone()
+    }
+
+    /// How the user's attention is drawn when a long run ends, or `None` if it isn't.
+    #[getter(attention)]
+    fn py_attention(&self) -> Option<Attention> {
+        self.attention
+    }
+
+    /// How many seconds a run has to take for the user's attention to be drawn when it
+    /// ends.
+    #[getter(attention_after)]
+    fn py_attention_after(&self) -> f64 {
+        self.attention_after.as_secs_f64()
+    }
+
+    /// Whether sections, warnings and errors are marked for the continuous integration
+    /// system to show.
+    #[getter(ci_markers)]
+    fn py_ci_markers(&self) -> bool {
+        self.ci_markers
+    }
+
+    /// How many messages can be queued for the printer, or `None` if not limited.
+    #[getter(queue_capacity)]
+    fn py_queue_capacity(&self) -> Option<usize> {
+        self.queue_capacity
+    }
+
+    /// What happens to a message emitted while the printer's queue is full.
+    #[getter(overflow)]
+    fn py_overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    /// The most bytes of message text queued for the printer, or `None` if not limited.
+    #[getter(max_queued_bytes)]
+    fn py_max_queued_bytes(&self) -> Option<usize> {
+        self.max_queued_bytes
+    }
+
+    /// What happens when the emitter is created while another controls the terminal.
+    #[getter(exclusive)]
+    fn py_exclusive(&self) -> Exclusive {
+        self.exclusive
+    }
+
+    /// The name of the application, or `None` if taken from how it was invoked.
+    #[getter(appname)]
+    fn py_appname(&self) -> Option<String> {
+        self.appname.clone()
+    }
+
+    /// The version of the application, or `None` if not given.
+    #[getter(version)]
+    fn py_version(&self) -> Option<String> {
+        self.version.clone()
+    }
+}
+
+/// Parse the value of an environment variable that turns something on or off.
+fn parse_switch(name: &str, value: &str) -> Result<bool> {
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(invalid(name, value)),
+    }
+}
+
+/// The error for an environment variable set to a value that can't be used.
+fn invalid(name: &str, value: &str) -> Error {
+    Error::InvalidArgument(format!("Invalid value for {name}: {value:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::env;
+
+    mod resolve {
+        use super::*;
+
+        #[test]
+        fn environment_wins() {
+            let settings = Settings::default()
+                .with_clock(Clock::Local)
+                .with_max_redraw_rate(5)
+                .resolve_with(env(&[
+                    ("CRAFT_CLI_CLOCK", "UTC"),
+                    ("CRAFT_CLI_REDRAW_RATE", "10"),
+                ]))
+                .unwrap();
+
+            assert_eq!(settings.clock(), Clock::Utc);
+            assert_eq!(settings.redraw_interval(), Duration::from_millis(100));
+        }
+
+        #[test]
+        fn spinner_after_seconds() {
+            let settings = Settings::default()
+                .with_spinner_after(Duration::from_secs(10))
+                .resolve_with(env(&[("CRAFT_CLI_SPINNER_AFTER", "0.5")]))
+                .unwrap();
+
+            assert_eq!(settings.spinner_after(), Duration::from_millis(500));
+        }
+
+        #[test]
+        fn unlimited_debug_rate() {
+            let settings = Settings::default()
+                .with_max_debug_rate(Some(200))
+                .resolve_with(env(&[("CRAFT_CLI_DEBUG_RATE", "0")]))
+                .unwrap();
+
+            assert_eq!(settings.max_debug_rate(), None);
+        }
+
+        #[test]
+        fn deterministic_stops_spinners() {
+            let settings = Settings::default()
+                .resolve_with(env(&[
+                    ("CRAFT_CLI_DETERMINISTIC", "2024-01-01 00:00:00"),
+                    ("TERM", "xterm"),
+                ]))
+                .unwrap();
+
+            assert_eq!(settings.deterministic(), Some("2024-01-01 00:00:00"));
+            assert!(!settings.spinners());
+        }
+
+        #[test]
+        fn ci_detected() {
+            let settings = Settings::default()
+                .resolve_with(env(&[("GITLAB_CI", "true"), ("TERM", "xterm")]))
+                .unwrap();
+
+            assert_eq!(settings.ci(), Some(Ci::GitLab));
+            assert!(settings.plain());
+            assert!(!settings.spinners());
+            assert!(settings.timestamped());
+        }
+
+        #[test]
+        fn ci_overridden() {
+            let settings = Settings::default()
+                .resolve_with(env(&[
+                    ("CI", "true"),
+                    ("CRAFT_CLI_CI", "0"),
+                    ("TERM", "xterm"),
+                ]))
+                .unwrap();
+
+            assert_eq!(settings.ci(), None);
+            assert!(!settings.plain());
+        }
+
+        #[test]
+        fn arguments_kept() {
+            let settings = Settings::default()
+                .with_spinners(false)
+                .resolve_with(env(&[("CRAFT_CLI_CLOCK", ""), ("TERM", "xterm")]))
+                .unwrap();
+
+            assert!(!settings.spinners());
+            assert!(!settings.plain());
+            assert_eq!(settings.clock(), Clock::Local);
+        }
+
+        #[test]
+        fn dumb_terminal() {
+            let settings = Settings::default()
+                .resolve_with(env(&[("TERM", "dumb")]))
+                .unwrap();
+
+            assert!(settings.plain());
+            assert!(!settings.spinners());
+            assert!(!settings.cursor_control());
+        }
+
+        #[test]
+        fn managed_mode() {
+            let settings = Settings::default()
+                .resolve_with(env(&[("CRAFT_MANAGED_MODE", "1")]))
+                .unwrap();
+
+            assert!(settings.managed());
+            assert!(!settings.spinners());
+            assert!(!settings.cursor_control());
+        }
+
+        #[test]
+        fn attention_turned_off() {
+            let settings = Settings::default()
+                .with_attention(Some(Attention::Bell), Duration::from_secs(5))
+                .resolve_with(env(&[("CRAFT_CLI_ATTENTION", "off"), ("TERM", "xterm")]))
+                .unwrap();
+
+            assert_eq!(settings.attention(), None);
+        }
+
+        #[test]
+        fn attention_desktop() {
+            let settings = Settings::default()
+                .resolve_with(env(&[
+                    ("CRAFT_CLI_ATTENTION", "Desktop"),
+                    ("TERM", "xterm"),
+                ]))
+                .unwrap();
+
+            assert_eq!(settings.attention(), Some(Attention::Desktop));
+        }
+
+        #[test]
+        fn invalid_value() {
+            let err = Settings::default()
+                .resolve_with(env(&[("CRAFT_CLI_SPINNERS", "sometimes")]))
+                .unwrap_err();
+
+            assert!(err.to_string().contains("CRAFT_CLI_SPINNERS"));
+        }
+    }
+}
@@ -0,0 +1,272 @@
+//! Output of subprocesses, shown through the emitter as it's written.
+//!
+//! A stream is an OS pipe: its write end is handed to a subprocess as its stdout or
+//! stderr, and a dedicated thread reads the other end, logging every line and showing
+//! it as progress.
+
+use std::{
+    io::{self, BufReader, PipeReader, PipeWriter},
+    sync::{Arc, Mutex, PoisonError},
+    thread::{self, JoinHandle},
+};
+
+use crossbeam_channel::{Receiver, Sender};
+#[cfg(all(unix, feature = "python"))]
+use pyo3::{
+    Bound, IntoPyObject as _, PyAny, PyResult, Python, pyclass, pymethods, types::PyAnyMethods,
+};
+
+use crate::log_bridge::Sink;
+use crate::printer::{MessageType, Target, Verbosity};
+
+/// The write end of a stream's pipe, shared with the emitter so it can close it when
+/// finishing, until it's closed.
+type SharedWriter = Arc<Mutex<Option<PipeWriter>>>;
+
+/// A pipe whose output is shown through the emitter, line by line.
+///
+/// The reader thread ends once every copy of the write end is closed, including the
+/// ones held by subprocesses, so closing the stream waits for those to exit.
+#[cfg_attr(all(unix, feature = "python"), pyclass)]
+pub struct Stream {
+    /// The write end of the pipe.
+    writer: SharedWriter,
+
+    /// Hangs up once the reader thread is done.
+    done: Receiver<()>,
+}
+
+/// The thread reading a stream, supervised by the emitter.
+pub(crate) struct Reader {
+    /// The write end of the pipe it reads.
+    writer: SharedWriter,
+
+    /// The thread itself.
+    thread: JoinHandle<()>,
+}
+
+impl Stream {
+    /// Open a pipe, showing each line written to it through `sink` with `prefix`.
+    ///
+    /// If `keep_ansi` is set, the ANSI sequences in lines are kept on the terminal from
+    /// verbose mode up, where lines aren't truncated. They're always stripped from
+    /// the log.
+    pub(crate) fn open(sink: Sink, prefix: String, keep_ansi: bool) -> io::Result<(Self, Reader)> {
+        let (reader, writer) = io::pipe()?;
+        let (done_send, done) = crossbeam_channel::bounded(0);
+        let thread = thread::Builder::new()
+            .name("craft-cli-stream".to_string())
+            .spawn(move || read(&sink, &prefix, keep_ansi, reader, done_send))?;
+        let writer = Arc::new(Mutex::new(Some(writer)));
+        Ok((
+            Self {
+                writer: Arc::clone(&writer),
+                done,
+            },
+            Reader { writer, thread },
+        ))
+    }
+
+    /// A copy of the write end of the pipe, to give to a subprocess.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the stream was already closed, or the write end can't be copied.
+    pub fn writer(&self) -> io::Result<PipeWriter> {
+        match &*self.writer.lock().unwrap_or_else(PoisonError::into_inner) {
+            Some(writer) => writer.try_clone(),
+            None => Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "The stream is closed",
+            )),
+        }
+    }
+
+    /// Close the write end, and wait for everything written so far to be shown.
+    pub fn close(&mut self) {
+        close(&self.writer);
+        // Only ever hangs up, once the reader is done
+        _ = self.done.recv();
+    }
+}
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+impl Reader {
+    /// Whether the thread is done, and needs no joining.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Close the write end, and wait for the thread to show everything written so far.
+    ///
+    /// Returns whether the thread ended cleanly, rather than panicking.
+    pub fn join(self) -> bool {
+        close(&self.writer);
+        self.thread.join().is_ok()
+    }
+}
+
+/// The Python bindings for streams, used as context managers giving the file
+/// descriptor of the write end.
+#[cfg(all(unix, feature = "python"))]
+#[pymethods]
+impl Stream {
+    /// The file descriptor of the write end, to pass to `subprocess` as `stdout` or
+    /// `stderr`.
+    fn fileno(&self) -> PyResult<i32> {
+        use std::os::fd::AsRawFd as _;
+        match &*self.writer.lock().unwrap_or_else(PoisonError::into_inner) {
+            Some(writer) => Ok(writer.as_raw_fd()),
+            None => Err(pyo3::exceptions::PyValueError::new_err(
+                "I/O operation on closed stream",
+            )),
+        }
+    }
+
+    /// Give the file descriptor of the write end.
+    fn __enter__(&self) -> PyResult<i32> {
+        self.fileno()
+    }
+
+    /// Close the write end, and wait for everything written so far to be shown.
+    #[pyo3(name = "close")]
+    fn py_close(&mut self, py: Python<'_>) {
+        py.allow_threads(|| self.close());
+    }
+
+    /// Give the file descriptor of the write end, as an awaitable.
+    fn __aenter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let fd = self.fileno()?.into_pyobject(py)?;
+        crate::utils::resolved(&fd)
+    }
+
+    /// Close the stream once everything written to it was shown, without blocking the
+    /// running event loop.
+    fn __aexit__<'py>(
+        slf: &Bound<'py, Self>,
+        _exc_type: &Bound<'py, PyAny>,
+        _exc_value: &Bound<'py, PyAny>,
+        _traceback: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        crate::utils::run_in_executor(&slf.getattr("close")?)
+    }
+
+    /// Close the stream, once everything written to it was shown.
+    fn __exit__(
+        &mut self,
+        py: Python<'_>,
+        _exc_type: &Bound<'_, PyAny>,
+        _exc_value: &Bound<'_, PyAny>,
+        _traceback: &Bound<'_, PyAny>,
+    ) {
+        py.allow_threads(|| self.close());
+    }
+}
+
+/// Close a shared write end, if still open.
+fn close(writer: &SharedWriter) {
+    drop(writer.lock().unwrap_or_else(PoisonError::into_inner).take());
+}
+
+/// Show every line read from `reader`, until the write end is closed.
+///
+/// Lines are shown as ephemeral progress in brief mode, and kept on screen from
+/// verbose mode up. Lines ended by a lone carriage return, as redrawn by progress
+/// indicators, are always ephemeral. Invalid UTF-8 is replaced rather than treated as
+/// an error, but a failure to read is shown as a warning, as the rest of the output is
+/// lost.
+///
+/// `done` is dropped once everything read was sent to the printer.
+fn read(sink: &Sink, prefix: &str, keep_ansi: bool, reader: PipeReader, done: Sender<()>) {
+    let target = Target::Stderr;
+    let (model, keep_ansi) = match sink.verbosity {
+        Verbosity::Quiet | Verbosity::Brief => (MessageType::ProgEphemeral(target), false),
+        _ => (
+            MessageType::ProgPersistent(target),
+            keep_ansi && console::colors_enabled_stderr(),
+        ),
+    };
+    for line in crate::utils::lines_lossy(BufReader::new(reader)) {
+        match line {
+            Ok((line, redrawn)) => {
+                let model = if redrawn {
+                    MessageType::ProgEphemeral(target)
+                } else {
+                    model
+                };
+                let plain = format!("{prefix}:: {}", console::strip_ansi_codes(&line));
+                if keep_ansi {
+                    let styled = format!("{prefix}:: {line}");
+                    sink.emit_styled(model, sink.target_for(model), &plain, &styled);
+                } else {
+                    sink.emit(model, sink.target_for(model), &plain);
+                }
+            }
+            Err(e) => {
+                let warning = MessageType::Warning();
+                let text = format!("{prefix}Stopped reading subprocess output: {e}");
+                sink.emit(warning, sink.target_for(warning), &text);
+                break;
+            }
+        }
+    }
+    drop(done);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use super::*;
+    use crate::printer::Message;
+    use crate::test_utils::sink;
+
+    mod stream {
+        use super::*;
+
+        #[test]
+        fn lines_shown() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("test.log");
+            let (sink, shown) = sink(&path, Verbosity::Verbose);
+
+            let (mut stream, reader) = Stream::open(sink, "[part] ".to_string(), false).unwrap();
+            let mut writer = stream.writer().unwrap();
+            writeln!(writer, "\x1b[32mcompiling\x1b[0m\tfoo.c").unwrap();
+            write!(writer, "linking").unwrap();
+            drop(writer);
+            stream.close();
+
+            assert!(reader.join());
+            let shown: Vec<Message> = shown.try_iter().collect();
+            let texts: Vec<&str> = shown.iter().map(Message::text).collect();
+            assert_eq!(texts, ["[part] :: compiling\tfoo.c", "[part] :: linking"]);
+            assert!(matches!(shown[0].model(), MessageType::ProgPersistent(_)));
+        }
+
+        #[test]
+        fn redrawn_lines_ephemeral() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("test.log");
+            let (sink, shown) = sink(&path, Verbosity::Verbose);
+
+            let (mut stream, reader) = Stream::open(sink, String::new(), false).unwrap();
+            let mut writer = stream.writer().unwrap();
+            write!(writer, "10%\r100%\r\nfetched\n").unwrap();
+            drop(writer);
+            stream.close();
+
+            assert!(reader.join());
+            let shown: Vec<Message> = shown.try_iter().collect();
+            let texts: Vec<&str> = shown.iter().map(Message::text).collect();
+            assert_eq!(texts, [":: 10%", ":: 100%", ":: fetched"]);
+            assert!(matches!(shown[0].model(), MessageType::ProgEphemeral(_)));
+            assert!(matches!(shown[1].model(), MessageType::ProgPersistent(_)));
+        }
+    }
+}
@@ -1,11 +1,99 @@
 //! Utilities for testing
 #![cfg(test)]
 
-use std::fmt::Debug;
+use std::{
+    ffi::OsString,
+    fmt::Debug,
+    fs::File,
+    io::BufWriter,
+    path::Path,
+    sync::{Arc, Mutex},
+};
 
+use crossbeam_channel::Receiver;
 use pyo3::{PyErr, PyTypeInfo, Python};
 use regex::Regex;
 
+use crate::emitter::Emitter;
+use crate::log_bridge::Sink;
+use crate::printer::{Message, OutputFormat, Verbosity};
+use crate::secrets::Secrets;
+use crate::settings::Settings;
+use crate::theme::Theme;
+use crate::timestamp::Timestamps;
+
+/// Settings writing messages as JSON lines, so that tests can read them back.
+pub fn json() -> Settings {
+    Settings::default().with_format(OutputFormat::Json)
+}
+
+/// Start a quiet emitter writing JSON, logging to a file in `dir`.
+pub fn emitter(dir: &tempfile::TempDir) -> Emitter {
+    emitter_with(dir, Verbosity::Quiet, json())
+}
+
+/// Start an emitter with the given verbosity and settings, logging to a file in `dir`.
+pub fn emitter_with(dir: &tempfile::TempDir, verbosity: Verbosity, settings: Settings) -> Emitter {
+    emitter_at(&dir.path().join("test.log"), verbosity, settings)
+}
+
+/// Start an emitter with the given verbosity and settings, logging to `log_filepath`.
+pub fn emitter_at(log_filepath: &Path, verbosity: Verbosity, settings: Settings) -> Emitter {
+    Emitter::new(
+        log_filepath.to_string_lossy().into_owned(),
+        verbosity,
+        "",
+        "Hello".to_string(),
+        settings.with_theme(Theme::no_color()),
+    )
+    .unwrap()
+}
+
+/// A sink logging to `log_filepath` and showing messages at `verbosity`, along with
+/// the channel the messages it shows end up on.
+pub fn sink(log_filepath: &Path, verbosity: Verbosity) -> (Sink, Receiver<Message>) {
+    let (printer, shown) = crossbeam_channel::unbounded();
+    let sink = Sink {
+        log_handle: Arc::new(Mutex::new(BufWriter::new(
+            File::create(log_filepath).unwrap(),
+        ))),
+        printer,
+        budget: Arc::default(),
+        verbosity,
+        timestamps: Timestamps::default(),
+        secrets: Secrets::default(),
+        timestamped: false,
+        #[cfg(feature = "tracing")]
+        deterministic: false,
+        #[cfg(unix)]
+        journal: None,
+    };
+    (sink, shown)
+}
+
+/// The lines of an emitter's log, without the timestamps they start with.
+pub fn log_lines(emitter: &Emitter) -> Vec<String> {
+    std::fs::read_to_string(emitter.log_filepath())
+        .unwrap()
+        .lines()
+        .map(|line| line.split_once(" [").unwrap().1.to_string())
+        .collect()
+}
+
+/// Build an environment from pairs of names and values, to look variables up in
+/// instead of the real one.
+pub fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<OsString> {
+    let vars: Vec<(String, OsString)> = vars
+        .iter()
+        .map(|(name, value)| ((*name).to_string(), (*value).into()))
+        .collect();
+    move |name| {
+        vars.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.clone())
+    }
+}
+
 pub fn assert_error_type<T: PyTypeInfo>(err: &PyErr) {
     Python::with_gil(|py| assert!(err.is_instance_of::<T>(py)));
 }
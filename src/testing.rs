@@ -0,0 +1,128 @@
+//! Helpers for testing applications built on Craft CLI.
+
+use pyo3::pymodule;
+
+/// Helpers for testing applications built on Craft CLI, checking what they emit
+/// without capturing the terminal.
+#[pymodule(submodule)]
+#[pyo3(module = "craft_cli._rs.testing")]
+pub mod testing {
+    use pyo3::{
+        Bound, PyResult, Python,
+        exceptions::PyAssertionError,
+        pyfunction,
+        types::{PyAnyMethods, PyModule},
+    };
+
+    use crate::emitter::Emitter;
+    use crate::utils::fix_imports;
+
+    #[pymodule_export]
+    use crate::screen::Screen;
+
+    /// Keep every message the emitter emits from now on, hidden or not.
+    #[pyfunction]
+    fn record(emitter: &Emitter) {
+        emitter.record();
+    }
+
+    /// The type and text of every message recorded so far, in order.
+    #[pyfunction]
+    fn recorded(emitter: &Emitter) -> Vec<(&'static str, String)> {
+        emitter
+            .recorded()
+            .iter()
+            .map(|message| (message.model().name(), message.text().to_string()))
+            .collect()
+    }
+
+    /// Check that messages matching `expected` were recorded, in that order.
+    ///
+    /// Each expected message is a pair of a type, such as `"progress"` or `"warning"`,
+    /// and a regular expression matched against the start of the text, as with
+    /// `re.match`. Other messages may come before, after and in between them.
+    #[pyfunction]
+    // PyO3 can only extract a list into an owned vector
+    #[expect(clippy::needless_pass_by_value)]
+    fn assert_messages(
+        py: Python<'_>,
+        emitter: &Emitter,
+        expected: Vec<(String, String)>,
+    ) -> PyResult<()> {
+        let re = py.import("re")?;
+        let dotall = re.getattr("DOTALL")?;
+        let recorded = recorded(emitter);
+
+        let mut remaining = recorded.iter();
+        for (model, pattern) in &expected {
+            let mut found = false;
+            for (name, text) in remaining.by_ref() {
+                if name == model
+                    && re
+                        .call_method1("match", (pattern, text, &dotall))?
+                        .is_truthy()?
+                {
+                    found = true;
+                    break;
+                }
+            }
+            if !found {
+                let listing: Vec<String> = recorded
+                    .iter()
+                    .map(|(name, text)| format!("  {name}: {text:?}"))
+                    .collect();
+                return Err(PyAssertionError::new_err(format!(
+                    "Expected a {model} message matching {pattern:?} in order, but recorded:\n{}",
+                    listing.join("\n")
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Fix syspath for easier importing in Python.
+    #[pymodule_init]
+    fn init(m: &Bound<'_, PyModule>) -> PyResult<()> {
+        fix_imports(m, "craft_cli._rs.testing")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::{Py, Python, exceptions::PyAssertionError, types::PyAnyMethods, wrap_pymodule};
+
+    use super::testing;
+    use crate::printer::SpinnerHint;
+    use crate::test_utils::{assert_error_contents, assert_error_type, emitter};
+
+    mod assert_messages {
+        use super::*;
+
+        #[test]
+        fn in_order() {
+            let dir = tempfile::tempdir().unwrap();
+            let emitter = emitter(&dir);
+            emitter.record();
+            emitter
+                .progress("Pulling base", None, SpinnerHint::Auto)
+                .unwrap();
+            emitter.warning("Disk nearly full").unwrap();
+            emitter.message("Packed hello.snap").unwrap();
+
+            Python::with_gil(|py| {
+                let testing = wrap_pymodule!(testing)(py);
+                let emitter = Py::new(py, emitter).unwrap();
+                let check = |expected: Vec<(&str, &str)>| {
+                    testing
+                        .bind(py)
+                        .call_method1("assert_messages", (&emitter, expected))
+                };
+
+                check(vec![("progress", "Pulling.*"), ("info", r"Packed \w+")]).unwrap();
+                let err = check(vec![("info", "Packed"), ("progress", "Pulling")]).unwrap_err();
+                assert_error_type::<PyAssertionError>(&err);
+                assert_error_contents(&err, "progress message matching \"Pulling\"");
+            });
+        }
+    }
+}
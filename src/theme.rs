@@ -0,0 +1,481 @@
+//! Per-message-type styling of terminal output.
+
+use std::{collections::HashMap, sync::LazyLock, time::Duration};
+
+use console::{Color, Style};
+#[cfg(feature = "python")]
+use pyo3::{
+    Bound, PyResult,
+    exceptions::PyValueError,
+    pyclass, pymethods,
+    types::{PyAnyMethods, PyDict},
+};
+
+use crate::printer::{MessageType, Target};
+
+/// The names of every message type that can be styled.
+const STYLEABLE: [&str; 8] = [
+    "progress",
+    "warning",
+    "error",
+    "debug",
+    "trace",
+    "info",
+    "success",
+    "progress_bar",
+];
+
+/// Whether the terminal can be expected to render non-ASCII icons.
+///
+/// This follows the locale, and excludes the Linux virtual console whose fonts lack
+/// most symbols.
+static SUPPORTS_UNICODE: LazyLock<bool> = LazyLock::new(|| {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    (locale.contains("utf-8") || locale.contains("utf8"))
+        && std::env::var("TERM").is_ok_and(|term| term != "linux")
+});
+
+/// An icon to put before a message, with a fallback for limited terminals.
+#[derive(Clone, Debug)]
+pub struct Icon {
+    /// The icon itself.
+    pub unicode: String,
+
+    /// A plain ASCII stand-in for the icon.
+    pub ascii: String,
+}
+
+impl Icon {
+    /// Create an icon from its Unicode and ASCII variants.
+    fn new(unicode: &str, ascii: &str) -> Self {
+        Self {
+            unicode: unicode.to_string(),
+            ascii: ascii.to_string(),
+        }
+    }
+
+    /// Get the variant of the icon suitable for the current terminal.
+    fn for_terminal(&self) -> &str {
+        if *SUPPORTS_UNICODE {
+            &self.unicode
+        } else {
+            &self.ascii
+        }
+    }
+}
+
+/// How a single type of message should be styled.
+#[derive(Clone, Debug, Default)]
+pub struct MessageStyle {
+    /// The foreground color of the message, if any.
+    pub color: Option<Color>,
+
+    /// Whether the message should be bold.
+    pub bold: bool,
+
+    /// Text to put before the message.
+    pub prefix: String,
+
+    /// An icon to put before the message, if the theme shows icons.
+    pub icon: Option<Icon>,
+}
+
+impl MessageStyle {
+    #[cfg(feature = "python")]
+    /// Parse a color from its name or its ANSI 256-color index.
+    fn parse_color(name: &str) -> Option<Color> {
+        Some(match name.to_lowercase().as_str() {
+            "black" => Color::Black,
+            "red" => Color::Red,
+            "green" => Color::Green,
+            "yellow" => Color::Yellow,
+            "blue" => Color::Blue,
+            "magenta" => Color::Magenta,
+            "cyan" => Color::Cyan,
+            "white" => Color::White,
+            other => Color::Color256(other.parse().ok()?),
+        })
+    }
+
+    #[cfg(feature = "python")]
+    /// Parse a style from a Python dict with optional `color`, `bold`, `prefix` and
+    /// `icon` keys.
+    ///
+    /// An icon can either be a single string, or a tuple of a Unicode icon and its
+    /// ASCII fallback.
+    fn from_dict(dict: &Bound<'_, PyDict>) -> PyResult<Self> {
+        let mut style = Self::default();
+        for (key, value) in dict {
+            match key.extract::<String>()?.as_str() {
+                "color" => {
+                    let name: Option<String> = value.extract()?;
+                    style.color = name
+                        .map(|name| {
+                            Self::parse_color(&name).ok_or_else(|| {
+                                PyValueError::new_err(format!("Unknown color: {name:?}"))
+                            })
+                        })
+                        .transpose()?;
+                }
+                "bold" => style.bold = value.extract()?,
+                "prefix" => style.prefix = value.extract()?,
+                "icon" => {
+                    style.icon = if let Ok(icon) = value.extract::<String>() {
+                        Some(Icon::new(&icon, &icon))
+                    } else {
+                        let (unicode, ascii): (String, String) = value.extract()?;
+                        Some(Icon::new(&unicode, &ascii))
+                    };
+                }
+                other => {
+                    return Err(PyValueError::new_err(format!(
+                        "Unknown style attribute: {other:?}"
+                    )));
+                }
+            }
+        }
+        Ok(style)
+    }
+}
+
+/// A mapping of message types to the style they should be printed with.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "python", pyclass)]
+pub struct Theme {
+    /// Styles for each message type, keyed by the message type's name.
+    styles: HashMap<&'static str, MessageStyle>,
+
+    /// Whether to put each message type's icon before its messages.
+    icons: bool,
+
+    /// The suffix put after a message that took a while, where `{duration}` is
+    /// replaced with how long it took.
+    duration_template: String,
+
+    /// How long a message must take before its duration is shown.
+    duration_threshold: Duration,
+
+    /// A name put before every message, to tell apart tools sharing a console.
+    app_prefix: Option<String>,
+}
+
+impl Default for Theme {
+    /// The default theme: warnings, errors and successes stand out, everything else is
+    /// plain.
+    fn default() -> Self {
+        let mut theme = Self::no_color();
+
+        let warning = theme.styles.get_mut("warning").expect("Styleable");
+        warning.color = Some(Color::Yellow);
+
+        let error = theme.styles.get_mut("error").expect("Styleable");
+        error.color = Some(Color::Red);
+        error.bold = true;
+
+        let success = theme.styles.get_mut("success").expect("Styleable");
+        success.color = Some(Color::Green);
+
+        theme
+    }
+}
+
+impl Theme {
+    /// A theme without any colors or text decorations.
+    ///
+    /// Icons are still defined for when they are enabled with `with_icons`. Successes
+    /// show theirs regardless, as it's what sets them apart without colors.
+    #[must_use]
+    pub fn no_color() -> Self {
+        let icons = [
+            ("progress", Icon::new("•", "-")),
+            ("warning", Icon::new("⚠", "!")),
+            ("error", Icon::new("✖", "x")),
+            ("info", Icon::new("✔", "+")),
+            ("success", Icon::new("✔", "+")),
+        ];
+        let styles = STYLEABLE
+            .into_iter()
+            .map(|name| {
+                let icon = icons
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, icon)| icon.clone());
+                (
+                    name,
+                    MessageStyle {
+                        icon,
+                        ..MessageStyle::default()
+                    },
+                )
+            })
+            .collect();
+
+        Self {
+            styles,
+            icons: false,
+            duration_template: String::from(" (took {duration})"),
+            duration_threshold: Duration::from_secs(10),
+            app_prefix: None,
+        }
+    }
+
+    /// A copy of this theme that puts status icons before messages.
+    ///
+    /// The icons fall back to ASCII characters on terminals that can't render them.
+    #[must_use]
+    pub fn with_icons(&self) -> Self {
+        Self {
+            icons: true,
+            ..self.clone()
+        }
+    }
+
+    /// A copy of this theme that only shows how long a message took past `threshold`,
+    /// with a suffix where `{duration}` is replaced with the time taken.
+    #[must_use]
+    pub fn with_duration_suffix(&self, threshold: Duration, template: &str) -> Self {
+        Self {
+            duration_threshold: threshold,
+            duration_template: template.to_string(),
+            ..self.clone()
+        }
+    }
+
+    /// A copy of this theme that puts a dimmed name, usually the application's, before
+    /// every message.
+    #[must_use]
+    pub fn with_app_prefix(&self, name: &str) -> Self {
+        Self {
+            app_prefix: Some(name.to_string()),
+            ..self.clone()
+        }
+    }
+
+    /// The suffix to put after a message that took `elapsed`, if it took long enough
+    /// to be worth mentioning.
+    #[must_use]
+    pub fn duration_suffix(&self, elapsed: Duration) -> Option<String> {
+        if elapsed < self.duration_threshold {
+            return None;
+        }
+        let duration = format!("{:#}", indicatif::HumanDuration(elapsed));
+        Some(self.duration_template.replace("{duration}", &duration))
+    }
+
+    /// Get the style for a type of message.
+    #[must_use]
+    pub fn style(&self, model: MessageType) -> &MessageStyle {
+        // Every styleable message type is always present
+        &self.styles[model.name()]
+    }
+
+    /// Style the text of a message for the stream it's printed to.
+    #[must_use]
+    pub fn apply(&self, model: MessageType, target: Target, text: &str) -> String {
+        let message_style = self.style(model);
+        let mut base = Style::new();
+        if let Target::Stderr = target {
+            base = base.for_stderr();
+        }
+        let mut style = base.clone();
+        if let Some(color) = message_style.color {
+            style = style.fg(color);
+        }
+        if message_style.bold {
+            style = style.bold();
+        }
+
+        let icon = match &message_style.icon {
+            Some(icon) if self.icons || matches!(model, MessageType::Success()) => {
+                format!("{} ", icon.for_terminal())
+            }
+            _ => String::new(),
+        };
+
+        let app = match &self.app_prefix {
+            Some(name) => format!("{} ", base.dim().apply_to(name)),
+            None => String::new(),
+        };
+
+        format!(
+            "{app}{}{icon}{}",
+            message_style.prefix,
+            style.apply_to(text)
+        )
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Theme {
+    /// The default theme, where warnings and errors stand out.
+    #[staticmethod]
+    #[pyo3(name = "default")]
+    fn py_default() -> Self {
+        Self::default()
+    }
+
+    /// A theme without any colors or text decorations.
+    #[staticmethod]
+    #[pyo3(name = "no_color")]
+    fn py_no_color() -> Self {
+        Self::no_color()
+    }
+
+    /// A copy of this theme that puts status icons before messages.
+    #[pyo3(name = "with_icons")]
+    fn py_with_icons(&self) -> Self {
+        self.with_icons()
+    }
+
+    /// A copy of this theme that only shows how long a message took past `threshold`
+    /// seconds, with a suffix where `{duration}` is replaced with the time taken.
+    #[pyo3(name = "with_duration_suffix", signature = (threshold, template = " (took {duration})"))]
+    fn py_with_duration_suffix(&self, threshold: f64, template: &str) -> PyResult<Self> {
+        let threshold = Duration::try_from_secs_f64(threshold)
+            .map_err(|e| PyValueError::new_err(format!("Invalid threshold: {e}")))?;
+        Ok(self.with_duration_suffix(threshold, template))
+    }
+
+    /// A copy of this theme that puts a dimmed name, usually the application's, before
+    /// every message.
+    #[pyo3(name = "with_app_prefix")]
+    fn py_with_app_prefix(&self, name: &str) -> Self {
+        self.with_app_prefix(name)
+    }
+
+    /// Build a theme from a dict of message type names to styles.
+    ///
+    /// Each style is a dict with optional `color`, `bold` and `prefix` keys. Message
+    /// types that aren't mentioned keep their style from the default theme.
+    #[staticmethod]
+    fn from_dict(styles: &Bound<'_, PyDict>) -> PyResult<Self> {
+        let mut theme = Self::default();
+        for (key, value) in styles {
+            let name: String = key.extract()?;
+            let Some(name) = STYLEABLE.into_iter().find(|n| *n == name) else {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown message type: {name:?}"
+                )));
+            };
+            theme
+                .styles
+                .insert(name, MessageStyle::from_dict(value.downcast()?)?);
+        }
+        Ok(theme)
+    }
+}
+
+#[cfg(all(test, feature = "python"))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{assert_error_contents, assert_error_type};
+    use pyo3::{Python, ffi::c_str};
+
+    mod duration_suffix {
+        use super::*;
+
+        #[test]
+        fn threshold() {
+            let theme =
+                Theme::no_color().with_duration_suffix(Duration::from_secs(5), " [{duration}]");
+
+            assert_eq!(theme.duration_suffix(Duration::from_secs(4)), None);
+            assert_eq!(
+                theme.duration_suffix(Duration::from_secs(7)).as_deref(),
+                Some(" [7s]")
+            );
+        }
+    }
+
+    mod app_prefix {
+        use super::*;
+
+        #[test]
+        fn every_message() {
+            let theme = Theme::no_color().with_app_prefix("snapcraft");
+
+            for model in [MessageType::Info(), MessageType::Error()] {
+                let text = theme.apply(model, Target::Stderr, "Hello");
+                assert_eq!(console::strip_ansi_codes(&text), "snapcraft Hello");
+            }
+        }
+    }
+
+    mod success {
+        use super::*;
+
+        #[test]
+        fn always_marked() {
+            let theme = Theme::no_color();
+
+            let text = theme.apply(MessageType::Success(), Target::Stdout, "Packed");
+            assert!(text == "✔ Packed" || text == "+ Packed");
+            assert_eq!(
+                Theme::default().style(MessageType::Success()).color,
+                Some(Color::Green)
+            );
+        }
+    }
+
+    mod from_dict {
+        use super::*;
+
+        #[test]
+        fn basic() {
+            Python::with_gil(|py| {
+                let styles = py
+                    .eval(
+                        c_str!("{'info': {'prefix': '> ', 'color': None}}"),
+                        None,
+                        None,
+                    )
+                    .unwrap();
+                let theme = Theme::from_dict(styles.downcast().unwrap()).unwrap();
+
+                let text = theme.apply(MessageType::Info(), Target::Stdout, "Hello");
+                assert_eq!(text, "> Hello");
+                assert_eq!(
+                    theme.style(MessageType::Warning()).color,
+                    Some(Color::Yellow)
+                );
+            });
+        }
+
+        #[test]
+        fn icons() {
+            Python::with_gil(|py| {
+                let styles = py
+                    .eval(c_str!("{'info': {'icon': ('✔', '+')}}"), None, None)
+                    .unwrap();
+                let theme = Theme::from_dict(styles.downcast().unwrap()).unwrap();
+
+                let plain = theme.apply(MessageType::Info(), Target::Stdout, "Done");
+                assert_eq!(plain, "Done");
+
+                let with_icons = theme.with_icons();
+                let text = with_icons.apply(MessageType::Info(), Target::Stdout, "Done");
+                assert!(text == "✔ Done" || text == "+ Done");
+            });
+        }
+
+        #[test]
+        fn bad_color() {
+            Python::with_gil(|py| {
+                let styles = py
+                    .eval(c_str!("{'info': {'color': 'chartreuse'}}"), None, None)
+                    .unwrap();
+                let err = Theme::from_dict(styles.downcast().unwrap()).unwrap_err();
+
+                assert_error_type::<PyValueError>(&err);
+                assert_error_contents(&err, "Unknown color");
+            });
+        }
+    }
+}
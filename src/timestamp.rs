@@ -0,0 +1,136 @@
+//! Timestamps shown on messages and written to the log.
+
+use jiff::{Timestamp, Zoned, fmt::strtime, tz::TimeZone};
+#[cfg(feature = "python")]
+use pyo3::pyclass;
+
+use crate::error::{Error, Result};
+
+/// The format of local timestamps, unless another is given.
+const LOCAL_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+/// The format of UTC timestamps, unless another is given. The offset is shown to
+/// avoid them being mistaken for local ones.
+const UTC_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f%:z";
+
+/// The clock timestamps are read from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+pub enum Clock {
+    /// The local time, which users find easiest to read.
+    #[default]
+    Local,
+
+    /// UTC, which lines up logs from build machines in different time zones.
+    Utc,
+}
+
+impl Clock {
+    /// The current time on this clock.
+    fn now(self) -> Zoned {
+        match self {
+            Clock::Local => Zoned::now(),
+            Clock::Utc => Timestamp::now().to_zoned(TimeZone::UTC),
+        }
+    }
+}
+
+/// How timestamps are read and formatted.
+#[derive(Clone, Debug, Default)]
+pub struct Timestamps {
+    /// The clock timestamps are read from.
+    clock: Clock,
+
+    /// A strftime format for timestamps, if not the clock's default.
+    format: Option<String>,
+
+    /// The timestamp shown instead of the time, if fixed.
+    fixed: Option<String>,
+}
+
+impl Timestamps {
+    /// Read timestamps from the given clock.
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
+    /// Format timestamps with the given strftime format.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the format is invalid.
+    pub fn set_format(&mut self, format: String) -> Result<()> {
+        strtime::format(&format, &self.clock.now())
+            .map_err(|e| Error::InvalidArgument(format!("Invalid timestamp format: {e}")))?;
+        self.format = Some(format);
+        Ok(())
+    }
+
+    /// Show `timestamp` instead of the time from now on, as it was already formatted.
+    pub fn set_fixed(&mut self, timestamp: String) {
+        self.fixed = Some(timestamp);
+    }
+
+    /// The current time, formatted for a message or log line.
+    pub fn now(&self) -> String {
+        if let Some(fixed) = &self.fixed {
+            return fixed.clone();
+        }
+        let default = match self.clock {
+            Clock::Local => LOCAL_FORMAT,
+            Clock::Utc => UTC_FORMAT,
+        };
+        let format = self.format.as_deref().unwrap_or(default);
+        // The format was checked when it was set, and only depends on fields every
+        // zoned time has
+        strtime::format(format, &self.clock.now()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod timestamps {
+        use super::*;
+
+        #[test]
+        fn default_format() {
+            let re = regex::Regex::new(r"^\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}\.\d{3}$").unwrap();
+            let now = Timestamps::default().now();
+
+            assert!(re.is_match(&now), "{now:?}");
+        }
+
+        #[test]
+        fn utc_offset() {
+            let mut timestamps = Timestamps::default();
+            timestamps.set_clock(Clock::Utc);
+
+            assert!(timestamps.now().ends_with("+00:00"));
+        }
+
+        #[test]
+        fn custom_format() {
+            let mut timestamps = Timestamps::default();
+            timestamps.set_format("%H:%M:%S%.6f".to_string()).unwrap();
+
+            assert_eq!(timestamps.now().len(), "00:00:00.000000".len());
+        }
+
+        #[test]
+        fn fixed() {
+            let mut timestamps = Timestamps::default();
+            timestamps.set_fixed("<now>".to_string());
+
+            assert_eq!(timestamps.now(), "<now>");
+        }
+
+        #[test]
+        fn invalid_format() {
+            let mut timestamps = Timestamps::default();
+
+            assert!(timestamps.set_format("%!".to_string()).is_err());
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! A `tracing` layer showing events and spans through the emitter.
+//!
+//! Rust components instrumented with `tracing` can then run alongside the emitter and
+//! have their output look like the rest: events become messages and spans become
+//! timed sections.
+
+use std::{fmt, time::Instant};
+
+use tracing_core::{
+    Event, Level, Subscriber,
+    field::{Field, Visit},
+    span,
+};
+use tracing_subscriber::{Layer, layer::Context, registry::LookupSpan};
+
+use crate::log_bridge::with_sink;
+use crate::printer::{MessageType, Target};
+use crate::settings::ELAPSED_PLACEHOLDER;
+
+/// A `tracing` layer forwarding to the running emitter, if any.
+///
+/// Events are shown by level: errors as errors, warnings as warnings, info events as
+/// verbose messages, and debug and trace events as debug and trace messages. Spans at
+/// the info level or above are shown as sections, with how long they took once they
+/// close; finer spans are too numerous to be worth a line each.
+///
+/// ```ignore
+/// use tracing_subscriber::prelude::*;
+///
+/// tracing_subscriber::registry().with(craft_cli::EmitterLayer).init();
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EmitterLayer;
+
+/// When a section span was opened, and what it's shown as.
+struct Section {
+    /// The title of the section.
+    title: String,
+
+    /// When the span was created.
+    begun: Instant,
+}
+
+impl<S> Layer<S> for EmitterLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if *attrs.metadata().level() > Level::INFO {
+            return;
+        }
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut fields = Fields::default();
+        attrs.record(&mut fields);
+        let title = fields.text(attrs.metadata().name());
+        let depth = span.scope().skip(1).count();
+
+        with_sink(|sink| {
+            sink.write_log("SECTION", &format!("begin {title:?}"));
+            let model = MessageType::ProgPersistent(Target::Stderr);
+            sink.emit(model, sink.target_for(model), &indent(depth, &title));
+        });
+        span.extensions_mut().insert(Section {
+            title,
+            begun: Instant::now(),
+        });
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let extensions = span.extensions();
+        let Some(section) = extensions.get::<Section>() else {
+            return;
+        };
+        let elapsed = section.begun.elapsed().as_secs_f64();
+        let depth = span.scope().skip(1).count();
+
+        with_sink(|sink| {
+            let (shown, logged) = if sink.deterministic {
+                (
+                    ELAPSED_PLACEHOLDER.to_string(),
+                    ELAPSED_PLACEHOLDER.to_string(),
+                )
+            } else {
+                (format!("{elapsed:.1}s"), format!("{elapsed:.3}s"))
+            };
+            let model = MessageType::ProgPersistent(Target::Stderr);
+            let text = format!("{} completed in {shown}", section.title);
+            sink.emit(model, sink.target_for(model), &indent(depth, &text));
+            sink.write_log(
+                "SECTION",
+                &format!("end {:?} after {logged}", section.title),
+            );
+        });
+    }
+
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let model = match *metadata.level() {
+            Level::ERROR => MessageType::Error(),
+            Level::WARN => MessageType::Warning(),
+            Level::INFO => MessageType::Info(),
+            Level::DEBUG => MessageType::Debug(),
+            Level::TRACE => MessageType::Trace(),
+        };
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let text = fields.text(metadata.target());
+
+        with_sink(|sink| {
+            let target = sink.target_for(model);
+            // Verbose messages are tagged as debug, like the emitter's own
+            let model = match model {
+                MessageType::Info() => MessageType::Debug(),
+                model => model,
+            };
+            sink.emit(model, target, &text);
+        });
+    }
+}
+
+/// Indent text under the sections it's nested in.
+fn indent(depth: usize, text: &str) -> String {
+    format!("{}{text}", "  ".repeat(depth))
+}
+
+/// The fields recorded on an event or span.
+#[derive(Default)]
+struct Fields {
+    /// The formatted message, if any.
+    message: Option<String>,
+
+    /// Every other field, as `name=value`.
+    others: Vec<String>,
+}
+
+impl Fields {
+    /// Render the fields as a line of text, led by the message or `fallback` if there
+    /// isn't one.
+    fn text(&self, fallback: &str) -> String {
+        let mut text = self.message.as_deref().unwrap_or(fallback).to_string();
+        for field in &self.others {
+            text.push(' ');
+            text.push_str(field);
+        }
+        text
+    }
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = Some(value.to_string());
+        } else {
+            self.others.push(format!("{}={value:?}", field.name()));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = Some(format!("{value:?}"));
+        } else {
+            self.others.push(format!("{}={value:?}", field.name()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write as _, sync::Arc};
+
+    use tracing_subscriber::layer::SubscriberExt as _;
+
+    use super::*;
+    use crate::log_bridge::attach_local;
+    use crate::printer::{Message, Verbosity};
+    use crate::test_utils::sink;
+
+    mod emitter_layer {
+        use super::*;
+
+        #[test]
+        fn events_and_spans() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("test.log");
+            let (mut sink, shown) = sink(&path, Verbosity::Verbose);
+            sink.deterministic = true;
+            let log_handle = Arc::clone(&sink.log_handle);
+            attach_local(Some(sink));
+
+            let subscriber = tracing_subscriber::registry().with(EmitterLayer);
+            tracing::subscriber::with_default(subscriber, || {
+                let _section = tracing::info_span!("Build").entered();
+                tracing::info!(part = "foo", "built");
+                tracing::error!("broken");
+                tracing::debug!("hidden");
+            });
+            attach_local(None);
+            log_handle.lock().unwrap().flush().unwrap();
+
+            let shown: Vec<Message> = shown.try_iter().collect();
+            let texts: Vec<&str> = shown.iter().map(Message::text).collect();
+            assert_eq!(
+                texts,
+                [
+                    "Build",
+                    "built part=\"foo\"",
+                    "broken",
+                    "Build completed in <elapsed>"
+                ]
+            );
+            assert!(matches!(shown[2].model(), MessageType::Error()));
+            let log = std::fs::read_to_string(&path).unwrap();
+            assert!(log.contains("[SECTION] begin \"Build\"\n"), "{log}");
+            assert!(log.contains("[DEBUG] hidden\n"), "{log}");
+            assert!(
+                log.contains("[SECTION] end \"Build\" after <elapsed>\n"),
+                "{log}"
+            );
+        }
+    }
+}
@@ -1,45 +1,312 @@
 //! Internal utils for Craft CLI.
 
+use std::{
+    fmt::Write,
+    io::{self, BufRead},
+    path::Path,
+};
+
+#[cfg(feature = "python")]
 use pyo3::{
-    Bound, PyResult, Python,
+    Bound, PyAny, PyResult, Python,
     types::{PyAnyMethods, PyModule},
 };
 
+#[cfg(feature = "python")]
 /// Hack: workaround for [an upstream issue in PyO3](https://github.com/PyO3/pyo3/issues/759)
 pub fn fix_imports(m: &Bound<'_, PyModule>, name: &str) -> PyResult<()> {
     Python::with_gil(|py| py.import("sys")?.getattr("modules")?.set_item(name, m))
 }
 
-// This log function is very convenient for development, but may not necessarily always exist
-// in live code.
-#[allow(unused, clippy::allow_attributes)]
-/// Log a message for debugging purposes only.
-pub fn log(message: impl Into<String>) {
-    #[cfg(debug_assertions)]
-    {
-        use std::{
-            fs,
-            io::Write as _,
-            sync::{LazyLock, Mutex},
+#[cfg(feature = "python")]
+/// Call `callable` on the default executor of the running asyncio event loop, giving a
+/// future to await for its result without blocking the loop.
+pub fn run_in_executor<'py>(callable: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+    let py = callable.py();
+    let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+    event_loop.call_method1("run_in_executor", (py.None(), callable))
+}
+
+#[cfg(feature = "python")]
+/// A future of the running asyncio event loop, already resolved to `value`.
+pub fn resolved<'py>(value: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+    let event_loop = value
+        .py()
+        .import("asyncio")?
+        .call_method0("get_running_loop")?;
+    let future = event_loop.call_method0("create_future")?;
+    future.call_method1("set_result", (value,))?;
+    Ok(future)
+}
+
+/// Read the lines of a stream, replacing invalid UTF-8 with replacement characters,
+/// with whether each ended in a lone carriage return.
+///
+/// Build tools routinely mix encodings in their output, which must never stop it from
+/// being shown. Lines end in `\n`, `\r\n` or a lone `\r`, which tools like downloaders
+/// use to redraw a line over and over. Line endings are not included in the lines.
+///
+/// A line is yielded as soon as its `\r` is read, so a `\n` arriving separately later
+/// is taken as ending an empty line that's skipped.
+pub fn lines_lossy(mut reader: impl BufRead) -> impl Iterator<Item = io::Result<(String, bool)>> {
+    let mut line = Vec::new();
+    let mut after_return = false;
+    std::iter::from_fn(move || {
+        loop {
+            let available = match reader.fill_buf() {
+                Ok(available) => available,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(e)),
+            };
+            if available.is_empty() {
+                if line.is_empty() {
+                    return None;
+                }
+                let text = String::from_utf8_lossy(&line).into_owned();
+                line.clear();
+                return Some(Ok((text, false)));
+            }
+            if std::mem::take(&mut after_return) && available[0] == b'\n' {
+                reader.consume(1);
+                continue;
+            }
+            let Some(end) = available.iter().position(|&b| b == b'\n' || b == b'\r') else {
+                line.extend_from_slice(available);
+                let read = available.len();
+                reader.consume(read);
+                continue;
+            };
+            line.extend_from_slice(&available[..end]);
+            let crlf = available[end..].starts_with(b"\r\n");
+            let lone_return = available[end] == b'\r' && !crlf;
+            after_return = lone_return && end + 1 == available.len();
+            reader.consume(end + 1 + usize::from(crlf));
+            let text = String::from_utf8_lossy(&line).into_owned();
+            line.clear();
+            return Some(Ok((text, lone_return)));
+        }
+    })
+}
+
+/// The candidates similar enough to `word` to suggest in its place, best first.
+///
+/// Similarity is the edit distance between the two, scaled by the length of the longer
+/// one, so that it runs from 0 for nothing in common to 1 for identical strings.
+/// Candidates scoring below `cutoff` are left out.
+pub fn find_similar<'a>(
+    word: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+    cutoff: f64,
+) -> Vec<&'a str> {
+    let mut scored: Vec<(f64, &str)> = candidates
+        .into_iter()
+        .map(|candidate| (similarity(word, candidate), candidate))
+        .filter(|(score, _)| *score >= cutoff)
+        .collect();
+    // A stable sort keeps equally similar candidates in the order given
+    scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    scored.into_iter().map(|(_, candidate)| candidate).collect()
+}
+
+/// How similar two strings are, from 0 for nothing in common to 1 for identical.
+#[expect(clippy::cast_precision_loss)] // Words are nowhere near long enough to lose any
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    // The Levenshtein distance, keeping a single row of the table at a time
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    1.0 - row[b.len()] as f64 / longest as f64
+}
+
+/// Explain an error from the operating system in one line, with what the user can do
+/// about it.
+///
+/// Common errors get a plain explanation and a suggested fix. Any other error falls
+/// back to its `detail`, usually the system's own description.
+#[must_use]
+pub fn humanize_io_error(kind: io::ErrorKind, detail: &str, path: Option<&str>) -> String {
+    use io::ErrorKind::*;
+    let (meaning, fix) = match kind {
+        NotFound => (
+            "it doesn't exist",
+            "Check that the path is spelled correctly.",
+        ),
+        PermissionDenied => (
+            "permission was denied",
+            "Check its permissions, or run as a user who can access it.",
+        ),
+        AlreadyExists => ("it already exists", "Remove it or choose another path."),
+        IsADirectory => ("it is a directory", "Give the path of a file instead."),
+        NotADirectory => (
+            "part of the path is not a directory",
+            "Check that every part of the path but the last is a directory.",
+        ),
+        DirectoryNotEmpty => ("the directory is not empty", "Empty the directory first."),
+        StorageFull => ("the disk is full", "Free up some space and try again."),
+        ReadOnlyFilesystem => (
+            "the filesystem is read-only",
+            "Use a location on a writable filesystem.",
+        ),
+        _ => {
+            return match path {
+                Some(path) => format!("Cannot use '{path}': {detail}."),
+                None => format!("{detail}."),
+            };
+        }
+    };
+    match path {
+        Some(path) => format!("Cannot use '{path}': {meaning}. {fix}"),
+        None => format!("An operation failed because {meaning}. {fix}"),
+    }
+}
+
+/// Build the `file://` URL for an absolute path, like Python's `Path.as_uri()`.
+///
+/// Every byte but the unreserved characters and the separators is percent-encoded.
+#[must_use]
+pub fn file_url(path: &Path) -> String {
+    #[cfg(unix)]
+    let (bytes, safe) = (
+        std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()).to_vec(),
+        "/",
+    );
+    #[cfg(not(unix))]
+    let (bytes, safe) = {
+        let path = path.to_string_lossy().replace('\\', "/");
+        let path = if path.starts_with('/') {
+            path
+        } else {
+            format!("/{path}")
         };
+        (path.into_bytes(), "/:")
+    };
+
+    let mut url = String::from("file://");
+    for byte in bytes {
+        if byte.is_ascii_alphanumeric()
+            || b"-._~".contains(&byte)
+            || safe.as_bytes().contains(&byte)
+        {
+            url.push(char::from(byte));
+        } else {
+            // Writing to a String can't fail
+            let _ = write!(url, "%{byte:02X}");
+        }
+    }
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod file_url {
+        use super::*;
+
+        #[test]
+        fn plain() {
+            assert_eq!(file_url(Path::new("/tmp/app.log")), "file:///tmp/app.log");
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn escaped() {
+            assert_eq!(
+                file_url(Path::new("/tmp/my logs/50%#1/café?.log")),
+                "file:///tmp/my%20logs/50%25%231/caf%C3%A9%3F.log"
+            );
+        }
+    }
+
+    mod lines_lossy {
+        use super::*;
+
+        #[test]
+        fn invalid_utf8() {
+            let input: &[u8] = b"caf\xe9\r\nok\n\xff";
+            let lines: Vec<String> = lines_lossy(input).map(|line| line.unwrap().0).collect();
+
+            assert_eq!(lines, ["caf\u{fffd}", "ok", "\u{fffd}"]);
+        }
+
+        #[test]
+        fn carriage_returns() {
+            let input: &[u8] = b"10%\r50%\rdone\r\nnext\n";
+            let lines: Vec<(String, bool)> = lines_lossy(input).map(Result::unwrap).collect();
+
+            assert_eq!(
+                lines,
+                [
+                    ("10%".to_string(), true),
+                    ("50%".to_string(), true),
+                    ("done".to_string(), false),
+                    ("next".to_string(), false),
+                ]
+            );
+        }
+
+        #[test]
+        fn carriage_return_then_newline_apart() {
+            let input = io::BufReader::with_capacity(4, b"50%\r\nnext\n".as_slice());
+            let lines: Vec<(String, bool)> = lines_lossy(input).map(Result::unwrap).collect();
+
+            assert_eq!(
+                lines,
+                [("50%".to_string(), true), ("next".to_string(), false)]
+            );
+        }
+    }
+
+    mod humanize_io_error {
+        use super::*;
+
+        #[test]
+        fn known_error() {
+            let text = humanize_io_error(io::ErrorKind::NotFound, "", Some("snap.yaml"));
+
+            assert_eq!(
+                text,
+                "Cannot use 'snap.yaml': it doesn't exist. Check that the path is spelled correctly."
+            );
+        }
+
+        #[test]
+        fn unknown_error() {
+            let text = humanize_io_error(io::ErrorKind::Other, "Device is haunted", None);
+
+            assert_eq!(text, "Device is haunted.");
+        }
+    }
+
+    mod find_similar {
+        use super::*;
+
+        #[test]
+        fn best_first() {
+            let candidates = ["pack", "pull", "push", "prime"];
+
+            assert_eq!(find_similar("psh", candidates, 0.6), ["push"]);
+            assert_eq!(find_similar("pul", candidates, 0.5), ["pull", "push"]);
+        }
 
-        static FILE: LazyLock<Mutex<fs::File>> = LazyLock::new(|| {
-            let mut handle = fs::OpenOptions::new()
-                .create(true)
-                .write(true)
-                .truncate(true)
-                .open("craft-cli-debug.log")
-                .expect("Couldn't open debugging log!");
-
-            handle
-                .write_all("I hope you find what you are looking for, traveller.\n".as_ref())
-                .expect("Couldn't write to debugging log!");
-
-            Mutex::new(handle)
-        });
-        FILE.lock()
-            .unwrap()
-            .write_all(format!("{}\n", message.into()).as_ref())
-            .expect("Couldn't write to debugging log!");
+        #[test]
+        fn nothing_close() {
+            assert!(find_similar("zzz", ["pack", "pull"], 0.6).is_empty());
+        }
     }
 }
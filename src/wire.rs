@@ -0,0 +1,204 @@
+//! The wire format for messages shipped between processes.
+//!
+//! Messages are serialized as flat objects, such as:
+//!
+//! ```json
+//! {"version": 2, "type": "progress", "permanent": true, "stream": "stderr",
+//!  "text": "Building", "target": "stderr", "fields": {"part": "foo"}}
+//! ```
+//!
+//! Fields are only ever added within a schema version, so readers should ignore fields
+//! they don't know about.
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::printer::{Fields, Message, MessageType, SpinnerHint, Target};
+
+/// The version of the wire schema written by this version of Craft CLI.
+///
+/// Readers reject messages from any later version, as their meaning can't be known.
+/// Adding a message type needs a new version, as readers also reject types they don't
+/// know. Version 2 added the `success` type.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// The fields describing a message's type on the wire.
+#[derive(Serialize, Deserialize)]
+pub struct WireType {
+    /// The name of the message type, as given by [`MessageType::name`].
+    #[serde(rename = "type")]
+    kind: String,
+
+    /// Whether a progress message stays on screen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    permanent: Option<bool>,
+
+    /// The total of a progress bar.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    total: Option<u64>,
+
+    /// The stream a progress message or bar is drawn on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stream: Option<Target>,
+}
+
+impl From<MessageType> for WireType {
+    fn from(model: MessageType) -> Self {
+        let (permanent, total, stream) = match model {
+            MessageType::ProgPersistent(target) => (Some(true), None, Some(target)),
+            MessageType::ProgEphemeral(target) => (Some(false), None, Some(target)),
+            MessageType::ProgBar(target, total) => (None, Some(total), Some(target)),
+            _ => (None, None, None),
+        };
+        Self {
+            kind: model.name().to_string(),
+            permanent,
+            total,
+            stream,
+        }
+    }
+}
+
+impl TryFrom<WireType> for MessageType {
+    type Error = String;
+
+    fn try_from(wire: WireType) -> Result<Self, String> {
+        let stream = wire.stream.unwrap_or(Target::Stderr);
+        Ok(match wire.kind.as_str() {
+            "progress" if wire.permanent.unwrap_or(false) => MessageType::ProgPersistent(stream),
+            "progress" => MessageType::ProgEphemeral(stream),
+            "progress_bar" => MessageType::ProgBar(stream, wire.total.unwrap_or_default()),
+            "warning" => MessageType::Warning(),
+            "error" => MessageType::Error(),
+            "debug" => MessageType::Debug(),
+            "trace" => MessageType::Trace(),
+            "info" => MessageType::Info(),
+            "success" => MessageType::Success(),
+            other => return Err(format!("unknown message type: {other:?}")),
+        })
+    }
+}
+
+/// A message as it appears on the wire.
+#[derive(Serialize, Deserialize)]
+pub struct WireMessage {
+    /// The schema version the message was written with.
+    version: u32,
+
+    /// The type of the message.
+    #[serde(flatten)]
+    model: MessageType,
+
+    /// The text of the message.
+    text: Arc<str>,
+
+    /// Where the message should be sent.
+    target: Target,
+
+    /// When to show a spinner while waiting for the next message.
+    #[serde(default, skip_serializing_if = "is_auto")]
+    spinner: SpinnerHint,
+
+    /// Structured fields attached to the message, as an object of strings.
+    #[serde(default, skip_serializing_if = "Fields::is_empty")]
+    fields: Fields,
+}
+
+/// Check whether a spinner hint is the default, to leave it out of the wire.
+#[expect(clippy::trivially_copy_pass_by_ref)] // Required by serde
+fn is_auto(hint: &SpinnerHint) -> bool {
+    *hint == SpinnerHint::Auto
+}
+
+impl From<Message> for WireMessage {
+    fn from(msg: Message) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            model: msg.model,
+            text: msg.text,
+            target: msg.target,
+            spinner: msg.spinner,
+            fields: msg.fields,
+        }
+    }
+}
+
+impl TryFrom<WireMessage> for Message {
+    type Error = String;
+
+    fn try_from(wire: WireMessage) -> Result<Self, String> {
+        if wire.version > SCHEMA_VERSION {
+            return Err(format!(
+                "unsupported schema version {} (at most {SCHEMA_VERSION} is supported)",
+                wire.version
+            ));
+        }
+        Ok(Self {
+            text: wire.text,
+            model: wire.model,
+            target: wire.target,
+            spinner: wire.spinner,
+            fields: wire.fields,
+            urgent: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod message {
+        use super::*;
+
+        #[test]
+        fn round_trip() {
+            let msg = Message {
+                text: "Building".into(),
+                model: MessageType::ProgPersistent(Target::Stderr),
+                target: Target::Stderr,
+                spinner: SpinnerHint::Auto,
+                fields: Fields::new(),
+                urgent: false,
+            };
+
+            let json = serde_json::to_string(&msg).unwrap();
+            assert_eq!(
+                json,
+                r#"{"version":2,"type":"progress","permanent":true,"stream":"stderr","text":"Building","target":"stderr"}"#
+            );
+
+            let back: Message = serde_json::from_str(&json).unwrap();
+            assert_eq!(&*back.text, "Building");
+            assert!(matches!(
+                back.model,
+                MessageType::ProgPersistent(Target::Stderr)
+            ));
+        }
+
+        #[test]
+        fn unknown_fields_ignored() {
+            let json = r#"{"version":1,"type":"warning","text":"Careful","target":"stderr","colour":"red"}"#;
+            let msg: Message = serde_json::from_str(json).unwrap();
+
+            assert!(matches!(msg.model, MessageType::Warning()));
+        }
+
+        #[test]
+        fn future_version() {
+            let json = r#"{"version":3,"type":"info","text":"Hi","target":"stdout"}"#;
+            let err = serde_json::from_str::<Message>(json).unwrap_err();
+
+            assert!(err.to_string().contains("unsupported schema version 3"));
+        }
+
+        #[test]
+        fn unknown_type() {
+            let json = r#"{"version":1,"type":"shout","text":"Hi","target":"stdout"}"#;
+            let err = serde_json::from_str::<Message>(json).unwrap_err();
+
+            assert!(err.to_string().contains("unknown message type"));
+        }
+    }
+}
@@ -16,7 +16,7 @@
 from pathlib import Path
 
 import craft_cli
-from craft_cli.completion import complete
+from craft_cli.completion import Shell, complete
 from craft_cli.completion.completion import DispatcherAndConfig
 from typing import Any, Callable, Type
 
@@ -68,6 +68,28 @@ def test_completion_output() -> None:
 
     assert actual_output == expected_output
 
+def test_completion_output_fish() -> None:
+    app_info_func = get_app_info_func([FakeLsCommand, FakeCpCommand])
+    actual_output = complete("testcraft", app_info_func, Shell.fish)
+
+    assert "set -l commands ls cp\n" in actual_output
+    assert (
+        "complete -c testcraft -n \"__fish_seen_subcommand_from ls\" -l color -r -f "
+        "-a 'always auto never' -d 'When to output in color'\n"
+    ) in actual_output
+    assert "complete -c testcraft -n \"__fish_seen_subcommand_from cp\" -F\n" in actual_output
+
+
+def test_completion_output_zsh() -> None:
+    app_info_func = get_app_info_func([FakeLsCommand, FakeCpCommand])
+    actual_output = complete("testcraft", app_info_func, Shell.zsh)
+
+    bash_output = (Path(__file__).parent / "test_completion" / "expected_script.sh").read_text()
+
+    assert actual_output.startswith("#compdef testcraft\n")
+    assert "autoload -U +X bashcompinit && bashcompinit\n" in actual_output
+    assert actual_output.endswith(bash_output)
+
 class FakeMvCommand(craft_cli.BaseCommand):
     """A copycat mv command initialized with a dict."""
 
@@ -26,10 +26,39 @@ from craft_cli.dispatcher import (
     CommandGroup,
     Dispatcher,
     GlobalArgument,
+    get_requested_mode,
 )
 from craft_cli.errors import ArgumentParsingError
 from tests.factory import create_command
 
+# --- Tests for the verbosity requested before parsing
+
+
+@pytest.mark.parametrize(
+    "sysargs, expected",
+    [
+        ([], EmitterMode.BRIEF),
+        (["somecommand"], EmitterMode.BRIEF),
+        (["-q", "somecommand"], EmitterMode.QUIET),
+        (["somecommand", "--verbose"], EmitterMode.VERBOSE),
+        (["--verbosity", "TRACE", "somecommand"], EmitterMode.TRACE),
+        (["somecommand", "--verbosity=debug"], EmitterMode.DEBUG),
+        # left for the Dispatcher to report
+        (["--verbosity=yelling", "somecommand"], EmitterMode.BRIEF),
+        (["-q", "-v", "somecommand"], EmitterMode.BRIEF),
+        (["somecommand", "--verbosity"], EmitterMode.BRIEF),
+    ],
+)
+def test_get_requested_mode(sysargs, expected):
+    """Get the verbosity requested, or the default if it's not clear."""
+    assert get_requested_mode(sysargs) == expected
+
+
+def test_get_requested_mode_default():
+    """The default is given when nothing was requested."""
+    assert get_requested_mode(["somecommand"], EmitterMode.QUIET) == EmitterMode.QUIET
+
+
 # --- Tests for the Dispatcher
 
 
@@ -457,6 +486,51 @@ def test_dispatcher_generic_setup_paramglobal_no_short():
     assert global_args["globalparam"] == "foobar"
 
 
+@pytest.mark.parametrize(
+    ("params", "expected_first_line"),
+    [
+        pytest.param([], "#!/usr/bin/env bash", id="default"),
+        pytest.param(["bash"], "#!/usr/bin/env bash", id="bash"),
+        pytest.param(["zsh"], "#compdef appname", id="zsh"),
+        pytest.param(["fish"], "# Fish completion script for appname", id="fish"),
+    ],
+)
+def test_dispatcher_completion_command(capsys, params, expected_first_line):
+    """The hidden completion command writes the script for the requested shell to stdout."""
+    groups = [CommandGroup("title", [create_command("somecommand")])]
+    dispatcher = Dispatcher("appname", groups)
+
+    dispatcher.pre_parse_args(["completion", *params])
+    dispatcher.load_command(None)
+    dispatcher.run()
+
+    out, err = capsys.readouterr()
+    assert out.splitlines()[0] == expected_first_line
+    assert "somecommand" in out
+    assert err == ""
+
+
+def test_dispatcher_completion_command_bad_shell():
+    """The hidden completion command validates the requested shell."""
+    groups = [CommandGroup("title", [create_command("somecommand")])]
+    dispatcher = Dispatcher("appname", groups)
+
+    with pytest.raises(ArgumentParsingError) as exc_cm:
+        dispatcher.pre_parse_args(["completion", "tcsh"])
+
+    assert "Bad shell 'tcsh'" in str(exc_cm.value)
+
+
+def test_dispatcher_completion_command_overridden():
+    """An application command named 'completion' takes precedence over the hidden one."""
+    cmd = create_command("completion")
+    groups = [CommandGroup("title", [cmd])]
+    dispatcher = Dispatcher("appname", groups)
+
+    dispatcher.pre_parse_args(["completion", "bash"])
+    assert isinstance(dispatcher.load_command(None), cmd)
+
+
 def test_dispatcher_build_commands_ok():
     """Correct command loading."""
     cmd0, cmd1, cmd2 = [create_command(f"cmd-name-{n}", "cmd help") for n in range(3)]
@@ -1017,7 +1017,14 @@ def test_reporterror_with_resolution_final_user_modes(mode, get_initiated_emitte
     full_log_message = f"Full execution log: {repr(emitter._log_filepath)}"
     assert emitter.printer_calls == [
         call().show(sys.stderr, "test message", use_timestamp=False, end_line=True),
-        call().show(sys.stderr, "Recommended resolution: run", use_timestamp=False, end_line=True),
+        call().show(None, "Recommended resolution: run", use_timestamp=False, end_line=True),
+        call().show(
+            sys.stderr,
+            "Recommended resolution: run",
+            use_timestamp=False,
+            end_line=True,
+            avoid_logging=True,
+        ),
         call().show(sys.stderr, full_log_message, use_timestamp=False, end_line=True),
         call().stop(),
     ]
@@ -1033,7 +1040,14 @@ def test_reporterror_with_resolution_developer_modes(mode, get_initiated_emitter
     full_log_message = f"Full execution log: {repr(emitter._log_filepath)}"
     assert emitter.printer_calls == [
         call().show(sys.stderr, "test message", use_timestamp=True, end_line=True),
-        call().show(sys.stderr, "Recommended resolution: run", use_timestamp=True, end_line=True),
+        call().show(None, "Recommended resolution: run", use_timestamp=True, end_line=True),
+        call().show(
+            sys.stderr,
+            "Recommended resolution: run",
+            use_timestamp=True,
+            end_line=True,
+            avoid_logging=True,
+        ),
         call().show(sys.stderr, full_log_message, use_timestamp=True, end_line=True),
         call().stop(),
     ]
@@ -1100,7 +1114,14 @@ def test_reporterror_full_complete(get_initiated_emitter):
         call().show(sys.stderr, "Detailed information: boom", use_timestamp=True, end_line=True),
         call().show(sys.stderr, "traceback line 1", use_timestamp=True, end_line=True),
         call().show(sys.stderr, "traceback line 2", use_timestamp=True, end_line=True),
-        call().show(sys.stderr, "Recommended resolution: run", use_timestamp=True, end_line=True),
+        call().show(None, "Recommended resolution: run", use_timestamp=True, end_line=True),
+        call().show(
+            sys.stderr,
+            "Recommended resolution: run",
+            use_timestamp=True,
+            end_line=True,
+            avoid_logging=True,
+        ),
         call().show(sys.stderr, full_docs_message, use_timestamp=True, end_line=True),
         call().show(sys.stderr, full_log_message, use_timestamp=True, end_line=True),
         call().stop(),
@@ -1127,6 +1148,26 @@ def test_reporterror_double_after_error(get_initiated_emitter):
     assert emitter.printer_calls == []
 
 
+def test_reporterror_suggested_exit_code(get_initiated_emitter):
+    """The retcode of the reported error is kept, and only the first one."""
+    emitter = get_initiated_emitter(EmitterMode.QUIET)
+    assert emitter.suggested_exit_code == 0
+
+    emitter.error(CraftError("test message", retcode=42))
+    assert emitter.suggested_exit_code == 42
+
+    emitter.error(CraftError("another message", retcode=3))
+    assert emitter.suggested_exit_code == 42
+
+
+def test_reporterror_suggested_exit_code_oserror(get_initiated_emitter):
+    """An OSError suggests the default retcode."""
+    emitter = get_initiated_emitter(EmitterMode.QUIET)
+    emitter.error(FileNotFoundError(2, "No such file or directory", "missing.yaml"))
+
+    assert emitter.suggested_exit_code == 1
+
+
 def test_reporterror_no_logpath(get_initiated_emitter):
     """The log path is not reported if indicated."""
     emitter = get_initiated_emitter(EmitterMode.TRACE)
@@ -1245,6 +1286,26 @@ def test_confirm_with_user_defaults_without_tty(get_initiated_emitter, emitter_m
     assert mock_input.mock_calls == []
 
 
+def test_confirm_without_tty_policy_fail(get_initiated_emitter, emitter_mode, mock_input, mock_isatty):
+    mock_isatty.return_value = False
+    emit = get_initiated_emitter(emitter_mode)
+
+    with pytest.raises(CraftError, match="Cannot confirm 'prompt' without a terminal."):
+        emit.confirm("prompt", default=True, non_interactive="fail")
+
+    assert mock_input.mock_calls == []
+
+
+@pytest.mark.parametrize("isatty", [True, False])
+def test_confirm_assume_yes(get_initiated_emitter, emitter_mode, mock_input, mock_isatty, isatty):
+    mock_isatty.return_value = isatty
+    emit = get_initiated_emitter(emitter_mode)
+    emit.set_assume_yes(True)
+
+    assert emit.confirm("prompt", default=False, non_interactive="fail") is True
+    assert mock_input.mock_calls == []
+
+
 @pytest.mark.parametrize(
     ("user_input", "expected"),
     [
@@ -275,6 +275,28 @@ def test_pipereader_invalid_utf8(recording_printer, invalid_text, expected):
     assert msg.text == f":: valid prefix {expected} valid suffix"
 
 
+def test_pipereader_carriage_return(recording_printer):
+    """Lines redrawn with carriage returns are shown as ephemeral."""
+    flags = {"use_timestamp": False, "ephemeral": False, "end_line": True}
+    prt = _PipeReaderThread(recording_printer, sys.stdout, flags)
+    prt.start()
+    os.write(prt.write_pipe, b"\r10%\r50%\r")
+    time.sleep(0.001)
+    os.write(prt.write_pipe, b"\ndone\r\n")
+    prt.stop()
+
+    shown = [
+        (msg.text, msg.ephemeral, msg.end_line)
+        for msg in recording_printer.written_terminal_lines
+    ]
+    assert shown == [
+        (":: 10%", True, False),
+        (":: 50%", True, False),
+        (":: 50%", False, True),  # the final redraw stays once the line ends
+        (":: done", False, True),
+    ]
+
+
 def test_pipereader_chunk_assembler(recording_printer, monkeypatch):
     """Converts ok arbitrary chunks to lines."""
     monkeypatch.setattr(messages, "_PIPE_READER_CHUNK_SIZE", 5)